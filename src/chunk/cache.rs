@@ -0,0 +1,76 @@
+use crate::gc::{Gc, Trace};
+use crate::object::{ObjClass, ObjClosure, ObjInstance, ObjString};
+use crate::value::value::Value;
+
+/// What a `GetProperty` instruction found the last time it read a property from an instance.
+/// The next read from an instance of the same class checks the cached answer before falling
+/// back to the hash lookups, and a read from an instance of another class replaces it.
+#[derive(Clone, Default, PartialEq)]
+pub enum PropertyCache {
+    #[default]
+    Empty,
+    //the field at this position in the instance's fields; instances of one class can add their
+    //fields in different orders, so a hit also checks the key stored there
+    Field(Gc<ObjClass>, usize),
+    //a method of the class; a field of the same name still shadows it
+    Method(Gc<ObjClass>, Gc<ObjClosure>),
+}
+
+/// A property read answered by a `PropertyCache`.
+pub enum CacheHit {
+    Field(Value),
+    Method(Gc<ObjClosure>),
+}
+
+impl PropertyCache {
+    /// Answers a read of `name` from `instance` if the cache still describes where it is found.
+    pub fn lookup(&self, instance: &ObjInstance, name: &Gc<ObjString>) -> Option<CacheHit> {
+        match self {
+            PropertyCache::Field(class, index) if *class == instance.class => {
+                match instance.fields.get_index(*index) {
+                    Some((key, value)) if key == name => Some(CacheHit::Field(value.clone())),
+                    _ => None,
+                }
+            }
+            PropertyCache::Method(class, method)
+                if *class == instance.class && instance.fields.get(name).is_none() =>
+            {
+                Some(CacheHit::Method(method.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+unsafe impl Trace for PropertyCache {
+    fn trace(&self) {
+        match self {
+            PropertyCache::Empty => (),
+            PropertyCache::Field(class, _) => class.trace(),
+            PropertyCache::Method(class, method) => {
+                class.trace();
+                method.trace();
+            }
+        }
+    }
+    fn root(&self) {
+        match self {
+            PropertyCache::Empty => (),
+            PropertyCache::Field(class, _) => class.root(),
+            PropertyCache::Method(class, method) => {
+                class.root();
+                method.root();
+            }
+        }
+    }
+    fn unroot(&self) {
+        match self {
+            PropertyCache::Empty => (),
+            PropertyCache::Field(class, _) => class.unroot(),
+            PropertyCache::Method(class, method) => {
+                class.unroot();
+                method.unroot();
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::verify::instruction_length;
+use super::{Chunk, OpCode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    //the combined constants don't fit in the one-byte operands that refer to them
+    TooManyConstants,
+    //the combined property caches don't fit in `GetProperty`'s two-byte operand
+    TooManyPropertyCaches,
+    InvalidOpcode(usize),
+}
+
+impl Chunk {
+    /// A chunk that runs this one and then `other`. The constants and property caches of `other`
+    /// are appended to ours and the operands referring to them shifted to match; jumps are
+    /// relative, so they need no change, and globals are named by slot, which both chunks share
+    /// as long as they were compiled with the same `GlobalSlots`. If this chunk ends in the
+    /// `nil; return` a script ends with, it is dropped so that execution falls through into
+    /// `other`.
+    pub fn merge(&self, other: &Chunk) -> Result<Chunk, MergeError> {
+        if self.constants.len() + other.constants.len() > u8::MAX as usize + 1 {
+            return Err(MergeError::TooManyConstants);
+        }
+        if self.property_caches.len() + other.property_caches.len() > u16::MAX as usize + 1 {
+            return Err(MergeError::TooManyPropertyCaches);
+        }
+        let mut merged = self.clone();
+        if merged
+            .code
+            .ends_with(&[OpCode::Nil as u8, OpCode::Return as u8])
+        {
+            merged.code.truncate(merged.code.len() - 2);
+            merged.lines.truncate(merged.lines.len() - 2);
+        }
+        let constant_shift = self.constants.len() as u8;
+        let cache_shift = self.property_caches.len() as u16;
+        let start = merged.code.len();
+        merged.code.extend_from_slice(&other.code);
+        merged.lines.extend_from_slice(&other.lines);
+        merged.constants.extend(other.constants.iter().cloned());
+        merged
+            .property_caches
+            .extend(other.property_caches.iter().cloned());
+
+        let mut offset = 0;
+        while offset < other.code.len() {
+            let op = match other.read_operation(offset) {
+                Some(op) => op,
+                None => return Err(MergeError::InvalidOpcode(offset)),
+            };
+            let code = &mut merged.code[start + offset..];
+            match op {
+                OpCode::Constant
+                | OpCode::SetProperty
+                | OpCode::GetSuper
+                | OpCode::Closure
+                | OpCode::Class
+                | OpCode::Method
+                | OpCode::StaticMethod
+                | OpCode::Invoke
+                | OpCode::SuperInvoke => code[1] += constant_shift,
+                OpCode::GetProperty => {
+                    code[1] += constant_shift;
+                    let cache = u16::from_be_bytes([code[2], code[3]]) + cache_shift;
+                    code[2..4].copy_from_slice(&cache.to_be_bytes());
+                }
+                _ => (),
+            }
+            //measured in `other`, where a `Closure` operand still names the right function
+            offset += instruction_length(other, offset, op);
+        }
+        Ok(merged)
+    }
+}
@@ -1,6 +1,13 @@
-use crate::{value::value::Value, gc::Trace};
+use crate::{gc::Trace, global::GlobalSlots, object::ObjFunction, value::value::Value};
 
+pub mod cache;
+pub mod merge;
 pub mod operations;
+pub mod peephole;
+pub mod serialize;
+pub mod stats;
+pub mod verify;
+pub use cache::{CacheHit, PropertyCache};
 pub use operations::OpCode;
 
 #[derive(Clone, PartialEq)]
@@ -8,6 +15,8 @@ pub struct Chunk {
     pub code: Vec<u8>,
     lines: Vec<u32>,
     pub constants: Vec<Value>,
+    //one entry for each `GetProperty` in `code`, which names its entry in its second operand
+    pub property_caches: Vec<PropertyCache>,
 }
 
 impl Chunk {
@@ -16,23 +25,64 @@ impl Chunk {
             code: vec![],
             lines: vec![],
             constants: vec![],
+            property_caches: vec![],
         }
     }
 
+    /// The line of the byte before `ip`, which for a frame's `ip` is part of the instruction that
+    /// just ran.
     pub fn get_line(&self, ip: usize) -> u32 {
         self.lines[ip - 1]
     }
 
+    /// Puts the code from `start` on, which must all be part of the same instruction or run of
+    /// instructions, on `line`.
+    pub fn set_line(&mut self, start: usize, line: u32) {
+        self.lines[start..].fill(line);
+    }
+
     pub fn add_byte(&mut self, byte: u8, line: u32) {
         self.code.push(byte);
         self.lines.push(line);
     }
 
+    pub fn remove_last_byte(&mut self) {
+        self.code.pop();
+        self.lines.pop();
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.code.truncate(len);
+        self.lines.truncate(len);
+    }
+
     pub fn add_constant(&mut self, value: Value) -> usize {
         self.constants.push(value);
         self.constants.len() - 1
     }
 
+    /// Replaces the code from `start` on with the given ranges of it, in the order given; the
+    /// ranges must cover it exactly once. Jumps inside a range keep working as they're relative.
+    pub fn rearrange(&mut self, start: usize, ranges: &[std::ops::Range<usize>]) {
+        let code: Vec<u8> = ranges
+            .iter()
+            .flat_map(|range| self.code[range.clone()].to_vec())
+            .collect();
+        let lines: Vec<u32> = ranges
+            .iter()
+            .flat_map(|range| self.lines[range.clone()].to_vec())
+            .collect();
+        self.code.truncate(start);
+        self.code.extend(code);
+        self.lines.truncate(start);
+        self.lines.extend(lines);
+    }
+
+    pub fn add_property_cache(&mut self) -> usize {
+        self.property_caches.push(PropertyCache::Empty);
+        self.property_caches.len() - 1
+    }
+
     pub fn read_operation(&self, index: usize) -> Option<OpCode> {
         if index >= self.code.len() {
             return None;
@@ -44,10 +94,42 @@ impl Chunk {
         *self.code.get(index).unwrap()
     }
 
-    pub fn disassemble_instruction(&self, index: usize) -> Option<usize> {
+    pub fn disassemble_instruction(&self, globals: &GlobalSlots, index: usize) -> Option<usize> {
+        self.disassemble_instruction_to(globals, &mut std::io::stdout(), index)
+    }
+
+    /// Writes the instruction at `index` and returns where the next one starts. `globals` are
+    /// the slots the chunk was compiled with, for the names of global variables.
+    pub fn disassemble_instruction_to(
+        &self,
+        globals: &GlobalSlots,
+        out: &mut impl std::io::Write,
+        index: usize,
+    ) -> Option<usize> {
+        //walk from the start to find where the instruction before this one begins
+        let mut previous = None;
+        let mut offset = 0;
+        while offset < index {
+            let op = self.read_operation(offset)?;
+            previous = Some(offset);
+            offset += verify::instruction_length(self, offset, op);
+        }
+        let previous_line = previous.map(|previous| self.lines[previous]);
+        self.disassemble_instruction_after(globals, out, index, previous_line)
+    }
+
+    //an instruction's line is the line of its opcode byte, shown as `|` when it's the same as the
+    //previous instruction's. the lines of operand bytes are never looked at
+    fn disassemble_instruction_after(
+        &self,
+        globals: &GlobalSlots,
+        out: &mut impl std::io::Write,
+        index: usize,
+        previous_line: Option<u32>,
+    ) -> Option<usize> {
         let op = self.read_operation(index);
         if op.is_some() {
-            let line = if index != 0 && self.lines[index] == self.lines[index - 1] {
+            let line = if previous_line == Some(self.lines[index]) {
                 "   |".to_string()
             } else {
                 format!("{:4}", self.lines[index])
@@ -55,64 +137,86 @@ impl Chunk {
             let operation = op.unwrap();
             let new_index = match operation {
                 OpCode::Constant
-                | OpCode::GetGlobal
-                | OpCode::DefineGlobal
-                | OpCode::SetGlobal
                 | OpCode::GetLocal
                 | OpCode::SetLocal
                 | OpCode::GetUpvalue
                 | OpCode::SetUpvalue
                 | OpCode::Call
+                | OpCode::TailCall
                 | OpCode::Class
-                | OpCode::GetProperty
                 | OpCode::SetProperty 
                 | OpCode::GetSuper
-                | OpCode::Method => {
+                | OpCode::Method
+                | OpCode::StaticMethod
+                | OpCode::Array
+                | OpCode::PushByte
+                | OpCode::PopN => {
                     let constant = self.code[index + 1];
-                    println!("{:04} {} {:?} {}", index, line, operation, constant);
+                    writeln!(out, "{:04} {} {:?} {}", index, line, operation, constant).ok();
                     index + 2
                 }
-                OpCode::Loop | OpCode::Jump | OpCode::JumpIfFalse => {
+                OpCode::GetGlobal | OpCode::DefineGlobal | OpCode::SetGlobal => {
+                    let slot = ((self.code[index + 1] as u16) << 8) | self.code[index + 2] as u16;
+                    let name = globals.name(slot);
+                    writeln!(out, "{:04} {} {:?} '{}'", index, line, operation, name).ok();
+                    index + 3
+                }
+                OpCode::GetProperty => {
+                    let constant = self.code[index + 1];
+                    let cache = ((self.code[index + 2] as u16) << 8) | self.code[index + 3] as u16;
+                    writeln!(out, "{:04} {} {:?} {} (cache {})", index, line, operation, constant, cache).ok();
+                    index + 4
+                }
+                OpCode::Loop
+                | OpCode::Jump
+                | OpCode::JumpIfFalse
+                | OpCode::JumpIfTrue
+                | OpCode::JumpIfNotNil
+                | OpCode::PopJumpIfFalse
+                | OpCode::PopJumpIfTrue
+                | OpCode::PushHandler => {
                     let offset1 = self.code[index + 1] as u16;
                     let offset2 = self.code[index + 2] as u16;
                     let offset = (offset1 << 8) | offset2;
-                    println!("{:04} {} {:?} {}", index, line, operation, offset);
+                    writeln!(out, "{:04} {} {:?} {}", index, line, operation, offset).ok();
                     index + 3
                 }
                 OpCode::Invoke
                 | OpCode::SuperInvoke => {
                     let constant = self.code[index + 1];
                     let arg_count = self.code[index + 2];
-                    println!("{:04} {} {:?} ({} args) {} {}", index, line, operation, arg_count, constant, self.constants[constant as usize]);
+                    writeln!(out, "{:04} {} {:?} ({} args) {} {}", index, line, operation, arg_count, constant, self.constants[constant as usize]).ok();
                     index + 3
                 }
                 OpCode::Closure => {
                     let mut offset = index + 1;
                     let constant = self.code[offset];
                     offset += 1;
-                    println!(
+                    writeln!(
+                        out,
                         "{:04} {} {:?} {} {}",
                         index, line, operation, constant, self.constants[constant as usize]
-                    );
+                    )
+                    .ok();
                     if let Ok(function) = self.constants[constant as usize].clone().as_function() {
                         for _ in 0..function.borrow().upvalue_count {
-                            
                             let is_local = self.code[offset];
-                            offset += 1;
-                            let index = self.code[offset];
-                            offset += 1;
-                            println!(
+                            let index = self.code[offset + 1];
+                            writeln!(
+                                out,
                                 "{:04}    | {} {}",
                                 offset,
                                 if is_local != 0 { "local" } else { "upvalue" },
                                 index
-                            );
+                            )
+                            .ok();
+                            offset += 2;
                         }
                     }
                     offset
                 }
                 opcode => {
-                    println!("{:04} {} {:?}", index, line, opcode);
+                    writeln!(out, "{:04} {} {:?}", index, line, opcode).ok();
                     index + 1
                 }
             };
@@ -121,10 +225,47 @@ impl Chunk {
         return None;
     }
 
-    pub fn disassemble(&self) {
+    pub fn disassemble(&self, globals: &GlobalSlots) {
+        self.disassemble_to(globals, &mut std::io::stdout());
+    }
+
+    pub fn disassemble_to(&self, globals: &GlobalSlots, out: &mut impl std::io::Write) {
+        let mut previous_line = None;
         let mut index = Some(0);
-        while index.is_some() {
-            index = self.disassemble_instruction(index.unwrap());
+        while let Some(current) = index {
+            index = self.disassemble_instruction_after(globals, out, current, previous_line);
+            previous_line = self.lines.get(current).copied();
+        }
+    }
+}
+
+/// Writes the listing of `function` and then of every function nested in it, found among the
+/// constants of its chunk, each under a `== name ==` header. The script is `<script>` and a
+/// nested function without a name is `<anonymous>`.
+pub fn disassemble_function(
+    function: &ObjFunction,
+    globals: &GlobalSlots,
+    out: &mut impl std::io::Write,
+) {
+    disassemble_nested(function, globals, out, true);
+}
+
+fn disassemble_nested(
+    function: &ObjFunction,
+    globals: &GlobalSlots,
+    out: &mut impl std::io::Write,
+    root: bool,
+) {
+    if function.name.is_none() && !root {
+        writeln!(out, "== <anonymous> ==").ok();
+    } else {
+        writeln!(out, "== {} ==", function).ok();
+    }
+    let chunk = function.chunk.borrow();
+    chunk.disassemble_to(globals, out);
+    for constant in chunk.constants.iter() {
+        if let Ok(nested) = constant.as_function() {
+            disassemble_nested(&nested.borrow(), globals, out, false);
         }
     }
 }
@@ -132,11 +273,14 @@ impl Chunk {
 unsafe impl Trace for Chunk {
     fn trace(&self) {
         self.constants.trace();
+        self.property_caches.trace();
     }
     fn root(&self) {
         self.constants.root();
+        self.property_caches.root();
     }
     fn unroot(&self) {
         self.constants.unroot();
+        self.property_caches.unroot();
     }
 }
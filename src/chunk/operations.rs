@@ -16,18 +16,24 @@ pub enum OpCode {
     GetProperty,
     SetProperty,
     GetSuper,
+    GetIndex,
+    SetIndex,
     Equal,
     Greater,
     Less,
+    IsInstance,
     Add,
     Subtract,
     Multiply,
     Divide,
     Not,
     Negate,
+    UnaryPlus,
     Print,
+    PrintSep,
     Jump,
     JumpIfFalse,
+    JumpIfTrue,
     Loop,
     Call,
     Invoke,
@@ -37,8 +43,142 @@ pub enum OpCode {
     Class,
     Inherit,
     Method,
+    StaticMethod,
+    Array,
+    Uninitialized,
+    Length,
+    TailCall,
+    PushByte,
+    Xor,
+    PopN,
+    Dup,
+    Swap,
+    Over,
+    Nop,
+    PopJumpIfFalse,
+    PopJumpIfTrue,
+    PushHandler,
+    PopHandler,
+    Throw,
+    Iterable,
+    JumpIfNotNil,
+    GreaterEqual,
+    LessEqual,
     Return,
 }
+impl OpCode {
+    /// How many operand bytes follow the opcode. `Closure` is also followed by two bytes for each
+    /// upvalue of its function, which aren't counted here.
+    pub fn operand_bytes(self) -> usize {
+        match self {
+            OpCode::Constant
+            | OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::GetUpvalue
+            | OpCode::SetUpvalue
+            | OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Call
+            | OpCode::TailCall
+            | OpCode::Closure
+            | OpCode::Class
+            | OpCode::Method
+            | OpCode::StaticMethod
+            | OpCode::Array
+            | OpCode::PushByte
+            | OpCode::PopN => 1,
+            OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::PopJumpIfFalse
+            | OpCode::PopJumpIfTrue
+            | OpCode::PushHandler
+            | OpCode::JumpIfNotNil
+            | OpCode::Loop
+            | OpCode::GetGlobal
+            | OpCode::DefineGlobal
+            | OpCode::SetGlobal
+            | OpCode::Invoke
+            | OpCode::SuperInvoke => 2,
+            //the name constant and a two-byte index into the chunk's property caches
+            OpCode::GetProperty => 3,
+            _ => 0,
+        }
+    }
+
+    /// Values pushed minus values popped. `operand_hint` is the argument count for `Call`,
+    /// `TailCall`, `Invoke` and `SuperInvoke`, the element count for `Array` and the slot count for
+    /// `PopN`; other opcodes ignore it.
+    pub fn stack_effect(self, operand_hint: u8) -> i32 {
+        match self {
+            OpCode::Constant
+            | OpCode::Nil
+            | OpCode::True
+            | OpCode::False
+            | OpCode::Uninitialized
+            | OpCode::GetLocal
+            | OpCode::GetGlobal
+            | OpCode::GetUpvalue
+            | OpCode::Closure
+            | OpCode::Class
+            | OpCode::PushByte
+            | OpCode::Dup
+            | OpCode::Over => 1,
+            OpCode::SetLocal
+            | OpCode::SetGlobal
+            | OpCode::SetUpvalue
+            | OpCode::GetProperty
+            | OpCode::GetSuper
+            | OpCode::Not
+            | OpCode::Negate
+            | OpCode::UnaryPlus
+            | OpCode::Length
+            | OpCode::Iterable
+            | OpCode::Swap
+            | OpCode::Nop
+            | OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfNotNil
+            | OpCode::Loop
+            | OpCode::PushHandler
+            | OpCode::PopHandler => 0,
+            OpCode::Pop
+            | OpCode::Throw
+            | OpCode::PopJumpIfFalse
+            | OpCode::PopJumpIfTrue
+            | OpCode::DefineGlobal
+            | OpCode::SetProperty
+            | OpCode::GetIndex
+            | OpCode::Equal
+            | OpCode::Greater
+            | OpCode::Less
+            | OpCode::GreaterEqual
+            | OpCode::LessEqual
+            | OpCode::IsInstance
+            | OpCode::Xor
+            | OpCode::Add
+            | OpCode::Subtract
+            | OpCode::Multiply
+            | OpCode::Divide
+            | OpCode::Print
+            | OpCode::PrintSep
+            | OpCode::CloseUpvalue
+            | OpCode::Method
+            | OpCode::StaticMethod
+            | OpCode::Return => -1,
+            OpCode::SetIndex | OpCode::Inherit => -2,
+            //the callee or receiver and the arguments are replaced by the result
+            //a `TailCall` that reuses the frame never falls through, so it's counted like `Call`
+            OpCode::Call | OpCode::TailCall | OpCode::Invoke | OpCode::SuperInvoke => {
+                -(operand_hint as i32)
+            }
+            OpCode::Array => 1 - operand_hint as i32,
+            OpCode::PopN => -(operand_hint as i32),
+        }
+    }
+}
+
 impl TryInto<OpCode> for u8 {
     type Error = ();
     fn try_into(self) -> Result<OpCode, Self::Error> {
@@ -0,0 +1,170 @@
+use super::verify::instruction_length;
+use super::{Chunk, OpCode};
+
+impl Chunk {
+    /// Removes some redundant code the compiler leaves behind: a jump that lands on a `Jump` goes
+    /// straight to where that one leads, a `Jump` to the next instruction is dropped and a run of
+    /// `Pop`s and `PopN`s becomes a single `PopN`, unless something jumps into the middle of it.
+    /// The bytes that are no longer needed are overwritten with `Nop`s, which are then removed,
+    /// moving every jump and line entry to match. A chunk with an invalid opcode is left alone.
+    pub fn peephole(&mut self) {
+        let starts = match self.instruction_starts() {
+            Some(starts) => starts,
+            None => return,
+        };
+        self.thread_jumps(&starts);
+        self.replace_with_nops(&starts);
+        if let Some(starts) = self.instruction_starts() {
+            self.remove_nops(&starts);
+        }
+    }
+
+    fn instruction_starts(&self) -> Option<Vec<usize>> {
+        let mut starts = vec![];
+        let mut offset = 0;
+        while offset < self.code.len() {
+            let op = self.read_operation(offset)?;
+            starts.push(offset);
+            offset += instruction_length(self, offset, op);
+        }
+        Some(starts)
+    }
+
+    //where the jump at `offset` lands, if it is one
+    fn jump_target(&self, offset: usize) -> Option<usize> {
+        let op = self.read_operation(offset)?;
+        if !matches!(op, OpCode::Loop) && !is_forward_jump(op) {
+            return None;
+        }
+        let operand = ((self.code[offset + 1] as usize) << 8) | self.code[offset + 2] as usize;
+        match op {
+            OpCode::Loop => Some(offset + 3 - operand),
+            _ => Some(offset + 3 + operand),
+        }
+    }
+
+    fn set_jump_operand(&mut self, offset: usize, operand: usize) {
+        self.code[offset + 1] = (operand >> 8) as u8;
+        self.code[offset + 2] = operand as u8;
+    }
+
+    //a `Jump` leaves the stack as it is, so landing on one is the same as landing where it goes.
+    //forward jumps only lead further forward, so following them always ends
+    fn thread_jumps(&mut self, starts: &[usize]) {
+        for &offset in starts {
+            if !self.read_operation(offset).is_some_and(is_forward_jump) {
+                continue;
+            }
+            let mut target = self.jump_target(offset).unwrap();
+            while target < self.code.len()
+                && matches!(self.read_operation(target), Some(OpCode::Jump))
+            {
+                target = self.jump_target(target).unwrap();
+            }
+            let operand = target - (offset + 3);
+            if operand <= u16::MAX as usize {
+                self.set_jump_operand(offset, operand);
+            }
+        }
+    }
+
+    fn replace_with_nops(&mut self, starts: &[usize]) {
+        let targets: std::collections::HashSet<usize> = starts
+            .iter()
+            .filter_map(|offset| self.jump_target(*offset))
+            .collect();
+        let mut index = 0;
+        while index < starts.len() {
+            let offset = starts[index];
+            match self.read_operation(offset) {
+                Some(OpCode::Jump) if self.jump_target(offset) == Some(offset + 3) => {
+                    self.code[offset..offset + 3].fill(OpCode::Nop as u8);
+                }
+                Some(OpCode::Pop | OpCode::PopN) => {
+                    //the run ends at anything else, at a jump target or when the count would
+                    //no longer fit in a byte
+                    let mut count = self.pop_count(offset);
+                    let mut end = index + 1;
+                    while end < starts.len()
+                        && !targets.contains(&starts[end])
+                        && matches!(
+                            self.read_operation(starts[end]),
+                            Some(OpCode::Pop | OpCode::PopN)
+                        )
+                        && count + self.pop_count(starts[end]) <= u8::MAX as usize
+                    {
+                        count += self.pop_count(starts[end]);
+                        end += 1;
+                    }
+                    if end > index + 1 {
+                        let run_end = starts.get(end).copied().unwrap_or(self.code.len());
+                        self.code[offset] = OpCode::PopN as u8;
+                        self.code[offset + 1] = count as u8;
+                        self.code[offset + 2..run_end].fill(OpCode::Nop as u8);
+                        index = end;
+                        continue;
+                    }
+                }
+                _ => (),
+            }
+            index += 1;
+        }
+    }
+
+    fn pop_count(&self, offset: usize) -> usize {
+        match self.read_operation(offset) {
+            Some(OpCode::PopN) => self.code[offset + 1] as usize,
+            _ => 1,
+        }
+    }
+
+    //a jump to a `Nop` lands on the next instruction that's kept, which does the same
+    fn remove_nops(&mut self, starts: &[usize]) {
+        //a `Nop` is a single byte, so it's enough to look at where instructions start
+        let mut is_nop = vec![false; self.code.len()];
+        for &offset in starts {
+            is_nop[offset] = matches!(self.read_operation(offset), Some(OpCode::Nop));
+        }
+        let removed_before: Vec<usize> = std::iter::once(0)
+            .chain(is_nop.iter().scan(0, |removed, nop| {
+                *removed += *nop as usize;
+                Some(*removed)
+            }))
+            .collect();
+        if removed_before[self.code.len()] == 0 {
+            return;
+        }
+        let new_offset = |offset: usize| offset - removed_before[offset];
+        for &offset in starts {
+            let target = match self.jump_target(offset) {
+                Some(target) => target,
+                None => continue,
+            };
+            let (from, to) = (new_offset(offset) + 3, new_offset(target));
+            let operand = if matches!(self.read_operation(offset), Some(OpCode::Loop)) {
+                from - to
+            } else {
+                to - from
+            };
+            self.set_jump_operand(offset, operand);
+        }
+        let kept: Vec<usize> = (0..self.code.len())
+            .filter(|offset| !is_nop[*offset])
+            .collect();
+        self.code = kept.iter().map(|offset| self.code[*offset]).collect();
+        self.lines = kept.iter().map(|offset| self.lines[*offset]).collect();
+    }
+}
+
+fn is_forward_jump(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfNotNil
+            | OpCode::PopJumpIfFalse
+            | OpCode::PopJumpIfTrue
+            | OpCode::PushHandler
+    )
+}
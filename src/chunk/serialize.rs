@@ -0,0 +1,364 @@
+use super::verify::{verify_function, StackError};
+use super::{Chunk, OpCode, PropertyCache};
+use crate::gc::Gc;
+use crate::global::GlobalSlots;
+use crate::object::{ObjFunction, ObjString};
+use crate::value::value::Value;
+use crate::value::ValueType;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SerializeError {
+    //only nil, booleans, numbers, strings and functions can be written as constants
+    UnsupportedConstant,
+    //the input ended in the middle of a chunk
+    Truncated,
+    InvalidTag(u8),
+    InvalidString,
+    //the input doesn't start with `MAGIC`
+    BadMagic,
+    //the input was written by a build with this other format version
+    VersionMismatch(u32),
+    InvalidOpcode(usize),
+    //the instruction at this offset refers to a constant that doesn't exist or has the wrong type
+    InvalidConstant(usize),
+    //the instruction at this offset refers to a global slot the file doesn't name
+    InvalidGlobal(usize),
+    //the code would underflow the stack, jump somewhere it can't or read a local or upvalue
+    //that isn't there
+    InvalidCode(StackError),
+    //the script itself has upvalues, which nothing could have given it
+    ScriptUpvalues,
+}
+
+impl std::fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializeError::UnsupportedConstant => f.write_str("unsupported constant"),
+            SerializeError::Truncated => f.write_str("unexpected end of input"),
+            SerializeError::InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+            SerializeError::InvalidString => f.write_str("invalid UTF-8 in string"),
+            SerializeError::BadMagic => f.write_str("not a bytecode file"),
+            SerializeError::VersionMismatch(version) => write!(
+                f,
+                "format version {} is not supported (expected {})",
+                version, FORMAT_VERSION
+            ),
+            SerializeError::InvalidOpcode(offset) => write!(f, "invalid opcode at {}", offset),
+            SerializeError::InvalidConstant(offset) => {
+                write!(f, "invalid constant operand at {}", offset)
+            }
+            SerializeError::InvalidGlobal(offset) => {
+                write!(f, "invalid global operand at {}", offset)
+            }
+            SerializeError::InvalidCode(error) => error.fmt(f),
+            SerializeError::ScriptUpvalues => f.write_str("the script can't have upvalues"),
+        }
+    }
+}
+
+/// Every serialized script starts with these bytes, followed by `FORMAT_VERSION`.
+pub const MAGIC: &[u8; 4] = b"LOXC";
+/// Bump this whenever the encoding or the meaning of an opcode changes.
+pub const FORMAT_VERSION: u32 = 21;
+
+const TAG_NIL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NUMBER: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_FUNCTION: u8 = 5;
+//only written by builds with integers, and only they can read it back
+#[cfg(feature = "integers")]
+const TAG_INT: u8 = 6;
+
+impl Chunk {
+    /// Writes the chunk's code, lines and constants. Numbers are written as their raw bits so
+    /// `-0`, NaN and infinities come back exactly.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializeError> {
+        let mut bytes = vec![];
+        write_chunk(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Reads a chunk written by `to_bytes`. Global slots are taken as they are, so this only
+    /// makes sense with the `GlobalSlots` the chunk was compiled with.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chunk, SerializeError> {
+        Reader {
+            bytes,
+            position: 0,
+            globals: None,
+        }
+        .read_chunk()
+    }
+}
+
+/// Writes a compiled script, along with every function nested in its constants, behind a
+/// versioned header. Global slots are only meaningful with the `globals` the script was compiled
+/// with, so the names of every slot are written too and reassigned when the script is loaded.
+pub fn serialize_function(
+    function: &ObjFunction,
+    globals: &GlobalSlots,
+) -> Result<Vec<u8>, SerializeError> {
+    let mut bytes = MAGIC.to_vec();
+    write_u32(&mut bytes, FORMAT_VERSION);
+    let names = globals.names();
+    write_u32(&mut bytes, names.len() as u32);
+    for name in names.iter() {
+        write_string(&mut bytes, name);
+    }
+    write_function(&mut bytes, function)?;
+    Ok(bytes)
+}
+
+/// Reads a script written by `serialize_function`, giving its globals slots in `globals`.
+pub fn deserialize_function(
+    bytes: &[u8],
+    globals: &mut GlobalSlots,
+) -> Result<Gc<ObjFunction>, SerializeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(SerializeError::BadMagic);
+    }
+    let mut reader = Reader {
+        bytes,
+        position: MAGIC.len(),
+        globals: None,
+    };
+    let version = reader.read_u32()?;
+    if version != FORMAT_VERSION {
+        return Err(SerializeError::VersionMismatch(version));
+    }
+    let count = reader.read_u32()?;
+    let mut slots = vec![];
+    for _ in 0..count {
+        let name = reader.read_string()?;
+        let slot = globals.slot(name.borrow().as_str()).ok_or(SerializeError::InvalidString)?;
+        slots.push(slot);
+    }
+    reader.globals = Some(slots);
+    let function = reader.read_function()?;
+    if function.borrow().upvalue_count != 0 {
+        return Err(SerializeError::ScriptUpvalues);
+    }
+    verify_function(&function.borrow()).map_err(SerializeError::InvalidCode)?;
+    Ok(function)
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    write_u32(bytes, string.len() as u32);
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+fn write_chunk(bytes: &mut Vec<u8>, chunk: &Chunk) -> Result<(), SerializeError> {
+    write_u32(bytes, chunk.code.len() as u32);
+    bytes.extend_from_slice(&chunk.code);
+    for line in chunk.lines.iter() {
+        write_u32(bytes, *line);
+    }
+    write_u32(bytes, chunk.constants.len() as u32);
+    for constant in chunk.constants.iter() {
+        write_constant(bytes, constant)?;
+    }
+    Ok(())
+}
+
+fn write_function(bytes: &mut Vec<u8>, function: &ObjFunction) -> Result<(), SerializeError> {
+    match &function.name {
+        None => bytes.push(0),
+        Some(name) => {
+            bytes.push(1);
+            write_string(bytes, name.borrow().as_str());
+        }
+    }
+    write_u32(bytes, function.arity as u32);
+    write_u32(bytes, function.upvalue_count as u32);
+    write_u32(bytes, function.local_count as u32);
+    bytes.push(function.is_getter as u8);
+    write_chunk(bytes, &function.chunk.borrow())
+}
+
+fn write_constant(bytes: &mut Vec<u8>, constant: &Value) -> Result<(), SerializeError> {
+    match constant.value_type() {
+        ValueType::Nil if constant.is_nil() => bytes.push(TAG_NIL),
+        ValueType::Bool if constant.as_bool().unwrap() => bytes.push(TAG_TRUE),
+        ValueType::Bool => bytes.push(TAG_FALSE),
+        #[cfg(feature = "integers")]
+        ValueType::Number if constant.as_int().is_ok() => {
+            bytes.push(TAG_INT);
+            bytes.extend_from_slice(&constant.as_int().unwrap().to_le_bytes());
+        }
+        ValueType::Number => {
+            bytes.push(TAG_NUMBER);
+            bytes.extend_from_slice(&constant.as_number().unwrap().to_bits().to_le_bytes());
+        }
+        ValueType::String => {
+            bytes.push(TAG_STRING);
+            write_string(bytes, constant.as_string().unwrap().borrow().as_str());
+        }
+        ValueType::Function => {
+            bytes.push(TAG_FUNCTION);
+            write_function(bytes, &constant.as_function().unwrap().borrow())?;
+        }
+        _ => return Err(SerializeError::UnsupportedConstant),
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    //maps the writer's global slots to the reader's
+    globals: Option<Vec<u16>>,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], SerializeError> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(SerializeError::Truncated);
+        }
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, SerializeError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, SerializeError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, SerializeError> {
+        let bytes = self.read_bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self) -> Result<Gc<ObjString>, SerializeError> {
+        let length = self.read_u32()? as usize;
+        let string = std::str::from_utf8(self.read_bytes(length)?)
+            .map_err(|_| SerializeError::InvalidString)?;
+        Ok(ObjString::new(string.to_string()))
+    }
+
+    fn read_chunk(&mut self) -> Result<Chunk, SerializeError> {
+        let mut chunk = Chunk::new();
+        let code_length = self.read_u32()? as usize;
+        chunk.code = self.read_bytes(code_length)?.to_vec();
+        for _ in 0..code_length {
+            chunk.lines.push(self.read_u32()?);
+        }
+        let constant_count = self.read_u32()?;
+        for _ in 0..constant_count {
+            let constant = self.read_constant()?;
+            chunk.constants.push(constant);
+        }
+        validate(&mut chunk, self.globals.as_deref())?;
+        Ok(chunk)
+    }
+
+    fn read_function(&mut self) -> Result<Gc<ObjFunction>, SerializeError> {
+        let name = match self.read_u8()? {
+            0 => None,
+            1 => Some(self.read_string()?),
+            tag => return Err(SerializeError::InvalidTag(tag)),
+        };
+        let function = ObjFunction::new(name);
+        let arity = self.read_u32()? as usize;
+        let upvalue_count = self.read_u32()? as usize;
+        let local_count = self.read_u32()? as usize;
+        let is_getter = self.read_u8()? != 0;
+        let chunk = self.read_chunk()?;
+        {
+            let mut function = function.borrow_mut();
+            function.arity = arity;
+            function.upvalue_count = upvalue_count;
+            function.local_count = local_count;
+            function.is_getter = is_getter;
+            function.chunk = Gc::new(chunk);
+        }
+        Ok(function)
+    }
+
+    fn read_constant(&mut self) -> Result<Value, SerializeError> {
+        match self.read_u8()? {
+            TAG_NIL => Ok(Value::nil()),
+            TAG_FALSE => Ok(Value::bool_(false)),
+            TAG_TRUE => Ok(Value::bool_(true)),
+            TAG_NUMBER => Ok(Value::number(f64::from_bits(self.read_u64()?))),
+            #[cfg(feature = "integers")]
+            TAG_INT => Ok(Value::int(self.read_u64()? as i64)),
+            TAG_STRING => Ok(Value::string(self.read_string()?)),
+            TAG_FUNCTION => Ok(Value::function(self.read_function()?)),
+            tag => Err(SerializeError::InvalidTag(tag)),
+        }
+    }
+}
+
+//checks that every opcode is real and every constant operand points at a constant of the
+//right kind, moves global operands over to the reader's slots and makes an empty property
+//cache for every `GetProperty`. `deserialize_function` verifies the rest of the code once every
+//function is read, so a corrupt file fails there instead of inside the VM.
+fn validate(chunk: &mut Chunk, globals: Option<&[u16]>) -> Result<(), SerializeError> {
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let op = match chunk.read_operation(offset) {
+            Some(op) => op,
+            None => return Err(SerializeError::InvalidOpcode(offset)),
+        };
+        let mut next = offset + 1 + op.operand_bytes();
+        if next > chunk.code.len() {
+            return Err(SerializeError::Truncated);
+        }
+        let constant = || chunk.constants.get(chunk.code[offset + 1] as usize);
+        match op {
+            OpCode::Constant if constant().is_none() => {
+                return Err(SerializeError::InvalidConstant(offset));
+            }
+            OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Class
+            | OpCode::Method
+            | OpCode::StaticMethod
+            | OpCode::Invoke
+            | OpCode::SuperInvoke
+                if !constant().is_some_and(|constant| constant.is_string()) =>
+            {
+                return Err(SerializeError::InvalidConstant(offset));
+            }
+            OpCode::GetProperty => {
+                if !constant().is_some_and(|constant| constant.is_string()) {
+                    return Err(SerializeError::InvalidConstant(offset));
+                }
+                let cache = ((chunk.code[offset + 2] as usize) << 8) | chunk.code[offset + 3] as usize;
+                if cache >= chunk.property_caches.len() {
+                    chunk.property_caches.resize(cache + 1, PropertyCache::Empty);
+                }
+            }
+            OpCode::Closure => match constant().map(|constant| constant.as_function()) {
+                Some(Ok(function)) => next += 2 * function.borrow().upvalue_count,
+                _ => return Err(SerializeError::InvalidConstant(offset)),
+            },
+            OpCode::GetGlobal | OpCode::DefineGlobal | OpCode::SetGlobal => {
+                if let Some(globals) = globals {
+                    let slot = ((chunk.code[offset + 1] as usize) << 8) | chunk.code[offset + 2] as usize;
+                    let slot = *globals
+                        .get(slot)
+                        .ok_or(SerializeError::InvalidGlobal(offset))?;
+                    chunk.code[offset + 1..offset + 3].copy_from_slice(&slot.to_be_bytes());
+                }
+            }
+            _ => (),
+        }
+        offset = next;
+    }
+    if offset > chunk.code.len() {
+        return Err(SerializeError::Truncated);
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use super::verify::{instruction_length, max_stack_depth, StackError};
+use crate::object::ObjFunction;
+
+/// Size figures for one compiled function, as printed by `--bytecode-stats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionStats {
+    pub name: String,
+    pub instructions: usize,
+    pub constants: usize,
+    //the deepest the function's own stack window gets, counting the callee slot and locals
+    pub max_stack: usize,
+    pub locals: usize,
+    pub upvalues: usize,
+}
+
+/// Stats for `function`, followed by those of every function nested in its constants in the
+/// order they were declared.
+pub fn function_stats(function: &ObjFunction) -> Result<Vec<FunctionStats>, StackError> {
+    let mut stats = vec![];
+    collect_stats(function, &mut stats)?;
+    Ok(stats)
+}
+
+fn collect_stats(function: &ObjFunction, stats: &mut Vec<FunctionStats>) -> Result<(), StackError> {
+    let chunk = function.chunk.borrow();
+    let mut instructions = 0;
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        let op = chunk
+            .read_operation(offset)
+            .ok_or(StackError::InvalidOpcode(offset))?;
+        instructions += 1;
+        offset += instruction_length(&chunk, offset, op);
+    }
+    stats.push(FunctionStats {
+        name: function.to_string(),
+        instructions,
+        constants: chunk.constants.len(),
+        max_stack: max_stack_depth(&chunk, function.arity as i32 + 1)? as usize,
+        locals: function.local_count,
+        upvalues: function.upvalue_count,
+    });
+    for constant in chunk.constants.iter() {
+        if let Ok(nested) = constant.as_function() {
+            collect_stats(&nested.borrow(), stats)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_stats(stats: &[FunctionStats], out: &mut impl std::io::Write) {
+    writeln!(
+        out,
+        "{:<16}{:>13}{:>11}{:>11}{:>8}{:>10}",
+        "function", "instructions", "constants", "max stack", "locals", "upvalues"
+    )
+    .ok();
+    for function in stats {
+        writeln!(
+            out,
+            "{:<16}{:>13}{:>11}{:>11}{:>8}{:>10}",
+            function.name,
+            function.instructions,
+            function.constants,
+            function.max_stack,
+            function.locals,
+            function.upvalues
+        )
+        .ok();
+    }
+}
@@ -0,0 +1,166 @@
+use super::{Chunk, OpCode};
+use crate::object::ObjFunction;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackError {
+    //the instruction at this offset pops more values than are on the stack
+    Underflow(usize),
+    //two paths reach this offset with different stack depths
+    Mismatch(usize),
+    InvalidOpcode(usize),
+    //the jump at this offset, or the instruction falling through from it, goes outside the code
+    //or into the middle of an instruction
+    InvalidJump(usize),
+    //the instruction at this offset names a local above the top of the stack or an upvalue the
+    //function doesn't have
+    InvalidOperand(usize),
+}
+
+impl std::fmt::Display for StackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StackError::Underflow(offset) => write!(f, "stack underflow at {}", offset),
+            StackError::Mismatch(offset) => write!(f, "mismatched stack depths at {}", offset),
+            StackError::InvalidOpcode(offset) => write!(f, "invalid opcode at {}", offset),
+            StackError::InvalidJump(offset) => write!(f, "invalid jump at {}", offset),
+            StackError::InvalidOperand(offset) => {
+                write!(f, "invalid local or upvalue operand at {}", offset)
+            }
+        }
+    }
+}
+
+/// Statically walks every path through `function` and its nested functions, tracking how many
+/// values each instruction leaves on the stack, to catch codegen that leaves the stack unbalanced
+/// and bytecode that reads past the stack or the function's upvalues.
+pub fn verify_function(function: &ObjFunction) -> Result<(), StackError> {
+    let chunk = function.chunk.borrow();
+    //the callee and its arguments are already on the stack when the body starts
+    walk(&chunk, function.arity as i32 + 1, Some(function.upvalue_count))?;
+    for constant in chunk.constants.iter() {
+        if let Ok(nested) = constant.as_function() {
+            verify_function(&nested.borrow())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub fn verify_chunk(chunk: &Chunk, initial_depth: i32) -> Result<(), StackError> {
+    max_stack_depth(chunk, initial_depth).map(|_| ())
+}
+
+/// The deepest the stack gets on any path through `chunk`, counting the `initial_depth` values
+/// already on it.
+pub fn max_stack_depth(chunk: &Chunk, initial_depth: i32) -> Result<i32, StackError> {
+    walk(chunk, initial_depth, None)
+}
+
+//upvalue operands are only checked when `upvalue_count` is known
+fn walk(chunk: &Chunk, initial_depth: i32, upvalue_count: Option<usize>) -> Result<i32, StackError> {
+    //where each instruction starts, so a jump can't land inside one
+    let mut starts = vec![false; chunk.code.len()];
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        starts[offset] = true;
+        let op = chunk
+            .read_operation(offset)
+            .ok_or(StackError::InvalidOpcode(offset))?;
+        offset += instruction_length(chunk, offset, op);
+    }
+    let target = |from: usize, to: Option<usize>| match to {
+        Some(to) if starts.get(to) == Some(&true) => Ok(to),
+        _ => Err(StackError::InvalidJump(from)),
+    };
+    let upvalue = |offset: usize, index: u8| match upvalue_count {
+        Some(count) if index as usize >= count => Err(StackError::InvalidOperand(offset)),
+        _ => Ok(()),
+    };
+    let mut max_depth = initial_depth;
+    let mut depths: Vec<Option<i32>> = vec![None; chunk.code.len()];
+    let mut pending = vec![(target(0, Some(0))?, initial_depth)];
+    while let Some((offset, depth)) = pending.pop() {
+        match depths[offset] {
+            Some(seen) if seen == depth => continue,
+            Some(_) => return Err(StackError::Mismatch(offset)),
+            None => depths[offset] = Some(depth),
+        }
+        let op = match chunk.read_operation(offset) {
+            Some(op) => op,
+            None => return Err(StackError::InvalidOpcode(offset)),
+        };
+        //slot 0 is the callee, so a local's slot has to be below the depth before the instruction
+        match op {
+            OpCode::GetLocal | OpCode::SetLocal if chunk.code[offset + 1] as i32 >= depth => {
+                return Err(StackError::InvalidOperand(offset));
+            }
+            OpCode::GetUpvalue | OpCode::SetUpvalue => upvalue(offset, chunk.code[offset + 1])?,
+            OpCode::Closure => {
+                let operands = &chunk.code[offset + 2..offset + instruction_length(chunk, offset, op)];
+                for operand in operands.chunks_exact(2) {
+                    match operand[0] {
+                        0 => upvalue(offset, operand[1])?,
+                        //the closure is pushed before it captures, so it can capture itself
+                        _ if operand[1] as i32 > depth => {
+                            return Err(StackError::InvalidOperand(offset));
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+        let depth = depth + op.stack_effect(operand_hint(chunk, offset, op));
+        if depth < 0 {
+            return Err(StackError::Underflow(offset));
+        }
+        max_depth = max_depth.max(depth);
+        let next = offset + instruction_length(chunk, offset, op);
+        let jump = || jump_operand(chunk, offset);
+        match op {
+            OpCode::Return | OpCode::Throw => (),
+            OpCode::Jump => pending.push((target(offset, next.checked_add(jump()))?, depth)),
+            OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfNotNil
+            | OpCode::PopJumpIfFalse
+            | OpCode::PopJumpIfTrue => {
+                pending.push((target(offset, Some(next))?, depth));
+                pending.push((target(offset, next.checked_add(jump()))?, depth));
+            }
+            //the catch starts with the error message pushed where the stack was cut back to,
+            //which is where it is at the `PushHandler`
+            OpCode::PushHandler => {
+                pending.push((target(offset, Some(next))?, depth));
+                pending.push((target(offset, next.checked_add(jump()))?, depth + 1));
+            }
+            OpCode::Loop => pending.push((target(offset, next.checked_sub(jump()))?, depth)),
+            _ => pending.push((target(offset, Some(next))?, depth)),
+        }
+    }
+    Ok(max_depth)
+}
+
+fn jump_operand(chunk: &Chunk, offset: usize) -> usize {
+    ((chunk.code[offset + 1] as usize) << 8) | chunk.code[offset + 2] as usize
+}
+
+pub(super) fn instruction_length(chunk: &Chunk, offset: usize, op: OpCode) -> usize {
+    let mut length = 1 + op.operand_bytes();
+    if let OpCode::Closure = op {
+        //each captured variable adds an is_local byte and an index byte
+        let constant = chunk.code[offset + 1] as usize;
+        if let Ok(function) = chunk.constants[constant].as_function() {
+            length += 2 * function.borrow().upvalue_count;
+        }
+    }
+    length
+}
+
+fn operand_hint(chunk: &Chunk, offset: usize, op: OpCode) -> u8 {
+    match op {
+        OpCode::Call | OpCode::TailCall | OpCode::Array | OpCode::PopN => chunk.code[offset + 1],
+        OpCode::Invoke | OpCode::SuperInvoke => chunk.code[offset + 2],
+        _ => 0,
+    }
+}
@@ -1,18 +1,27 @@
 use crate::{
     chunk::{operations::OpCode, Chunk},
     gc::Gc,
+    diagnostic::CompileErrorKind,
+    global::GlobalSlots,
     object::{ObjFunction, ObjString},
-    scanner::{Scanner, Token, TokenKind},
-    value::value::copy_string,
+    scanner::{string_value, Scanner, Token, TokenKind},
+    value::value::{concatenate_strings, copy_string},
     value::value::Value,
+    value::ValueType,
     vm::InterpretError,
 };
+#[cfg(feature = "integers")]
+use crate::value::integer_arithmetic;
+use std::cmp::Ordering;
+use std::io::Write;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Precedence {
     None,
     Assignment, // =
+    Coalesce,   // ??
     Or,         // or
+    Xor,        // xor
     And,        // and
     Equality,   // == !=
     Comparison, // < > <= >=
@@ -27,8 +36,10 @@ impl Precedence {
     fn next(&self) -> Self {
         match self {
             Self::None => Self::Assignment,
-            Self::Assignment => Self::Or,
-            Self::Or => Self::And,
+            Self::Assignment => Self::Coalesce,
+            Self::Coalesce => Self::Or,
+            Self::Or => Self::Xor,
+            Self::Xor => Self::And,
             Self::And => Self::Equality,
             Self::Equality => Self::Comparison,
             Self::Comparison => Self::Term,
@@ -65,7 +76,7 @@ fn get_rule<'a, 'b, ErrOut:std::io::Write>(kind: TokenKind) -> ParseRule<'a, 'b,
             precedence: Precedence::Term,
         },
         TokenKind::Plus => ParseRule {
-            prefix: None,
+            prefix: Some(&Parser::unary),
             infix: Some(&Parser::binary),
             precedence: Precedence::Term,
         },
@@ -104,6 +115,11 @@ fn get_rule<'a, 'b, ErrOut:std::io::Write>(kind: TokenKind) -> ParseRule<'a, 'b,
             infix: Some(&Parser::binary),
             precedence: Precedence::Comparison,
         },
+        TokenKind::Is => ParseRule {
+            prefix: None,
+            infix: Some(&Parser::binary),
+            precedence: Precedence::Comparison,
+        },
         TokenKind::LessEqual => ParseRule {
             prefix: None,
             infix: Some(&Parser::binary),
@@ -124,6 +140,16 @@ fn get_rule<'a, 'b, ErrOut:std::io::Write>(kind: TokenKind) -> ParseRule<'a, 'b,
             infix: Some(&Parser::or),
             precedence: Precedence::Or,
         },
+        TokenKind::QuestionQuestion => ParseRule {
+            prefix: None,
+            infix: Some(&Parser::coalesce),
+            precedence: Precedence::Coalesce,
+        },
+        TokenKind::Xor => ParseRule {
+            prefix: None,
+            infix: Some(&Parser::binary),
+            precedence: Precedence::Xor,
+        },
         TokenKind::Super => ParseRule {
             prefix: Some(&Parser::super_),
             infix: None,
@@ -154,6 +180,17 @@ fn get_rule<'a, 'b, ErrOut:std::io::Write>(kind: TokenKind) -> ParseRule<'a, 'b,
             infix: Some(&Parser::dot),
             precedence: Precedence::Call,
         },
+        //a variable or field consumes a postfix `++` itself, so the infix rule only sees bad targets
+        TokenKind::PlusPlus | TokenKind::MinusMinus => ParseRule {
+            prefix: Some(&Parser::prefix_increment),
+            infix: Some(&Parser::invalid_increment),
+            precedence: Precedence::Call,
+        },
+        TokenKind::LeftBracket => ParseRule {
+            prefix: Some(&Parser::array),
+            infix: Some(&Parser::index),
+            precedence: Precedence::Call,
+        },
         _ => ParseRule {
             prefix: None,
             infix: None,
@@ -162,13 +199,20 @@ fn get_rule<'a, 'b, ErrOut:std::io::Write>(kind: TokenKind) -> ParseRule<'a, 'b,
     }
 }
 
-fn error(token: Token, message: &str, had_error: &mut bool, panic_mode: &mut bool, err: &mut impl std::io::Write) {
+fn error(
+    token: Token,
+    kind: CompileErrorKind,
+    message: &str,
+    had_error: &mut bool,
+    panic_mode: &mut bool,
+    err: &mut ErrorOutput<impl std::io::Write>,
+) {
     if *panic_mode {
         return;
     }
     *panic_mode = true;
     *had_error = true;
-    write!(err, "[line {}] Error", token.line()).ok();
+    write!(err, "[line {}] Error[{}]", token.line(), kind.code()).ok();
     match token.kind() {
         TokenKind::Error => (),
         TokenKind::EOF => {
@@ -180,12 +224,94 @@ fn error(token: Token, message: &str, had_error: &mut bool, panic_mode: &mut boo
     }
 
     writeln!(err, ": {}", message).ok();
+    err.snippet(token);
+}
+fn warning(token: Token, message: &str, err: &mut ErrorOutput<impl std::io::Write>) {
+    writeln!(err, "[line {}] Warning at '{}': {}", token.line(), token.as_str(), message).ok();
+    err.snippet(token);
+}
+
+/// Where compile errors and warnings are written. With `source` set, each one is followed by
+/// the line it's on and carets under the token it's about, the way rustc shows them.
+struct ErrorOutput<'a, W: std::io::Write> {
+    out: &'a mut W,
+    source: Option<&'a str>,
+}
+
+impl<'a, W: std::io::Write> ErrorOutput<'a, W> {
+    fn snippet(&mut self, token: Token) {
+        let source = match self.source {
+            Some(source) => source,
+            None => return,
+        };
+        //error tokens hold a message rather than pointing into the source
+        let start = match (token.as_str().as_ptr() as usize).checked_sub(source.as_ptr() as usize) {
+            Some(start) if start <= source.len() && token.kind() != TokenKind::Error => start,
+            _ => return,
+        };
+        let (start, end) = match token.kind() {
+            //just past the last thing in the file, rather than on an empty last line
+            TokenKind::EOF => (source.trim_end().len(), source.trim_end().len()),
+            _ => (start, start + token.as_str().len()),
+        };
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |newline| start + newline);
+        //a string spanning several lines is only underlined on its first
+        let length = source[start..end.min(line_end)].chars().count().max(1);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        //tabs are kept so the carets line up however wide the terminal shows them
+        let indent: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(self.out, "{}", line).ok();
+        writeln!(self.out, "{}{}", indent, "^".repeat(length)).ok();
+    }
 }
+
+impl<'a, W: std::io::Write> std::io::Write for ErrorOutput<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct CompilerOptions {
+    //`var a;` leaves `a` uninitialized instead of nil, and functions that return a value
+    //somewhere but can reach the end of their body get a warning
+    pub strict: bool,
+    //a top-level expression statement with no `;` before the end of the input prints its value,
+    //so `1 + 2` at the prompt shows 3; anything ending in `;` is compiled as usual
+    pub repl: bool,
+    //accepts a few things other languages allow that Lox doesn't, like a unary `+` and
+    //`print` with several values
+    pub relaxed: bool,
+    //compile errors and warnings also show the source line with carets under the token
+    pub snippets: bool,
+    //warns about local variables that are declared but never used
+    pub warn_unused: bool,
+    //warns about the first statement in a block that comes after one that always returns
+    pub warn_unreachable: bool,
+    //each function's code goes through `Chunk::peephole` once it's compiled
+    pub optimize: bool,
+    //a statement's `;` can be left out when the next token is on a later line, is a `}` or is
+    //the end of the input. expressions are parsed as far as they go first, so a line ending in
+    //an operator or inside brackets carries on, but `return` at the end of a line returns nil
+    pub infer_semicolons: bool,
+}
+
 #[derive(Clone, Copy)]
 struct Local<'a> {
     name: &'a str,
     depth: Option<i32>,
     is_captured: bool,
+    //whether anything refers to it after its declaration
+    used: bool,
+    line: u32,
 }
 
 impl<'a> Local<'a> {
@@ -194,15 +320,30 @@ impl<'a> Local<'a> {
             name,
             depth,
             is_captured: false,
+            used: false,
+            line: 0,
         }
     }
 }
+//a name declared in source, with the parameter names if it's a function declared with `fun`
+//that named arguments can be matched against. entries are dropped with the scope they were
+//declared in, so the last one with a given name is the declaration a use of it refers to
+struct KnownName<'a> {
+    name: &'a str,
+    parameters: Option<Vec<&'a str>>,
+    function_depth: usize,
+    scope_depth: i32,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum FunctionType {
     Function,
+    Getter,
     Initializer,
     Method,
     Script,
+    StaticGetter,
+    StaticMethod,
 }
 #[derive(Clone, Copy)]
 struct Upvalue {
@@ -216,8 +357,18 @@ pub struct Compiler<'a> {
     function_type: FunctionType,
     locals: [Local<'a>; 256],
     local_count: usize,
+    max_local_count: usize,
     upvalues: [Upvalue; 256],
     scope_depth: i32,
+    returns_value: bool,
+    //offset of the last `Call` emitted, so `return f();` can turn it into a `TailCall`
+    last_call: Option<usize>,
+    //where the last `Not` ending a `!`, `!=`, `<=` or `>=` is, and where the last forward jump
+    //lands, for `emit_condition_jump`
+    last_not: Option<usize>,
+    last_jump_target: Option<usize>,
+    //how many `try` blocks the code being compiled is inside of
+    try_depth: usize,
 }
 
 impl<'a> Compiler<'a> {
@@ -235,11 +386,17 @@ impl<'a> Compiler<'a> {
             function_type,
             locals: [Local::new("", None); 256],
             local_count: 1,
+            max_local_count: 1,
             upvalues: [Upvalue {
                 index: 0,
                 is_local: false,
             }; 256],
             scope_depth: 0,
+            returns_value: false,
+            last_call: None,
+            last_not: None,
+            last_jump_target: None,
+            try_depth: 0,
         };
         compiler.locals[0].depth = Some(0);
         if function_type != FunctionType::Function {
@@ -253,19 +410,20 @@ impl<'a> Compiler<'a> {
     }
 
     fn resolve_local(
-        &self,
+        &mut self,
         name: &str,
         previous: Token,
         had_error: &mut bool,
         panic_mode: &mut bool,
-        err: &mut impl std::io::Write
+        err: &mut ErrorOutput<impl std::io::Write>
     ) -> Option<u8> {
         for i in (0..self.local_count).rev() {
-            let local = &self.locals[i];
+            let local = &mut self.locals[i];
             if local.name == name {
                 if local.depth.is_none() {
                     error(
                         previous,
+                        CompileErrorKind::OwnInitializer,
                         "Can't read local variable in its own initializer.",
                         had_error,
                         panic_mode,
@@ -273,6 +431,7 @@ impl<'a> Compiler<'a> {
                     );
                 }
                 else {
+                    local.used = true;
                     return Some(i as u8);
                 }
             }
@@ -286,7 +445,7 @@ impl<'a> Compiler<'a> {
         previous: Token,
         had_error: &mut bool,
         panic_mode: &mut bool,
-        err: &mut impl std::io::Write
+        err: &mut ErrorOutput<impl std::io::Write>
     ) -> Option<u8> {
         if self.enclosing.is_null() {
             return None;
@@ -312,7 +471,7 @@ impl<'a> Compiler<'a> {
         previous: Token,
         had_error: &mut bool,
         panic_mode: &mut bool,
-        err: &mut impl std::io::Write
+        err: &mut ErrorOutput<impl std::io::Write>
     ) -> Option<u8> {
         let upvalue_count = self.function.borrow().upvalue_count;
         for i in 0..upvalue_count {
@@ -324,6 +483,7 @@ impl<'a> Compiler<'a> {
         if upvalue_count == (u8::MAX as usize) + 1 {
             error(
                 previous,
+                CompileErrorKind::TooManyVariables,
                 "Too many closure variables in function.",
                 had_error,
                 panic_mode,
@@ -352,13 +512,38 @@ pub struct Parser<'a, ErrOut: std::io::Write> {
     class_compiler: *mut ClassCompiler,
     panic_mode: bool,
     had_error: bool,
-    err: &'a mut ErrOut
+    options: CompilerOptions,
+    //the slots of the VM the code is compiled for
+    globals: &'a mut GlobalSlots,
+    //whether the statement just compiled always returns or throws
+    ends_in_return: bool,
+    err: ErrorOutput<'a, ErrOut>,
+    known_names: Vec<KnownName<'a>>,
+    //how many function bodies the parser is inside
+    function_depth: usize,
+    //the variable just read and where its code ends, to tell if a call's callee is only that
+    callee: Option<(&'a str, usize)>,
+    //the operator of a prefix `++` or `--` waiting for the variable or field it applies to
+    increment: Option<OpCode>,
+    //where the code of the left operand of the infix rule being compiled starts
+    operand_start: usize,
 }
 
 impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
-    fn new(source: &'a str, err: &'a mut ErrOut) -> Parser<'a, ErrOut> {
+    fn new(
+        source: &'a str,
+        options: CompilerOptions,
+        globals: &'a mut GlobalSlots,
+        err: &'a mut ErrOut,
+    ) -> Parser<'a, ErrOut> {
         Parser {
-            err,
+            err: ErrorOutput {
+                out: err,
+                source: if options.snippets { Some(source) } else { None },
+            },
+            options,
+            globals,
+            ends_in_return: false,
             scanner: Scanner::new(source),
             previous: Token::default(),
             current: Token::default(),
@@ -366,6 +551,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             class_compiler: std::ptr::null_mut(),
             panic_mode: false,
             had_error: false,
+            known_names: vec![],
+            function_depth: 0,
+            callee: None,
+            increment: None,
+            operand_start: 0,
         }
     }
 
@@ -373,6 +563,10 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.current.kind() == kind
     }
 
+    fn check_next(&self, kind: TokenKind) -> bool {
+        self.scanner.clone().scan_token().kind() == kind
+    }
+
     fn match_token(&mut self, kind: TokenKind) -> bool {
         if self.check(kind) {
             self.advance();
@@ -399,7 +593,14 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             if current.kind() != TokenKind::Error {
                 break 'skip_errors;
             }
-            error(token, token.as_str(), had_error, panic_mode, err);
+            error(
+                token,
+                CompileErrorKind::InvalidToken,
+                token.as_str(),
+                had_error,
+                panic_mode,
+                err,
+            );
         }
     }
 
@@ -409,6 +610,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         } else {
             error(
                 self.current,
+                CompileErrorKind::ExpectedToken,
                 error_message,
                 &mut self.had_error,
                 &mut self.panic_mode,
@@ -417,6 +619,20 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         }
     }
 
+    //where `infer_semicolons` lets a statement end without its `;`
+    fn semicolon_inferred(&self) -> bool {
+        self.options.infer_semicolons
+            && self.current.kind() != TokenKind::Semicolon
+            && (self.current.line() > self.previous.line()
+                || matches!(self.current.kind(), TokenKind::RightBrace | TokenKind::EOF))
+    }
+
+    fn consume_semicolon(&mut self, error_message: &str) {
+        if !self.semicolon_inferred() {
+            self.consume(TokenKind::Semicolon, error_message);
+        }
+    }
+
     fn current_chunk(&mut self) -> Gc<Chunk> {
         self.compiler.function.borrow().chunk.clone()
     }
@@ -438,6 +654,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if offset > u16::MAX as usize {
             error(
                 self.previous,
+                CompileErrorKind::JumpTooLarge,
                 "Loop body too large.",
                 &mut self.had_error,
                 &mut self.panic_mode,
@@ -461,6 +678,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if constant > u8::MAX as usize {
             error(
                 self.previous,
+                CompileErrorKind::TooManyConstants,
                 "Too many constants in one chunk.",
                 &mut self.had_error,
                 &mut self.panic_mode,
@@ -480,6 +698,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if jump > u16::MAX as usize {
             error(
                 self.previous,
+                CompileErrorKind::JumpTooLarge,
                 "Too much code to jump over.",
                 &mut self.had_error,
                 &mut self.panic_mode,
@@ -489,11 +708,63 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
 
         self.current_chunk().borrow_mut().code[offset] = ((jump >> 8) & 0xFF) as u8;
         self.current_chunk().borrow_mut().code[offset + 1] = (jump & 0xFF) as u8;
+        self.compiler.last_jump_target = Some(self.current_chunk().borrow().code.len());
+    }
+
+    fn emit_not(&mut self) {
+        self.compiler.last_not = Some(self.current_chunk().borrow().code.len());
+        self.emit_byte(OpCode::Not);
+    }
+
+    //the jump out of an `if`, `while` or `for` whose condition is popped on both paths, by the
+    //jump itself when `pop` is set. a condition ending in a `Not` is tested the other way round
+    //instead, unless some jump lands just past the `Not` and so skips it. `and` and `or` can't do
+    //this as their operand is kept
+    fn emit_condition_jump(&mut self, pop: bool) -> usize {
+        let (if_false, if_true) = if pop {
+            (OpCode::PopJumpIfFalse, OpCode::PopJumpIfTrue)
+        } else {
+            (OpCode::JumpIfFalse, OpCode::JumpIfTrue)
+        };
+        let length = self.current_chunk().borrow().code.len();
+        if length > 0
+            && self.compiler.last_not == Some(length - 1)
+            && self.compiler.last_jump_target != Some(length)
+        {
+            self.current_chunk().borrow_mut().remove_last_byte();
+            self.compiler.last_not = None;
+            return self.emit_jump(if_true);
+        }
+        self.emit_jump(if_false)
     }
 
     fn number(&mut self, _: bool) {
-        let value = Value::number(self.previous.as_str().parse::<f64>().unwrap());
-        self.emit_constant(value);
+        let literal = self.previous.as_str();
+        //with integers, a literal without a decimal point is one, unless it's too big for one
+        #[cfg(feature = "integers")]
+        if let Some(integer) = literal.parse::<i64>().ok().filter(|_| !literal.contains('.')) {
+            return self.emit_integer(integer);
+        }
+        let number = literal.parse::<f64>().unwrap();
+        self.emit_number(number);
+    }
+
+    fn emit_number(&mut self, number: f64) {
+        //small whole numbers are pushed from the operand and take no constant slot. with
+        //integers that pushes an integer, so a float needs the constant
+        if !cfg!(feature = "integers") && number.fract() == 0.0 && (0.0..=255.0).contains(&number) {
+            self.emit_byte_pair(OpCode::PushByte, number as u8);
+        } else {
+            self.emit_constant(Value::number(number));
+        }
+    }
+
+    fn emit_integer(&mut self, integer: i64) {
+        if (0..=255).contains(&integer) {
+            self.emit_byte_pair(OpCode::PushByte, integer as u8);
+        } else {
+            self.emit_constant(Value::int(integer));
+        }
     }
 
     fn literal(&mut self, _: bool) {
@@ -506,10 +777,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
     }
 
     fn string(&mut self, _: bool) {
-        let string = self.previous.as_str();
-        let value = copy_string(
-            string.trim_start_matches('"').trim_end_matches('"')
-        );
+        let value = copy_string(&string_value(self.previous.as_str()));
         let index = self.make_constant(value);
         self.emit_byte_pair(OpCode::Constant, index as u8);
     }
@@ -534,30 +802,148 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         )
     }
 
-    fn named_variable(&mut self, token: Token, can_assign: bool) {
-        let get_op: OpCode;
-        let set_op: OpCode;
+    fn named_variable(&mut self, token: Token<'a>, can_assign: bool) {
         let name = token.as_str();
-        let arg = self.resolve_local(name);
-        let arg = if arg.is_some() {
-            get_op = OpCode::GetLocal;
-            set_op = OpCode::SetLocal;
-            arg.unwrap()
+        //`this` is resolved like a local but can't be incremented
+        let assignable = name != "this";
+        let (get_op, set_op, arg) = if let Some(arg) = self.resolve_local(name) {
+            (OpCode::GetLocal, OpCode::SetLocal, arg)
         } else if let Some(arg) = self.resolve_upvalue(name) {
-            get_op = OpCode::GetUpvalue;
-            set_op = OpCode::SetUpvalue;
-            arg
+            (OpCode::GetUpvalue, OpCode::SetUpvalue, arg)
         } else {
-            let constant = self.identifier_constant(token);
-            get_op = OpCode::GetGlobal;
-            set_op = OpCode::SetGlobal;
-            constant
+            let slot = self.global_slot(token);
+            if can_assign && self.match_token(TokenKind::Equal) {
+                self.forget_parameters(name);
+                self.expression();
+                self.emit_global(OpCode::SetGlobal, slot);
+            } else if let Some(op) = self.match_compound_assignment(can_assign) {
+                self.forget_parameters(name);
+                self.emit_global(OpCode::GetGlobal, slot);
+                self.expression();
+                self.emit_byte(op);
+                self.emit_global(OpCode::SetGlobal, slot);
+            } else if let Some((op, postfix)) = self.match_increment(assignable) {
+                self.forget_parameters(name);
+                self.emit_increment(op, postfix, |parser, set| {
+                    let global_op = if set { OpCode::SetGlobal } else { OpCode::GetGlobal };
+                    parser.emit_global(global_op, slot);
+                });
+            } else {
+                self.emit_global(OpCode::GetGlobal, slot);
+                self.callee = Some((name, self.current_chunk().borrow().code.len()));
+            }
+            return;
         };
         if can_assign && self.match_token(TokenKind::Equal) {
+            self.forget_parameters(name);
             self.expression();
             self.emit_byte_pair(set_op, arg);
+        } else if let Some(op) = self.match_compound_assignment(can_assign) {
+            self.forget_parameters(name);
+            self.emit_byte_pair(get_op, arg);
+            self.expression();
+            self.emit_byte(op);
+            self.emit_byte_pair(set_op, arg);
+        } else if let Some((op, postfix)) = self.match_increment(assignable) {
+            self.forget_parameters(name);
+            self.emit_increment(op, postfix, |parser, set| {
+                parser.emit_byte_pair(if set { set_op } else { get_op }, arg);
+            });
         } else {
             self.emit_byte_pair(get_op, arg);
+            self.callee = Some((name, self.current_chunk().borrow().code.len()));
+        }
+    }
+
+    //`+=`, `-=`, `*=` or `/=`, and the operator it applies before assigning
+    fn match_compound_assignment(&mut self, can_assign: bool) -> Option<OpCode> {
+        let op = match self.current.kind() {
+            TokenKind::PlusEqual => OpCode::Add,
+            TokenKind::MinusEqual => OpCode::Subtract,
+            TokenKind::StarEqual => OpCode::Multiply,
+            TokenKind::SlashEqual => OpCode::Divide,
+            _ => return None,
+        };
+        if !can_assign {
+            return None;
+        }
+        self.advance();
+        Some(op)
+    }
+
+    //a pending prefix `++` or `--` if the operand ends here, otherwise a postfix one; the bool is
+    //whether it was postfix
+    fn match_increment(&mut self, assignable: bool) -> Option<(OpCode, bool)> {
+        if !assignable {
+            return None;
+        }
+        let ends_operand = !matches!(
+            self.current.kind(),
+            TokenKind::Dot | TokenKind::LeftParen | TokenKind::LeftBracket
+        );
+        if ends_operand {
+            if let Some(op) = self.increment.take() {
+                return Some((op, false));
+            }
+        }
+        let op = match self.current.kind() {
+            TokenKind::PlusPlus => OpCode::Add,
+            TokenKind::MinusMinus => OpCode::Subtract,
+            _ => return None,
+        };
+        self.advance();
+        Some((op, true))
+    }
+
+    //`access` emits the read of the variable, or its write when passed true; a postfix operator
+    //leaves the old value
+    fn emit_increment(&mut self, op: OpCode, postfix: bool, access: impl Fn(&mut Self, bool)) {
+        access(self, false);
+        if postfix {
+            self.emit_byte(OpCode::Dup);
+        }
+        self.emit_byte_pair(OpCode::PushByte, 1);
+        self.emit_byte(op);
+        access(self, true);
+        if postfix {
+            self.emit_byte(OpCode::Pop);
+        }
+    }
+
+    fn prefix_increment(&mut self, _: bool) {
+        let operator = self.previous;
+        let op = if operator.kind() == TokenKind::PlusPlus {
+            OpCode::Add
+        } else {
+            OpCode::Subtract
+        };
+        self.increment = Some(op);
+        self.parse_precedence(Precedence::Call);
+        //nothing in the operand took it, so it doesn't end in a variable or field
+        if self.increment.take().is_some() {
+            self.increment_error(operator);
+        }
+    }
+
+    fn invalid_increment(&mut self, _: bool) {
+        self.increment_error(self.previous);
+    }
+
+    fn increment_error(&mut self, operator: Token<'a>) {
+        error(
+            operator,
+            CompileErrorKind::InvalidAssignmentTarget,
+            "Invalid increment target.",
+            &mut self.had_error,
+            &mut self.panic_mode,
+            &mut self.err
+        );
+    }
+
+    //after an assignment the variable may no longer hold the function it was declared as
+    fn forget_parameters(&mut self, name: &str) {
+        if let Some(known) = self.known_names.iter_mut().rev().find(|known| known.name == name) {
+            known.parameters = None;
         }
     }
 
@@ -569,18 +955,32 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if self.class_compiler.is_null() {
             error(
                 self.previous,
+                CompileErrorKind::InvalidSuper,
                 "Can't use 'super' outside of a class.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             );
         } else if !unsafe { &*self.class_compiler }.has_superclass {
             error(
                 self.previous,
+                CompileErrorKind::InvalidSuper,
                 "Can't use 'super' in a class with no superclass.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
+            );
+        } else if matches!(
+            self.compiler.function_type,
+            FunctionType::StaticMethod | FunctionType::StaticGetter
+        ) {
+            error(
+                self.previous,
+                CompileErrorKind::InvalidSuper,
+                "Can't use 'super' in a static method.",
+                &mut self.had_error,
+                &mut self.panic_mode,
+                &mut self.err
             );
         }
 
@@ -588,14 +988,14 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.consume(TokenKind::Identifier, "Expect superclass method name.");
         let name = self.identifier_constant(self.previous);
 
+        //the superclass isn't pushed here: the VM finds it through the class the running method
+        //was defined in (see `ObjClosure::class`)
         self.named_variable(Token::synthetic_new("this"), false);
         if self.match_token(TokenKind::LeftParen) {
-            let arg_count = self.argument_list();
-            self.named_variable(Token::synthetic_new("super"), false);
+            let arg_count = self.argument_list(None);
             self.emit_byte_pair(OpCode::SuperInvoke, name);
             self.emit_byte(arg_count);
         } else {
-            self.named_variable(Token::synthetic_new("super"), false);
             self.emit_byte_pair(OpCode::GetSuper, name);
         }
     }
@@ -604,10 +1004,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if self.class_compiler.is_null() {
             error(
                 self.previous,
+                CompileErrorKind::ThisOutsideClass,
                 "Can't use 'this' outside of a class.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             );
             return;
         }
@@ -621,46 +1022,266 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
 
     fn unary(&mut self, _: bool) {
         let operator_kind = self.previous.kind();
+        if operator_kind == TokenKind::Plus && !self.options.relaxed {
+            //the same error as if `+` had no prefix rule
+            error(
+                self.previous,
+                CompileErrorKind::ExpectedExpression,
+                "Expect expression.",
+                &mut self.had_error,
+                &mut self.panic_mode,
+                &mut self.err
+            );
+            return;
+        }
+        //a runtime error in the operator is reported on its line, not the operand's
+        let line = self.previous.line();
+        let start = self.current_chunk().borrow().code.len();
         self.parse_precedence(Precedence::Unary);
 
+        let end = self.current_chunk().borrow().code.len();
+        let operand = self.literal_value(start, end);
+        let folded = match (operator_kind, operand) {
+            #[cfg(feature = "integers")]
+            (TokenKind::Minus, Some(operand)) if operand.as_int().is_ok() => {
+                operand.as_int().unwrap().checked_neg().map(Value::int)
+            }
+            (TokenKind::Minus, Some(operand)) if operand.is_number() => {
+                Some(Value::number(-operand.as_number().unwrap()))
+            }
+            //`!` of anything else depends on whether the VM uses pythonic truthiness
+            (TokenKind::Bang, Some(operand)) if operand.is_bool() || operand.is_nil() => {
+                Some(Value::bool_(operand.is_falsey()))
+            }
+            _ => None,
+        };
+        if let Some(value) = folded {
+            self.emit_folded(start, value);
+            return;
+        }
         match operator_kind {
             TokenKind::Minus => self.emit_byte(OpCode::Negate),
-            TokenKind::Bang => self.emit_byte(OpCode::Not),
+            TokenKind::Plus => self.emit_byte(OpCode::UnaryPlus),
+            TokenKind::Bang => self.emit_not(),
             _ => unreachable!(),
         }
+        self.current_chunk().borrow_mut().set_line(end, line);
     }
 
     fn binary(&mut self, _: bool) {
         let operator_kind = self.previous.kind();
+        //the operator is emitted after the right operand, which may end on a later line, but
+        //a runtime error in it is reported on the operator's line
+        let line = self.previous.line();
+        let left_start = self.operand_start;
+        let right_start = self.current_chunk().borrow().code.len();
         let parse_rule = get_rule::<ErrOut>(operator_kind);
         self.parse_precedence(parse_rule.precedence.next());
 
+        if let Some(value) = self.fold_binary(operator_kind, left_start, right_start) {
+            self.emit_folded(left_start, value);
+            return;
+        }
+        let operator_start = self.current_chunk().borrow().code.len();
         match operator_kind {
             TokenKind::Plus => self.emit_byte(OpCode::Add),
             TokenKind::Minus => self.emit_byte(OpCode::Subtract),
             TokenKind::Star => self.emit_byte(OpCode::Multiply),
             TokenKind::Slash => self.emit_byte(OpCode::Divide),
-            TokenKind::BangEqual => self.emit_byte_pair(OpCode::Equal, OpCode::Not),
+            TokenKind::BangEqual => {
+                self.emit_byte(OpCode::Equal);
+                self.emit_not();
+            }
             TokenKind::EqualEqual => self.emit_byte(OpCode::Equal),
             TokenKind::Greater => self.emit_byte(OpCode::Greater),
-            TokenKind::GreaterEqual => self.emit_byte_pair(OpCode::Less, OpCode::Not),
+            TokenKind::GreaterEqual => self.emit_byte(OpCode::GreaterEqual),
             TokenKind::Less => self.emit_byte(OpCode::Less),
-            TokenKind::LessEqual => self.emit_byte_pair(OpCode::Greater, OpCode::Not),
+            TokenKind::LessEqual => self.emit_byte(OpCode::LessEqual),
+            TokenKind::Is => self.emit_byte(OpCode::IsInstance),
+            //both operands are always evaluated, unlike `and` and `or`
+            TokenKind::Xor => self.emit_byte(OpCode::Xor),
             _ => unreachable!(),
         }
+        self.current_chunk().borrow_mut().set_line(operator_start, line);
+    }
+
+    //the value of the operands from `left_start` and `right_start` on if both are literals and
+    //the operator gives the same result whenever it runs. division by zero is left to the VM,
+    //which may report it or not depending on `set_ieee_division`
+    fn fold_binary(&mut self, operator: TokenKind, left_start: usize, right_start: usize) -> Option<Value> {
+        let end = self.current_chunk().borrow().code.len();
+        let left = self.literal_value(left_start, right_start)?;
+        let right = self.literal_value(right_start, end)?;
+        //an integer and a float fold as two floats
+        #[cfg(feature = "integers")]
+        let (left, right) = match (left.as_int(), right.as_int()) {
+            (Ok(a), Err(_)) if right.is_number() => (Value::number(a as f64), right),
+            (Err(_), Ok(b)) if left.is_number() => (left, Value::number(b as f64)),
+            _ => (left, right),
+        };
+        let value = match (operator, left.value_type(), right.value_type()) {
+            (TokenKind::EqualEqual, _, _) => Value::bool_(left == right),
+            (TokenKind::BangEqual, _, _) => Value::bool_(left != right),
+            (TokenKind::Plus, ValueType::String, ValueType::String) => {
+                concatenate_strings(left.to_string()?, right.to_string()?)
+            }
+            //an answer that isn't an integer is left to the VM
+            #[cfg(feature = "integers")]
+            (_, ValueType::Number, ValueType::Number)
+                if left.as_int().is_ok() && right.as_int().is_ok() =>
+            {
+                let (a, b) = (left.as_int().ok()?, right.as_int().ok()?);
+                match operator {
+                    TokenKind::Plus => integer_arithmetic(OpCode::Add, a, b)?,
+                    TokenKind::Minus => integer_arithmetic(OpCode::Subtract, a, b)?,
+                    TokenKind::Star => integer_arithmetic(OpCode::Multiply, a, b)?,
+                    TokenKind::Slash => integer_arithmetic(OpCode::Divide, a, b)?,
+                    TokenKind::Greater => Value::bool_(a > b),
+                    TokenKind::GreaterEqual => Value::bool_(a >= b),
+                    TokenKind::Less => Value::bool_(a < b),
+                    TokenKind::LessEqual => Value::bool_(a <= b),
+                    _ => return None,
+                }
+            }
+            (_, ValueType::Number, ValueType::Number) => {
+                let (a, b) = (left.as_number().ok()?, right.as_number().ok()?);
+                match operator {
+                    TokenKind::Plus => Value::number(a + b),
+                    TokenKind::Minus => Value::number(a - b),
+                    TokenKind::Star => Value::number(a * b),
+                    TokenKind::Slash if b != 0.0 => Value::number(a / b),
+                    TokenKind::Greater => Value::bool_(a > b),
+                    //the negated opposite comparison, like the VM does it, which is true for NaN
+                    TokenKind::GreaterEqual => {
+                        Value::bool_(a.partial_cmp(&b) != Some(Ordering::Less))
+                    }
+                    TokenKind::Less => Value::bool_(a < b),
+                    TokenKind::LessEqual => {
+                        Value::bool_(a.partial_cmp(&b) != Some(Ordering::Greater))
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    //the value the code from `start` to `end` pushes, if it's a single literal
+    fn literal_value(&mut self, start: usize, end: usize) -> Option<Value> {
+        let chunk = self.current_chunk();
+        let chunk = chunk.borrow();
+        let value = match (chunk.read_operation(start)?, end - start) {
+            (OpCode::PushByte, 2) => Value::int(chunk.code[start + 1] as i64),
+            //without integers this can be -0
+            #[cfg(not(feature = "integers"))]
+            (OpCode::PushByte, 3) if chunk.code[start + 2] == OpCode::Negate as u8 => {
+                Value::number(-(chunk.code[start + 1] as f64))
+            }
+            #[cfg(feature = "integers")]
+            (OpCode::PushByte, 3) if chunk.code[start + 2] == OpCode::Negate as u8 => {
+                Value::int(-(chunk.code[start + 1] as i64))
+            }
+            (OpCode::Constant, 2) => chunk.constants[chunk.code[start + 1] as usize].clone(),
+            (OpCode::True, 1) => Value::bool_(true),
+            (OpCode::False, 1) => Value::bool_(false),
+            (OpCode::Nil, 1) => Value::nil(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    //replaces the literals from `start` on with the single value they were folded into
+    fn emit_folded(&mut self, start: usize, value: Value) {
+        let chunk = self.current_chunk();
+        let mut chunk = chunk.borrow_mut();
+        //constants the literals added last are no longer needed
+        let mut offset = start;
+        let mut unused = vec![];
+        while let Some(op) = chunk.read_operation(offset) {
+            if let OpCode::Constant = op {
+                unused.push(chunk.code[offset + 1] as usize);
+            }
+            offset += 1 + op.operand_bytes();
+        }
+        while unused.contains(&(chunk.constants.len().wrapping_sub(1))) {
+            chunk.constants.pop();
+        }
+        chunk.truncate(start);
+        drop(chunk);
+        match value.value_type() {
+            #[cfg(feature = "integers")]
+            ValueType::Number if value.as_int().is_ok() => {
+                let integer = value.as_int().unwrap();
+                if (-255..0).contains(&integer) {
+                    self.emit_byte_pair(OpCode::PushByte, -integer as u8);
+                    self.emit_byte(OpCode::Negate);
+                } else {
+                    self.emit_integer(integer);
+                }
+            }
+            ValueType::Number => {
+                let number = value.as_number().unwrap();
+                //a small negative whole number, or -0, takes no constant slot either
+                if !cfg!(feature = "integers")
+                    && number.fract() == 0.0
+                    && number.is_sign_negative()
+                    && number >= -255.0
+                {
+                    self.emit_byte_pair(OpCode::PushByte, -number as u8);
+                    self.emit_byte(OpCode::Negate);
+                } else {
+                    self.emit_number(number);
+                }
+            }
+            ValueType::Bool if value.as_bool().unwrap() => self.emit_byte(OpCode::True),
+            ValueType::Bool => self.emit_byte(OpCode::False),
+            _ => self.emit_constant(value),
+        }
     }
 
-    fn argument_list(&mut self) -> u8 {
+    //`callee` is the name of the function being called, if the call is to a variable, which lets
+    //relaxed mode match named arguments to its parameters
+    fn argument_list(&mut self, callee: Option<&'a str>) -> u8 {
         let mut arg_count = 0;
+        let start = self.current_chunk().borrow().code.len();
+        let mut positional = vec![];
+        let mut named = vec![];
         'arguments: while !self.check(TokenKind::RightParen) {
-            self.expression();
+            let argument_start = self.current_chunk().borrow().code.len();
+            if self.options.relaxed
+                && self.check(TokenKind::Identifier)
+                && self.check_next(TokenKind::Colon)
+            {
+                self.advance();
+                let name = self.previous;
+                self.advance();
+                self.expression();
+                let end = self.current_chunk().borrow().code.len();
+                named.push((name, argument_start..end));
+            } else {
+                self.expression();
+                if !named.is_empty() {
+                    error(
+                        self.previous,
+                        CompileErrorKind::NamedArgument,
+                        "Positional arguments can't follow named arguments.",
+                        &mut self.had_error,
+                        &mut self.panic_mode,
+                        &mut self.err
+                    );
+                }
+                let end = self.current_chunk().borrow().code.len();
+                positional.push(argument_start..end);
+            }
             if arg_count == 255 {
                 error(
                     self.previous,
+                    CompileErrorKind::TooManyArguments,
                     "Can't have more than 255 arguments.",
                     &mut self.had_error,
                     &mut self.panic_mode,
-                    self.err
+                    &mut self.err
                 );
                 return 0; //rust panics on overflow
             }
@@ -670,11 +1291,88 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             }
         }
         self.consume(TokenKind::RightParen, "Expect ')' after arguments.");
+        if !named.is_empty() {
+            self.order_arguments(callee, start, positional, named);
+        }
         arg_count
     }
 
+    //puts the code of named arguments into parameter order, so the call is compiled as if they
+    //had been passed positionally. they are evaluated in that order too, not as written
+    fn order_arguments(
+        &mut self,
+        callee: Option<&'a str>,
+        start: usize,
+        mut ranges: Vec<std::ops::Range<usize>>,
+        named: Vec<(Token<'a>, std::ops::Range<usize>)>,
+    ) {
+        let known = callee.and_then(|callee| {
+            self.known_names.iter().rev().find(|known| known.name == callee)
+        });
+        let parameters = match known.and_then(|known| known.parameters.clone()) {
+            Some(parameters) => parameters,
+            None => {
+                error(
+                    named[0].0,
+                    CompileErrorKind::NamedArgument,
+                    "Named arguments need a function declared in scope.",
+                    &mut self.had_error,
+                    &mut self.panic_mode,
+                    &mut self.err
+                );
+                return;
+            }
+        };
+        let mut arguments = vec![None; parameters.len()];
+        for (name, range) in named {
+            let message = match parameters.iter().position(|parameter| *parameter == name.as_str()) {
+                Some(index) if index < ranges.len() || arguments[index].is_some() => {
+                    format!("Parameter '{}' already has an argument.", name.as_str())
+                }
+                Some(index) => {
+                    arguments[index] = Some(range);
+                    continue;
+                }
+                None => format!("No parameter named '{}'.", name.as_str()),
+            };
+            error(
+                name,
+                CompileErrorKind::NamedArgument,
+                &message,
+                &mut self.had_error,
+                &mut self.panic_mode,
+                &mut self.err
+            );
+            return;
+        }
+        for (parameter, argument) in parameters.iter().zip(arguments).skip(ranges.len()) {
+            match argument {
+                Some(range) => ranges.push(range),
+                None => {
+                    let message = format!("Missing an argument for parameter '{}'.", parameter);
+                    error(
+                        self.previous,
+                        CompileErrorKind::NamedArgument,
+                        &message,
+                        &mut self.had_error,
+                        &mut self.panic_mode,
+                        &mut self.err
+                    );
+                    return;
+                }
+            }
+        }
+        self.current_chunk().borrow_mut().rearrange(start, &ranges);
+    }
+
     fn call(&mut self, _: bool) {
-        let arg_count = self.argument_list();
+        let code_len = self.current_chunk().borrow().code.len();
+        let callee = match self.callee.take() {
+            Some((name, end)) if end == code_len => Some(name),
+            _ => None,
+        };
+        let arg_count = self.argument_list(callee);
+        self.compiler.last_call = Some(self.current_chunk().borrow().code.len());
         self.emit_byte_pair(OpCode::Call, arg_count);
     }
 
@@ -685,26 +1383,102 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if can_assign && self.match_token(TokenKind::Equal) {
             self.expression();
             self.emit_byte_pair(OpCode::SetProperty, name);
+        } else if let Some(op) = self.match_compound_assignment(can_assign) {
+            //the receiver is evaluated once and kept for the `SetProperty`
+            self.emit_byte(OpCode::Dup);
+            self.emit_get_property(name);
+            self.expression();
+            self.emit_byte(op);
+            self.emit_byte_pair(OpCode::SetProperty, name);
+        } else if let Some((op, postfix)) = self.match_increment(true) {
+            self.emit_byte(OpCode::Dup);
+            self.emit_get_property(name);
+            if postfix {
+                //the old value goes under the receiver, and a copy of it is incremented
+                self.emit_byte(OpCode::Swap);
+                self.emit_byte(OpCode::Over);
+            }
+            self.emit_byte_pair(OpCode::PushByte, 1);
+            self.emit_byte(op);
+            self.emit_byte_pair(OpCode::SetProperty, name);
+            if postfix {
+                self.emit_byte(OpCode::Pop);
+            }
         } else if self.match_token(TokenKind::LeftParen) {
-            let arg_count = self.argument_list();
+            let arg_count = self.argument_list(None);
             self.emit_byte_pair(OpCode::Invoke, name);
             self.emit_byte(arg_count);
         } else {
-            self.emit_byte_pair(OpCode::GetProperty, name);
+            self.emit_get_property(name);
+        }
+    }
+
+    fn emit_get_property(&mut self, name: u8) {
+        let cache = self.current_chunk().borrow_mut().add_property_cache();
+        if cache > u16::MAX as usize {
+            error(
+                self.previous,
+                CompileErrorKind::TooManyConstants,
+                "Too many property reads in one chunk.",
+                &mut self.had_error,
+                &mut self.panic_mode,
+                &mut self.err
+            );
+        }
+        self.emit_byte_pair(OpCode::GetProperty, name);
+        self.emit_byte((cache >> 8) as u8);
+        self.emit_byte(cache as u8);
+    }
+
+    fn array(&mut self, _: bool) {
+        let mut count = 0;
+        'elements: while !self.check(TokenKind::RightBracket) {
+            self.expression();
+            if count == 255 {
+                error(
+                    self.previous,
+                    CompileErrorKind::TooManyArguments,
+                    "Can't have more than 255 elements in an array literal.",
+                    &mut self.had_error,
+                    &mut self.panic_mode,
+                    &mut self.err
+                );
+                return;
+            }
+            count += 1;
+            if !self.match_token(TokenKind::Comma) {
+                break 'elements;
+            }
+        }
+        self.consume(TokenKind::RightBracket, "Expect ']' after array elements.");
+        self.emit_byte_pair(OpCode::Array, count);
+    }
+
+    fn index(&mut self, can_assign: bool) {
+        self.expression();
+        self.consume(TokenKind::RightBracket, "Expect ']' after index.");
+
+        if can_assign && self.match_token(TokenKind::Equal) {
+            self.expression();
+            self.emit_byte(OpCode::SetIndex);
+        } else {
+            self.emit_byte(OpCode::GetIndex);
         }
     }
 
     fn parse_precedence(&mut self, precedence: Precedence) {
         self.advance();
         let can_assign = precedence <= Precedence::Assignment;
+        let start = self.current_chunk().borrow().code.len();
         let prefix_rule = get_rule(self.previous.kind()).prefix;
         match prefix_rule {
             None => error(
                 self.previous,
+                CompileErrorKind::ExpectedExpression,
                 "Expect expression.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             ),
             Some(prefix_rule) => prefix_rule(self, can_assign),
         }
@@ -716,21 +1490,51 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
                 None => {
                     println!("no infix rule for {:?}", self.previous.kind());
                 }
-                Some(infix_rule) => infix_rule(self, can_assign),
+                Some(infix_rule) => {
+                    self.operand_start = start;
+                    infix_rule(self, can_assign)
+                }
             }
         }
 
-        if can_assign && self.match_token(TokenKind::Equal) {
+        if can_assign
+            && (self.match_token(TokenKind::Equal)
+                || self.match_compound_assignment(can_assign).is_some())
+        {
             error(
                 self.previous,
+                CompileErrorKind::InvalidAssignmentTarget,
                 "Invalid assignment target.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             );
         }
     }
 
+    fn global_slot(&mut self, name: Token) -> u16 {
+        match self.globals.slot(name.as_str()) {
+            Some(slot) => slot,
+            None => {
+                error(
+                    name,
+                    CompileErrorKind::TooManyVariables,
+                    "Too many global variables.",
+                    &mut self.had_error,
+                    &mut self.panic_mode,
+                    &mut self.err,
+                );
+                0
+            }
+        }
+    }
+
+    fn emit_global(&mut self, op: OpCode, slot: u16) {
+        self.emit_byte(op);
+        self.emit_byte((slot >> 8) as u8);
+        self.emit_byte(slot as u8);
+    }
+
     fn identifier_constant(&mut self, name: Token) -> u8 {
         let str_obj = ObjString::new(name.as_str().to_string());
         return self.make_constant(Value::string(str_obj.into())) as u8;
@@ -740,19 +1544,33 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if self.compiler.local_count == (u8::MAX as usize) + 1 {
             error(
                 self.previous,
+                CompileErrorKind::TooManyVariables,
                 "Too many local variables in function.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             );
             return;
         }
         let local = &mut self.compiler.locals[self.compiler.local_count as usize];
         self.compiler.local_count += 1;
+        self.compiler.max_local_count = self.compiler.max_local_count.max(self.compiler.local_count);
         *local = Local::new(name, None);
+        local.line = self.previous.line();
+    }
+
+    //records a declaration, which hides any function of the same name from named arguments
+    fn declare_known_name(&mut self, name: &'a str) {
+        self.known_names.push(KnownName {
+            name,
+            parameters: None,
+            function_depth: self.function_depth,
+            scope_depth: self.compiler.scope_depth,
+        });
     }
 
     fn declare_variable(&mut self) {
+        self.declare_known_name(self.previous.as_str());
         if self.compiler.scope_depth == 0 {
             return;
         }
@@ -766,10 +1584,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             if name == local.name {
                 error(
                     self.previous,
+                    CompileErrorKind::DuplicateVariable,
                     "Already a variable with this name in this scope.",
                     &mut self.had_error,
                     &mut self.panic_mode,
-                    self.err
+                    &mut self.err
                 );
             }
         }
@@ -783,22 +1602,23 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.compiler.locals[self.compiler.local_count - 1].depth = Some(self.compiler.scope_depth);
     }
 
-    fn parse_variable(&mut self, error_message: &str) -> u8 {
+    //returns the global slot to define, which is unused for locals
+    fn parse_variable(&mut self, error_message: &str) -> u16 {
         self.consume(TokenKind::Identifier, error_message);
         self.declare_variable();
         if self.compiler.scope_depth > 0 {
             return 0;
         }
 
-        return self.identifier_constant(self.previous);
+        self.global_slot(self.previous)
     }
 
-    fn define_variable(&mut self, global: u8) {
+    fn define_variable(&mut self, global: u16) {
         if self.compiler.scope_depth > 0 {
             self.mark_initialized();
             return;
         }
-        self.emit_byte_pair(OpCode::DefineGlobal, global);
+        self.emit_global(OpCode::DefineGlobal, global);
     }
 
     fn and(&mut self, _: bool) {
@@ -817,13 +1637,29 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.patch_jump(end_jump);
     }
 
+    //unlike `or`, only `nil` on the left gives the right, so `false ?? 1` is false
+    fn coalesce(&mut self, _: bool) {
+        let end_jump = self.emit_jump(OpCode::JumpIfNotNil);
+        self.emit_byte(OpCode::Pop);
+        self.parse_precedence(Precedence::Coalesce);
+        self.patch_jump(end_jump);
+    }
+
     fn expression(&mut self) {
+        //a prefix `++` outside doesn't apply to anything in a nested expression like an index
+        let increment = self.increment.take();
         self.parse_precedence(Precedence::Assignment);
+        self.increment = increment;
     }
 
     fn print_statement(&mut self) {
         self.expression();
-        self.consume(TokenKind::Semicolon, "Expect ';' after value.");
+        //`print a, b;` prints the values on one line, separated by spaces
+        while self.options.relaxed && self.match_token(TokenKind::Comma) {
+            self.emit_byte(OpCode::PrintSep);
+            self.expression();
+        }
+        self.consume_semicolon("Expect ';' after value.");
         self.emit_byte(OpCode::Print);
     }
 
@@ -831,29 +1667,43 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         if self.compiler.function_type == FunctionType::Script {
             error(
                 self.previous,
+                CompileErrorKind::TopLevelReturn,
                 "Can't return from top-level code.",
                 &mut self.had_error,
                 &mut self.panic_mode,
-                self.err
+                &mut self.err
             );
         }
 
-        if self.match_token(TokenKind::Semicolon) {
+        if self.match_token(TokenKind::Semicolon) || self.semicolon_inferred() {
             self.emit_return();
         } else {
             if self.compiler.function_type == FunctionType::Initializer {
                 error(
                     self.previous,
+                    CompileErrorKind::InitializerMisuse,
                     "Can't return a value from an initializer.",
                     &mut self.had_error,
                     &mut self.panic_mode,
-                    self.err
+                    &mut self.err
                 );
             }
             self.expression();
-            self.consume(TokenKind::Semicolon, "Expect ';' after return value.");
+            self.consume_semicolon("Expect ';' after return value.");
+            //a call that is the last thing the expression does can reuse this function's frame;
+            //the `Return` stays behind it for callees that aren't Lox functions. inside a `try`
+            //the frame has to stay, since its `catch` runs in it
+            let chunk = self.current_chunk();
+            let end = chunk.borrow().code.len();
+            if self.compiler.try_depth == 0
+                && self.compiler.last_call.is_some_and(|offset| offset + 2 == end)
+            {
+                chunk.borrow_mut().code[end - 2] = OpCode::TailCall as u8;
+            }
             self.emit_byte(OpCode::Return);
+            self.compiler.returns_value = true;
         }
+        self.ends_in_return = true;
     }
 
     fn while_statement(&mut self) {
@@ -861,7 +1711,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.consume(TokenKind::LeftParen, "Expect '(' after 'while'.");
         self.expression();
         self.consume(TokenKind::RightParen, "Expect ')' after condition.");
-        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        let exit_jump = self.emit_condition_jump(false);
         self.emit_byte(OpCode::Pop);
         self.statement();
         self.emit_loop(loop_start);
@@ -871,13 +1721,26 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
 
     fn expression_statement(&mut self) {
         self.expression();
-        self.consume(TokenKind::Semicolon, "Expect ';' after expression.");
+        if self.options.repl
+            && self.compiler.function_type == FunctionType::Script
+            && self.compiler.scope_depth == 0
+            && self.check(TokenKind::EOF)
+        {
+            self.emit_byte(OpCode::Print);
+            return;
+        }
+        self.consume_semicolon("Expect ';' after expression.");
         self.emit_byte(OpCode::Pop);
     }
 
     fn for_statement(&mut self) {
         self.begin_scope();
         self.consume(TokenKind::LeftParen, "Expect '(' after 'for'.");
+        if self.check(TokenKind::Identifier) && self.check_next(TokenKind::In) {
+            self.for_in_statement();
+            self.end_scope();
+            return;
+        }
         if self.match_token(TokenKind::Semicolon) {
         } else if self.match_token(TokenKind::Var) {
             self.var_declaration();
@@ -890,7 +1753,7 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             self.expression();
             self.consume(TokenKind::Semicolon, "Expect ';' after loop condition.");
 
-            let jump = Some(self.emit_jump(OpCode::JumpIfFalse));
+            let jump = Some(self.emit_condition_jump(false));
             self.emit_byte(OpCode::Pop);
             jump
         } else {
@@ -921,30 +1784,178 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.end_scope();
     }
 
-    fn if_statement(&mut self) {
-        self.consume(TokenKind::LeftParen, "Expect '(' after 'if'.");
+    fn for_in_statement(&mut self) {
+        self.consume(TokenKind::Identifier, "Expect loop variable name.");
+        let item = self.previous;
+        self.consume(TokenKind::In, "Expect 'in' after loop variable.");
         self.expression();
-        self.consume(TokenKind::RightParen, "Expect ')' after condition.");
-        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        if self.match_token(TokenKind::DotDot) {
+            self.for_range_statement(item);
+            return;
+        }
+        self.consume(TokenKind::RightParen, "Expect ')' after for clauses.");
+        self.emit_byte(OpCode::Iterable);
+
+        //hidden locals for the array and the current index. the names contain a space so
+        //they can never be resolved by user code
+        self.add_local("for array");
+        self.mark_initialized();
+        let array = (self.compiler.local_count - 1) as u8;
+        self.emit_constant(Value::number(0.0));
+        self.add_local("for index");
+        self.mark_initialized();
+        let index = array + 1;
+
+        let loop_start = self.current_chunk().borrow().code.len();
+        self.emit_byte_pair(OpCode::GetLocal, array);
+        self.emit_byte(OpCode::Length);
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.emit_byte(OpCode::Greater);
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
         self.emit_byte(OpCode::Pop);
+
+        //the loop variable lives in its own scope so each iteration gets a fresh variable,
+        //closed over separately if a closure captures it
+        self.begin_scope();
+        self.emit_byte_pair(OpCode::GetLocal, array);
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.emit_byte(OpCode::GetIndex);
+        self.add_local(item.as_str());
+        self.declare_known_name(item.as_str());
+        self.mark_initialized();
         self.statement();
+        self.end_scope();
+
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.emit_constant(Value::number(1.0));
+        self.emit_byte(OpCode::Add);
+        self.emit_byte_pair(OpCode::SetLocal, index);
+        self.emit_byte(OpCode::Pop);
+        self.emit_loop(loop_start);
+
+        self.patch_jump(exit_jump);
+        self.emit_byte(OpCode::Pop);
+    }
 
-        let else_jump = self.emit_jump(OpCode::Jump);
+    //`for (i in start..end)` counts from start up to but not including end. both bounds are
+    //evaluated once, before the first iteration
+    fn for_range_statement(&mut self, item: Token<'a>) {
+        //the unary plus makes each bound a runtime error unless it's a number
+        self.emit_byte(OpCode::UnaryPlus);
+        self.add_local("for index");
+        self.mark_initialized();
+        let index = (self.compiler.local_count - 1) as u8;
+        self.expression();
+        self.emit_byte(OpCode::UnaryPlus);
+        self.consume(TokenKind::RightParen, "Expect ')' after for clauses.");
+        self.add_local("for end");
+        self.mark_initialized();
+        let end = index + 1;
 
-        self.patch_jump(then_jump);
+        let loop_start = self.current_chunk().borrow().code.len();
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.emit_byte_pair(OpCode::GetLocal, end);
+        self.emit_byte(OpCode::Less);
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
         self.emit_byte(OpCode::Pop);
 
+        //a fresh copy of the counter each iteration, like the array form's item, so assigning
+        //to it or capturing it doesn't change the count
+        self.begin_scope();
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.add_local(item.as_str());
+        self.declare_known_name(item.as_str());
+        self.mark_initialized();
+        self.statement();
+        self.end_scope();
+
+        self.emit_byte_pair(OpCode::GetLocal, index);
+        self.emit_byte_pair(OpCode::PushByte, 1);
+        self.emit_byte(OpCode::Add);
+        self.emit_byte_pair(OpCode::SetLocal, index);
+        self.emit_byte(OpCode::Pop);
+        self.emit_loop(loop_start);
+
+        self.patch_jump(exit_jump);
+        self.emit_byte(OpCode::Pop);
+    }
+
+    fn if_statement(&mut self) {
+        self.consume(TokenKind::LeftParen, "Expect '(' after 'if'.");
+        self.expression();
+        self.consume(TokenKind::RightParen, "Expect ')' after condition.");
+        //the jump pops the condition, so without an `else` there's nothing to jump over
+        let then_jump = self.emit_condition_jump(true);
+        self.statement();
+        let then_returns = self.ends_in_return;
+        self.ends_in_return = false;
+
         if self.match_token(TokenKind::Else) {
+            let else_jump = self.emit_jump(OpCode::Jump);
+            self.patch_jump(then_jump);
             self.statement();
+            self.ends_in_return = then_returns && self.ends_in_return;
+            self.patch_jump(else_jump);
+        } else {
+            self.patch_jump(then_jump);
         }
-        self.patch_jump(else_jump);
+    }
+
+    fn throw_statement(&mut self) {
+        self.expression();
+        self.consume_semicolon("Expect ';' after thrown value.");
+        self.emit_byte(OpCode::Throw);
+        self.ends_in_return = true;
+    }
+
+    //a runtime error or `throw` in the `try` block goes to the `catch` block, with the stack as
+    //it was before the `try` and the error message or thrown value on top of it, which becomes
+    //the catch variable
+    fn try_statement(&mut self) {
+        let handler = self.emit_jump(OpCode::PushHandler);
+        self.consume(TokenKind::LeftBrace, "Expect '{' after 'try'.");
+        self.compiler.try_depth += 1;
+        self.begin_scope();
+        self.block();
+        self.end_scope();
+        self.compiler.try_depth -= 1;
+        let try_returns = self.ends_in_return;
+        self.emit_byte(OpCode::PopHandler);
+        let end_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(handler);
+        if !self.match_token(TokenKind::Catch) {
+            //what follows is better parsed as the next statement than as a catch block
+            self.consume(TokenKind::Catch, "Expect 'catch' after try block.");
+            return;
+        }
+        self.consume(TokenKind::LeftParen, "Expect '(' after 'catch'.");
+        self.begin_scope();
+        self.consume(TokenKind::Identifier, "Expect error variable name.");
+        self.declare_variable();
+        self.mark_initialized();
+        self.consume(TokenKind::RightParen, "Expect ')' after error variable.");
+        self.consume(TokenKind::LeftBrace, "Expect '{' before catch body.");
+        self.block();
+        self.end_scope();
+        self.ends_in_return = try_returns && self.ends_in_return;
+        self.patch_jump(end_jump);
     }
 
     fn block(&mut self) {
+        //whether a statement in this block always returns, so that nothing after it can run
+        let mut returned = false;
+        let mut warned = false;
         while !self.scanner.is_at_end() && !self.check(TokenKind::RightBrace) {
+            if returned && !warned && self.options.warn_unreachable {
+                writeln!(self.err, "[line {}] Warning: unreachable code.", self.current.line()).ok();
+                warned = true;
+            }
             self.declaration();
+            returned = returned || self.ends_in_return;
         }
         self.consume(TokenKind::RightBrace, "Expect '}' after block.");
+        self.ends_in_return = returned;
     }
 
     fn function(&mut self, function_type: FunctionType) {
@@ -955,7 +1966,59 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         );
         let mut old_compiler = std::mem::replace(&mut self.compiler, compiler);
         self.compiler.enclosing = &mut old_compiler as *mut _;
+        self.function_depth += 1;
         self.begin_scope();
+        if matches!(function_type, FunctionType::Getter | FunctionType::StaticGetter) {
+            self.compiler.function.borrow_mut().is_getter = true;
+        } else {
+            self.parameters();
+        }
+        if function_type == FunctionType::Function {
+            //the function's own name is the last thing declared outside it
+            let parameters = self.compiler.locals[1..self.compiler.local_count]
+                .iter()
+                .map(|local| local.name)
+                .collect();
+            let depth = self.function_depth - 1;
+            if let Some(known) = self
+                .known_names
+                .iter_mut()
+                .rev()
+                .find(|known| known.function_depth == depth)
+            {
+                known.parameters = Some(parameters);
+            }
+        }
+        self.consume(TokenKind::LeftBrace, "Expect '{' before function body.");
+        self.block();
+        if self.options.strict && self.compiler.returns_value && !self.ends_in_return {
+            let message = format!(
+                "Function '{}' can reach the end of its body without returning a value.",
+                self.compiler.function.borrow().name.as_ref().unwrap()
+            );
+            warning(self.previous, &message, &mut self.err);
+        }
+        self.ends_in_return = false;
+        //the body's outermost scope is never ended, it goes away with the compiler
+        let arity = self.compiler.function.borrow().arity;
+        self.warn_unused_locals(arity + 1);
+
+        let function = self.end();
+        let compiler = std::mem::replace(&mut self.compiler, old_compiler);
+        self.function_depth -= 1;
+        let depth = self.function_depth;
+        self.known_names.retain(|known| known.function_depth <= depth);
+
+        let f = self.make_constant(Value::function(function.clone().into()));
+        self.emit_byte_pair(OpCode::Closure, f);
+
+        for i in 0..function.borrow().upvalue_count {
+            self.emit_byte(if compiler.upvalues[i].is_local { 1 } else { 0 });
+            self.emit_byte(compiler.upvalues[i].index);
+        }
+    }
+
+    fn parameters(&mut self) {
         self.consume(TokenKind::LeftParen, "Expect '(' after function name.");
         'parameters: while !self.check(TokenKind::RightParen) {
             {
@@ -964,10 +2027,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
                 if *arity > 255 {
                     error(
                         self.current,
+                        CompileErrorKind::TooManyArguments,
                         "Can't have more than 255 parameters.",
                         &mut self.had_error,
                         &mut self.panic_mode,
-                        self.err
+                        &mut self.err
                     );
                 }
             }
@@ -978,41 +2042,57 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             }
         }
         self.consume(TokenKind::RightParen, "Expect ')' after parameters.");
-        self.consume(TokenKind::LeftBrace, "Expect '{' before function body.");
-        self.block();
-
-        let function = self.end();
-        let compiler = std::mem::replace(&mut self.compiler, old_compiler);
-
-        let f = self.make_constant(Value::function(function.clone().into()));
-        self.emit_byte_pair(OpCode::Closure, f);
-
-        for i in 0..function.borrow().upvalue_count {
-            self.emit_byte(if compiler.upvalues[i].is_local { 1 } else { 0 });
-            self.emit_byte(compiler.upvalues[i].index);
-        }
     }
 
-    fn method(&mut self) {
+    fn method(&mut self, is_static: bool) {
         self.consume(TokenKind::Identifier, "Expect method name.");
         let constant = self.identifier_constant(self.previous);
-        let function_type = if self.previous.as_str() == "init" {
+        //a method with no parameter list, like `area { ... }`, is a getter. getters can't take
+        //parameters: anything with a parameter list is an ordinary method
+        let function_type = if self.check(TokenKind::LeftBrace) {
+            if self.previous.as_str() == "init" {
+                error(
+                    self.previous,
+                    CompileErrorKind::InitializerMisuse,
+                    "An initializer can't be a getter.",
+                    &mut self.had_error,
+                    &mut self.panic_mode,
+                    &mut self.err
+                );
+            }
+            if is_static {
+                FunctionType::StaticGetter
+            } else {
+                FunctionType::Getter
+            }
+        } else if is_static {
+            FunctionType::StaticMethod
+        } else if self.previous.as_str() == "init" {
             FunctionType::Initializer
         } else {
             FunctionType::Method
         };
         self.function(function_type);
-        self.emit_byte_pair(OpCode::Method, constant);
+        if is_static {
+            self.emit_byte_pair(OpCode::StaticMethod, constant);
+        } else {
+            self.emit_byte_pair(OpCode::Method, constant);
+        }
     }
 
     fn class_declaration(&mut self) {
         self.consume(TokenKind::Identifier, "Expect class name.");
         let class_name = self.previous;
         let name_constant = self.identifier_constant(self.previous);
+        let global = if self.compiler.scope_depth > 0 {
+            0
+        } else {
+            self.global_slot(self.previous)
+        };
         self.declare_variable();
 
         self.emit_byte_pair(OpCode::Class, name_constant);
-        self.define_variable(name_constant);
+        self.define_variable(global);
 
         let mut class_compiler = ClassCompiler {
             enclosing: std::ptr::null_mut(),
@@ -1031,17 +2111,14 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             if class_name.as_str() == self.previous.as_str() {
                 error(
                     self.previous,
+                    CompileErrorKind::SelfInheritance,
                     "A class can't inherit from itself.",
                     &mut self.had_error,
                     &mut self.panic_mode,
-                    self.err
+                    &mut self.err
                 );
             }
 
-            self.begin_scope();
-            self.add_local("super");
-            self.define_variable(0);
-
             self.named_variable(class_name, false);
             self.emit_byte(OpCode::Inherit);
             unsafe {
@@ -1052,13 +2129,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.named_variable(class_name, false);
         self.consume(TokenKind::LeftBrace, "Expect '{' before class body.");
         while !self.check(TokenKind::RightBrace) && !self.check(TokenKind::EOF) {
-            self.method();
+            let is_static = self.match_token(TokenKind::Class);
+            self.method(is_static);
         }
         self.consume(TokenKind::RightBrace, "Expect '}' after class body.");
         self.emit_byte(OpCode::Pop);
-        if unsafe { &*self.class_compiler }.has_superclass {
-            self.end_scope();
-        }
         let _class_compiler = std::mem::replace(&mut self.class_compiler, old_class_compiler);
     }
 
@@ -1076,10 +2151,17 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
             self.if_statement();
         } else if self.match_token(TokenKind::Return) {
             self.return_statement();
+        } else if self.match_token(TokenKind::Try) {
+            self.try_statement();
+        } else if self.match_token(TokenKind::Throw) {
+            self.throw_statement();
         } else if self.match_token(TokenKind::While) {
             self.while_statement();
+            //the body might never run
+            self.ends_in_return = false;
         } else if self.match_token(TokenKind::For) {
             self.for_statement();
+            self.ends_in_return = false;
         } else if self.match_token(TokenKind::LeftBrace) {
             self.begin_scope();
             self.block();
@@ -1102,6 +2184,8 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
                 | TokenKind::For
                 | TokenKind::If
                 | TokenKind::While
+                | TokenKind::Try
+                | TokenKind::Throw
                 | TokenKind::Print
                 | TokenKind::Return => break 'sync,
                 _ => (),
@@ -1111,23 +2195,84 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
     }
 
     fn var_declaration(&mut self) {
+        if self.match_token(TokenKind::LeftBracket) {
+            self.destructuring_declaration();
+            return;
+        }
         let global = self.parse_variable("Expect variable name.");
 
         if self.match_token(TokenKind::Equal) {
             self.expression();
+        } else if self.options.strict {
+            self.emit_byte(OpCode::Uninitialized);
         } else {
             self.emit_byte(OpCode::Nil);
         }
 
-        self.consume(
-            TokenKind::Semicolon,
-            "Expect ';' after variable declaration.",
-        );
+        self.consume_semicolon("Expect ';' after variable declaration.");
 
         self.define_variable(global);
     }
 
+    //`var [a, b] = pair;` declares a variable for each element of the array, from the first on;
+    //an array that's too short is an index error. locals get a placeholder each before the
+    //initializer runs, so it can't read them, and are then set from the array while it sits on
+    //the stack above them
+    fn destructuring_declaration(&mut self) {
+        let first_local = self.compiler.local_count;
+        let mut globals = vec![];
+        loop {
+            globals.push(self.parse_variable("Expect variable name."));
+            if self.compiler.scope_depth > 0 {
+                self.emit_byte(OpCode::Nil);
+            }
+            if !self.match_token(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenKind::RightBracket, "Expect ']' after variable names.");
+        self.consume(TokenKind::Equal, "Expect '=' after variable names.");
+        self.expression();
+        self.consume_semicolon("Expect ';' after variable declaration.");
+
+        if self.compiler.scope_depth == 0 {
+            for (i, global) in globals.into_iter().enumerate() {
+                self.emit_byte(OpCode::Dup);
+                self.emit_integer(i as i64);
+                self.emit_byte(OpCode::GetIndex);
+                self.define_variable(global);
+            }
+            self.emit_byte(OpCode::Pop);
+            return;
+        }
+        let array = self.compiler.local_count;
+        if array > u8::MAX as usize {
+            error(
+                self.previous,
+                CompileErrorKind::TooManyVariables,
+                "Too many local variables in function.",
+                &mut self.had_error,
+                &mut self.panic_mode,
+                &mut self.err
+            );
+            return;
+        }
+        for slot in first_local..array {
+            self.emit_byte_pair(OpCode::GetLocal, array as u8);
+            self.emit_integer((slot - first_local) as i64);
+            self.emit_byte(OpCode::GetIndex);
+            self.emit_byte_pair(OpCode::SetLocal, slot as u8);
+            self.emit_byte(OpCode::Pop);
+        }
+        self.emit_byte(OpCode::Pop);
+        let depth = self.compiler.scope_depth;
+        for local in &mut self.compiler.locals[first_local..array] {
+            local.depth = Some(depth);
+        }
+    }
+
     fn declaration(&mut self) {
+        self.ends_in_return = false;
         if self.match_token(TokenKind::Class) {
             self.class_declaration();
         } else if self.match_token(TokenKind::Var) {
@@ -1153,6 +2298,11 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
 
     fn end(&mut self) -> Gc<ObjFunction> {
         self.emit_return();
+        if self.options.optimize {
+            self.current_chunk().borrow_mut().peephole();
+        }
+        //slot 0 holds the callee or `this`, which isn't counted as a local
+        self.compiler.function.borrow_mut().local_count = self.compiler.max_local_count - 1;
         self.compiler.function.clone()
     }
 
@@ -1160,25 +2310,106 @@ impl<'a, ErrOut:std::io::Write> Parser<'a, ErrOut> {
         self.compiler.scope_depth += 1;
     }
 
+    //warns about the locals from `first` on that were never used, in the order they're declared
+    fn warn_unused_locals(&mut self, first: usize) {
+        if !self.options.warn_unused {
+            return;
+        }
+        let count = self.compiler.local_count;
+        for local in &self.compiler.locals[first.min(count)..count] {
+            //hidden locals have a space in their name
+            if !local.used && !local.name.contains(' ') {
+                writeln!(
+                    self.err,
+                    "[line {}] Warning: unused local variable '{}'.",
+                    local.line, local.name
+                )
+                .ok();
+            }
+        }
+    }
+
     fn end_scope(&mut self) {
         self.compiler.scope_depth -= 1;
+        let (function_depth, scope_depth) = (self.function_depth, self.compiler.scope_depth);
+        let first = (0..self.compiler.local_count)
+            .rev()
+            .take_while(|i| self.compiler.locals[*i].depth > Some(scope_depth))
+            .last();
+        if let Some(first) = first {
+            self.warn_unused_locals(first);
+        }
+        self.known_names.retain(|known| {
+            known.function_depth < function_depth || known.scope_depth <= scope_depth
+        });
 
+        //runs of uncaptured locals are dropped together; captured ones are closed one at a time
+        let mut pops = 0u8;
         while self.compiler.local_count > 0
             && self.compiler.locals[self.compiler.local_count - 1].depth
                 > Some(self.compiler.scope_depth)
         {
             if self.compiler.locals[self.compiler.local_count - 1].is_captured {
+                self.emit_pops(pops);
+                pops = 0;
                 self.emit_byte(OpCode::CloseUpvalue);
             } else {
-                self.emit_byte(OpCode::Pop);
+                pops += 1;
             }
             self.compiler.local_count -= 1;
         }
+        self.emit_pops(pops);
+    }
+
+    //slot zero is never a block local, so a scope can't hold more than 255 of them
+    fn emit_pops(&mut self, count: u8) {
+        match count {
+            0 => (),
+            1 => self.emit_byte(OpCode::Pop),
+            _ => self.emit_byte_pair(OpCode::PopN, count),
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn compile(
+    source: &str,
+    globals: &mut GlobalSlots,
+    err: &mut impl std::io::Write,
+) -> Result<Gc<ObjFunction>, InterpretError> {
+    compile_with_options(source, CompilerOptions::default(), globals, err)
+}
+
+/// Compiles `source`, which must be a single expression, into a function that returns its value.
+pub fn compile_expression(
+    source: &str,
+    options: CompilerOptions,
+    globals: &mut GlobalSlots,
+    err: &mut impl std::io::Write,
+) -> Result<Gc<ObjFunction>, InterpretError> {
+    let mut parser = Parser::new(source, options, globals, err);
+    parser.advance();
+    parser.expression();
+    parser.consume(TokenKind::EOF, "Expect end of expression.");
+    //the `nil; return` that `end` adds after this is never reached
+    parser.emit_byte(OpCode::Return);
+    let function = parser.end();
+    match parser.had_error {
+        false => {
+            debug_assert_eq!(crate::chunk::verify::verify_function(&function.borrow()), Ok(()));
+            Ok(function)
+        }
+        true => Err(InterpretError::Compile),
     }
 }
 
-pub fn compile<'a>(source: &str, err: &mut impl std::io::Write) -> Result<Gc<ObjFunction>, InterpretError> {
-    let mut parser = Parser::new(source, err);
+pub fn compile_with_options(
+    source: &str,
+    options: CompilerOptions,
+    globals: &mut GlobalSlots,
+    err: &mut impl std::io::Write,
+) -> Result<Gc<ObjFunction>, InterpretError> {
+    let mut parser = Parser::new(source, options, globals, err);
     parser.advance();
     while !parser.scanner.is_at_end() {
         parser.declaration();
@@ -1186,7 +2417,7 @@ pub fn compile<'a>(source: &str, err: &mut impl std::io::Write) -> Result<Gc<Obj
     let function = parser.end();
     match parser.had_error {
         false => {
-            //parser.current_chunk().borrow().disassemble();
+            debug_assert_eq!(crate::chunk::verify::verify_function(&function.borrow()), Ok(()));
             Ok(function)
         }
         true => Err(InterpretError::Compile),
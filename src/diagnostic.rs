@@ -0,0 +1,294 @@
+/// The kind of a compile error. Each kind has a stable code that is printed with the error and
+/// can be looked up with `loxide --explain`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompileErrorKind {
+    InvalidAssignmentTarget,
+    ExpectedToken,
+    ExpectedExpression,
+    InvalidToken,
+    DuplicateVariable,
+    OwnInitializer,
+    ThisOutsideClass,
+    InvalidSuper,
+    TopLevelReturn,
+    InitializerMisuse,
+    SelfInheritance,
+    TooManyConstants,
+    TooManyVariables,
+    TooManyArguments,
+    JumpTooLarge,
+    NamedArgument,
+}
+
+impl CompileErrorKind {
+    pub const ALL: [CompileErrorKind; 16] = [
+        CompileErrorKind::InvalidAssignmentTarget,
+        CompileErrorKind::ExpectedToken,
+        CompileErrorKind::ExpectedExpression,
+        CompileErrorKind::InvalidToken,
+        CompileErrorKind::DuplicateVariable,
+        CompileErrorKind::OwnInitializer,
+        CompileErrorKind::ThisOutsideClass,
+        CompileErrorKind::InvalidSuper,
+        CompileErrorKind::TopLevelReturn,
+        CompileErrorKind::InitializerMisuse,
+        CompileErrorKind::SelfInheritance,
+        CompileErrorKind::TooManyConstants,
+        CompileErrorKind::TooManyVariables,
+        CompileErrorKind::TooManyArguments,
+        CompileErrorKind::JumpTooLarge,
+        CompileErrorKind::NamedArgument,
+    ];
+
+    //codes are never reused or renumbered, so new kinds go on the end
+    pub fn code(self) -> &'static str {
+        match self {
+            CompileErrorKind::InvalidAssignmentTarget => "E0001",
+            CompileErrorKind::ExpectedToken => "E0002",
+            CompileErrorKind::ExpectedExpression => "E0003",
+            CompileErrorKind::InvalidToken => "E0004",
+            CompileErrorKind::DuplicateVariable => "E0005",
+            CompileErrorKind::OwnInitializer => "E0006",
+            CompileErrorKind::ThisOutsideClass => "E0007",
+            CompileErrorKind::InvalidSuper => "E0008",
+            CompileErrorKind::TopLevelReturn => "E0009",
+            CompileErrorKind::InitializerMisuse => "E0010",
+            CompileErrorKind::SelfInheritance => "E0011",
+            CompileErrorKind::TooManyConstants => "E0012",
+            CompileErrorKind::TooManyVariables => "E0013",
+            CompileErrorKind::TooManyArguments => "E0014",
+            CompileErrorKind::JumpTooLarge => "E0015",
+            CompileErrorKind::NamedArgument => "E0016",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<CompileErrorKind> {
+        CompileErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn explanation(self) -> &'static str {
+        match self {
+            CompileErrorKind::InvalidAssignmentTarget => {
+                "\
+The left-hand side of `=` is not something that can be assigned to.
+
+Only variables, fields and indexed elements can be assigned:
+
+    a + b = c;  // error: `a + b` is a value, not a place
+
+Assign to a variable, a field or an index instead:
+
+    a = c;
+    point.x = c;
+    array[0] = c;
+
+`++` and `--` need a variable or a field:
+
+    ++f();  // error: a call's result is a value
+    i++;
+    point.x--;
+"
+            }
+            CompileErrorKind::ExpectedToken => {
+                "\
+The parser needed a particular token here, such as a `;`, a closing bracket or a name, and
+found something else. The message says which token was expected.
+
+    print 1  // error: Expect ';' after value.
+
+Add the missing token:
+
+    print 1;
+"
+            }
+            CompileErrorKind::ExpectedExpression => {
+                "\
+An expression was expected, but the next token can't start one.
+
+    var a = ;  // error: nothing to assign
+
+Write the value out, or leave the initializer off to start the variable as nil:
+
+    var a = 1;
+    var b;
+"
+            }
+            CompileErrorKind::InvalidToken => {
+                "\
+The scanner found text that isn't part of any Lox token, such as a stray character, a
+string with no closing quote or a backslash escape other than \\n, \\t, \\r, \\0, \\\" and \\\\.
+
+    var a = \"unfinished;  // error: Unterminated String.
+
+Remove the character, or close the string:
+
+    var a = \"unfinished\";
+"
+            }
+            CompileErrorKind::DuplicateVariable => {
+                "\
+A local variable was declared twice in the same block.
+
+    {
+      var a = 1;
+      var a = 2;  // error
+    }
+
+Assign to the existing variable, or open a new block to shadow it:
+
+    {
+      var a = 1;
+      a = 2;
+    }
+"
+            }
+            CompileErrorKind::OwnInitializer => {
+                "\
+A local variable was read in its own initializer, before it has a value.
+
+    var a = 1;
+    {
+      var a = a + 1;  // error: the inner `a` isn't ready yet
+    }
+
+Give the new variable a different name:
+
+    var a = 1;
+    {
+      var b = a + 1;
+    }
+"
+            }
+            CompileErrorKind::ThisOutsideClass => {
+                "\
+`this` was used outside a method, where there is no instance for it to refer to.
+
+    fun show() {
+      print this;  // error
+    }
+
+Make the function a method, or pass the instance in:
+
+    fun show(object) {
+      print object;
+    }
+"
+            }
+            CompileErrorKind::InvalidSuper => {
+                "\
+`super` was used where there is no superclass method to call: outside a class, in a class
+that doesn't inherit from anything, or in a static method.
+
+    class A {
+      method() {
+        super.method();  // error: A has no superclass
+      }
+    }
+
+Only use `super` in instance methods of a class declared with `<`:
+
+    class B < A {
+      method() {
+        super.method();
+      }
+    }
+"
+            }
+            CompileErrorKind::TopLevelReturn => {
+                "\
+`return` was used outside of any function.
+
+    return 1;  // error
+
+Move the code into a function, or use `exit()` to stop the script:
+
+    fun main() {
+      return 1;
+    }
+"
+            }
+            CompileErrorKind::InitializerMisuse => {
+                "\
+An initializer was used in a way that would stop it from returning the new instance: it
+returns a value, or it is declared as a getter.
+
+    class A {
+      init() {
+        return 1;  // error
+      }
+    }
+
+`init` always returns `this`; use a bare `return;` to leave early:
+
+    class A {
+      init() {
+        return;
+      }
+    }
+"
+            }
+            CompileErrorKind::SelfInheritance => {
+                "\
+A class was declared as its own superclass.
+
+    class A < A {}  // error
+
+Inherit from a different class, or drop the `<`:
+
+    class Base {}
+    class A < Base {}
+"
+            }
+            CompileErrorKind::TooManyConstants => {
+                "\
+A single function uses more than 256 distinct constants, the most one chunk can address.
+
+Split the function into smaller functions.
+"
+            }
+            CompileErrorKind::TooManyVariables => {
+                "\
+A function declares more than 256 local variables, or captures more than 256 variables from
+enclosing functions.
+
+Split the function into smaller functions, or group related values into an instance or
+array.
+"
+            }
+            CompileErrorKind::TooManyArguments => {
+                "\
+A call, function declaration or array literal has more than 255 arguments, parameters or
+elements.
+
+    print [1, 2, 3, /* ... */ 256];  // error
+
+Build large arrays up in a loop, and pass large groups of values as an array or instance.
+"
+            }
+            CompileErrorKind::JumpTooLarge => {
+                "\
+The body of a loop or conditional compiled to more bytecode than a jump can cross.
+
+Move part of the body into a function.
+"
+            }
+            CompileErrorKind::NamedArgument => {
+                "\
+A call with named arguments, which `--relaxed` allows, doesn't fit the function it calls.
+
+Arguments can only be named when calling a function declared with `fun` by name, in a scope
+where nothing else has taken that name since. Every parameter needs exactly one argument, and
+positional arguments come first:
+
+    fun area(width, height) { return width * height; }
+    area(height: 2, width: 3);  // ok
+    area(3, height: 2);         // ok
+    area(width: 3, 2);          // error: positional after named
+    area(3, width: 2);          // error: `width` given twice
+"
+            }
+        }
+    }
+}
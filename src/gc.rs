@@ -1,15 +1,15 @@
 use std::cell::{Cell, RefCell, UnsafeCell};
-use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
 
 use crate::object::ObjString;
+use crate::table::FxHashMap;
 
 struct GcState {
     allocations: Option<NonNull<GcBox<dyn Trace>>>,
-    interned_strings: HashMap<Box<str>, Gc<ObjString>>,
+    interned_strings: FxHashMap<Box<str>, Gc<ObjString>>,
 }
 
 impl GcState {
@@ -53,6 +53,26 @@ impl GcState {
     }
 }
 
+/// Frees everything that isn't reachable from a rooted `Gc`. Allocating already does this, so
+/// it's only needed to clean up after the last allocation, e.g. when a `VM` is dropped.
+pub fn collect_garbage() {
+    GC_STATE.with(|state| state.borrow_mut().collect_garbage());
+}
+
+/// How many objects are allocated on this thread.
+#[cfg(test)]
+pub fn allocations() -> usize {
+    GC_STATE.with(|state| {
+        let mut count = 0;
+        let mut current = state.borrow().allocations;
+        while let Some(allocation) = current {
+            count += 1;
+            current = unsafe { allocation.as_ref() }.next.get();
+        }
+        count
+    })
+}
+
 pub fn get_interned_string(string: &str) -> Option<Gc<ObjString>> {
     GC_STATE.with(|state| state.borrow().interned_strings.get(string).cloned())
 }
@@ -63,7 +83,7 @@ pub fn intern_string(key: Box<str>, value: Gc<ObjString>) {
 }
 
 thread_local! {
-    static GC_STATE: RefCell<GcState> = RefCell::new(GcState{allocations: None, interned_strings: HashMap::new()});
+    static GC_STATE: RefCell<GcState> = RefCell::new(GcState{allocations: None, interned_strings: FxHashMap::default()});
 }
 
 pub unsafe trait Trace {
@@ -149,6 +169,13 @@ impl BorrowFlag {
         self.0 & BorrowFlag::ROOT != 0
     }
 
+    fn set_rooted(self, rooted: bool) -> Self {
+        match rooted {
+            true => BorrowFlag(self.0 | Self::ROOT),
+            false => BorrowFlag(self.0 & !Self::ROOT),
+        }
+    }
+
     fn set_writing(self) -> Self {
         BorrowFlag(self.0 | Self::WRITING)
     }
@@ -239,6 +266,26 @@ unsafe impl<T: Trace> Trace for GcCell<T> {
             _ => unsafe { &*self.value.get() }.trace(),
         }
     }
+
+    //a cell moved into a `GcBox` is unrooted, and with it every `Gc` inside it, so what an
+    //object refers to is only kept alive by tracing from a root
+    fn root(&self) {
+        assert!(!self.flags.get().rooted(), "Can't root a GcCell twice!");
+        self.flags.set(self.flags.get().set_rooted(true));
+        match self.flags.get().borrowed() {
+            BorrowState::Writing => (),
+            _ => unsafe { &*self.value.get() }.root(),
+        }
+    }
+
+    fn unroot(&self) {
+        assert!(self.flags.get().rooted(), "Can't unroot a GcCell twice!");
+        self.flags.set(self.flags.get().set_rooted(false));
+        match self.flags.get().borrowed() {
+            BorrowState::Writing => (),
+            _ => unsafe { &*self.value.get() }.unroot(),
+        }
+    }
 }
 
 pub struct GcCellRef<'a, T: Trace> {
@@ -306,10 +353,18 @@ union Address<T> {
     ptr: *mut GcBox<GcCell<T>>,
 }
 
-unsafe fn clear_root_bit<T>(ptr: NonNull<GcBox<GcCell<T>>>) -> NonNull<GcBox<GcCell<T>>> {
+//the bits of a `Gc`'s pointer that aren't part of the address: bit 0 says whether the handle is
+//a root, and a nan-boxed `Value` keeps the NaN and the object's type in the top 16 bits and bits
+//1 and 2, which are always clear in a `GcBox`'s address
+#[cfg(not(feature = "nan-boxing"))]
+const FLAG_BITS: usize = 1;
+#[cfg(feature = "nan-boxing")]
+const FLAG_BITS: usize = 0xFFFF_0000_0000_0007;
+
+unsafe fn clear_bits<T>(ptr: NonNull<GcBox<GcCell<T>>>, bits: usize) -> NonNull<GcBox<GcCell<T>>> {
     let mut addr = Address { ptr: ptr.as_ptr() };
     unsafe {
-        addr.addr &= !1;
+        addr.addr &= !bits;
         NonNull::new_unchecked(addr.ptr)
     }
 }
@@ -344,7 +399,7 @@ impl<T: Trace> Gc<T> {
     }
 
     unsafe fn clear_root(&self) {
-        self.ptr.set(clear_root_bit(self.ptr.get()))
+        self.ptr.set(clear_bits(self.ptr.get(), 1))
     }
 
     fn rooted(&self) -> bool {
@@ -352,7 +407,14 @@ impl<T: Trace> Gc<T> {
     }
 
     fn inner(&self) -> &GcBox<GcCell<T>> {
-        unsafe { &*clear_root_bit(self.ptr.get()).as_ptr() }
+        unsafe { &*clear_bits(self.ptr.get(), FLAG_BITS).as_ptr() }
+    }
+
+    /// Whether both handles point at the same allocation, whatever it holds. This is also what
+    /// `==` on two `Gc`s means; it never looks at the values.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        //the root bit isn't part of the address
+        std::ptr::eq(this.inner(), other.inner())
     }
 
     pub fn borrow(&self) -> GcCellRef<T> {
@@ -371,8 +433,9 @@ impl<T: Trace> Gc<T> {
 impl<T: Trace> Clone for Gc<T> {
     fn clone(&self) -> Gc<T> {
         self.inner().root_inner();
+        //a copy taken out of a nan-boxed `Value` doesn't keep its tag
         let gc = Gc {
-            ptr: Cell::new(self.ptr.get()),
+            ptr: Cell::new(unsafe { clear_bits(self.ptr.get(), FLAG_BITS) }),
         };
         unsafe {
             gc.set_root();
@@ -383,7 +446,7 @@ impl<T: Trace> Clone for Gc<T> {
 
 impl<T: Trace> PartialEq for Gc<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.ptr.get() == other.ptr.get()
+        Gc::ptr_eq(self, other)
     }
 }
 
@@ -437,7 +500,7 @@ impl<T: Trace + Hash> Hash for Gc<T> {
     }
 }
 
-unsafe impl<K: Trace, V: Trace> Trace for std::collections::HashMap<K, V> {
+unsafe impl<K: Trace, V: Trace, S> Trace for std::collections::HashMap<K, V, S> {
     fn trace(&self) {
         for (k, v) in self.iter() {
             k.trace();
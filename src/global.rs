@@ -0,0 +1,38 @@
+use crate::table::FxHashMap;
+
+/// Global variable names get a slot the first time a compiler or VM using this table sees them.
+/// Compiled code refers to globals by slot, and a VM keeps its globals in a `Vec` indexed by
+/// slot, so a script has to be compiled with the table of the VM that runs it; `VM::compile`
+/// does that. The names are kept for error messages and disassembly.
+#[derive(Default)]
+pub struct GlobalSlots {
+    slots: FxHashMap<Box<str>, u16>,
+    names: Vec<Box<str>>,
+}
+
+impl GlobalSlots {
+    pub fn new() -> GlobalSlots {
+        GlobalSlots::default()
+    }
+
+    /// Returns the slot for `name`, assigning the next free one if it doesn't have one yet, or
+    /// `None` once every slot an operand can address is taken.
+    pub fn slot(&mut self, name: &str) -> Option<u16> {
+        if let Some(slot) = self.slots.get(name) {
+            return Some(*slot);
+        }
+        let slot = u16::try_from(self.names.len()).ok()?;
+        self.slots.insert(name.into(), slot);
+        self.names.push(name.into());
+        Some(slot)
+    }
+
+    pub fn name(&self, slot: u16) -> &str {
+        &self.names[slot as usize]
+    }
+
+    /// Every name that has a slot, in slot order.
+    pub fn names(&self) -> &[Box<str>] {
+        &self.names
+    }
+}
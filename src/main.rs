@@ -1,12 +1,17 @@
 mod chunk;
 mod compiler;
+mod diagnostic;
 mod gc;
+mod global;
+mod methods;
 mod test;
 mod object;
 mod scanner;
+mod table;
 mod value;
 mod vm;
 
+use std::io::IsTerminal;
 use vm::*;
 
 fn repl<StdOut, StdErr>(vm: &mut VM<StdOut, StdErr>)
@@ -14,6 +19,7 @@ where
     StdOut: std::io::Write,
     StdErr: std::io::Write,
 {
+    vm.set_repl_mode(true);
     let input = std::io::stdin();
     'repl: loop {
         let mut line = String::new();
@@ -24,6 +30,7 @@ where
                 }
                 match vm.interpret(line) {
                     Ok(()) => (),
+                    Err(InterpretError::Exit(code)) => std::process::exit(code),
                     Err(_) => break 'repl,
                 }
             }
@@ -32,32 +39,305 @@ where
     }
 }
 
-pub fn run_file<StdOut, StdErr>(vm: &mut VM<StdOut, StdErr>, file_path: String)
+/// Runs a script and returns the process exit code: whatever it passed to `exit`, otherwise 65
+/// for a compile error, 70 for a runtime error and 74 if the file couldn't be read.
+pub fn run_file<StdOut, StdErr>(vm: &mut VM<StdOut, StdErr>, file_path: String) -> i32
 where
     StdOut: std::io::Write,
     StdErr: std::io::Write,
 {
-    let file = std::fs::read_to_string(file_path.as_str());
-    match file {
-        Ok(source) => match vm.interpret(source) {
-            Ok(()) => (),
-            Err(_) => (),
-        },
-        Err(e) => eprintln!("could not read file {}: {}", file_path, e),
+    match std::fs::read(file_path.as_str()) {
+        Ok(bytes) => run_bytes(vm, bytes, file_path.as_str()),
+        Err(e) => {
+            eprintln!("could not read file {}: {}", file_path, e);
+            74
+        }
+    }
+}
+
+/// Reads a whole script from `input`, such as standard input, and runs it like `run_file`.
+pub fn run_reader<StdOut, StdErr>(
+    vm: &mut VM<StdOut, StdErr>,
+    input: &mut impl std::io::Read,
+) -> i32
+where
+    StdOut: std::io::Write,
+    StdErr: std::io::Write,
+{
+    let mut bytes = vec![];
+    match input.read_to_end(&mut bytes) {
+        Ok(_) => run_bytes(vm, bytes, "standard input"),
+        Err(e) => {
+            eprintln!("could not read standard input: {}", e);
+            74
+        }
+    }
+}
+
+fn run_bytes<StdOut, StdErr>(vm: &mut VM<StdOut, StdErr>, bytes: Vec<u8>, name: &str) -> i32
+where
+    StdOut: std::io::Write,
+    StdErr: std::io::Write,
+{
+    //compiled scripts are recognised by their header, whatever the file is called
+    let result = if bytes.starts_with(chunk::serialize::MAGIC) {
+        vm.interpret_bytecode(&bytes)
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(source) => vm.interpret(source),
+            Err(e) => {
+                eprintln!("could not read {}: {}", name, e);
+                return 74;
+            }
+        }
     };
+    match result {
+        Ok(()) => 0,
+        Err(InterpretError::Compile) => 65,
+        Err(InterpretError::Runtime) => 70,
+        Err(InterpretError::Exit(code)) => code,
+    }
+}
+
+/// Reads the source of the script at `file_path`, or all of `stdin` when the path is `-`. On
+/// failure the error is written to `err` and the exit code for it returned.
+pub fn read_source(
+    file_path: &str,
+    stdin: &mut impl std::io::Read,
+    err: &mut impl std::io::Write,
+) -> Result<String, i32> {
+    let mut source = String::new();
+    let result = if file_path == "-" {
+        stdin.read_to_string(&mut source).map(|_| source)
+    } else {
+        std::fs::read_to_string(file_path)
+    };
+    result.map_err(|e| {
+        match file_path {
+            "-" => writeln!(err, "could not read standard input: {}", e).ok(),
+            _ => writeln!(err, "could not read file {}: {}", file_path, e).ok(),
+        };
+        74
+    })
 }
 
+/// Runs a script, or standard input for `-`, then writes the disassembly of every function it
+/// compiled to `listing`.
+pub fn run_file_and_disassemble<StdOut, StdErr>(
+    vm: &mut VM<StdOut, StdErr>,
+    file_path: String,
+    listing: &mut impl std::io::Write,
+) -> i32
+where
+    StdOut: std::io::Write,
+    StdErr: std::io::Write,
+{
+    let code = if file_path == "-" {
+        run_reader(vm, &mut std::io::stdin().lock())
+    } else {
+        run_file(vm, file_path)
+    };
+    //after a compile error there's nothing to list
+    if let Some(function) = vm.last_script() {
+        chunk::disassemble_function(&function.borrow(), vm.global_slots(), listing);
+    }
+    code
+}
+
+/// Compiles `file_path` with `options` without running it and writes the bytecode to
+/// `output_path`, which `run_file` can later load in place of the source.
+pub fn compile_file(
+    file_path: String,
+    output_path: String,
+    options: compiler::CompilerOptions,
+    err: &mut impl std::io::Write,
+) -> i32 {
+    let source = match read_source(file_path.as_str(), &mut std::io::stdin().lock(), err) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+    let mut globals = global::GlobalSlots::new();
+    let function = match compiler::compile_with_options(source.as_str(), options, &mut globals, err) {
+        Ok(function) => function,
+        Err(_) => return 65,
+    };
+    let bytes = match chunk::serialize::serialize_function(&function.borrow(), &globals) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            writeln!(err, "could not serialize {}: {}", file_path, e).ok();
+            return 70;
+        }
+    };
+    match std::fs::write(output_path.as_str(), bytes) {
+        Ok(()) => 0,
+        Err(e) => {
+            writeln!(err, "could not write file {}: {}", output_path, e).ok();
+            74
+        }
+    }
+}
+
+/// Prints the long explanation of a compile error code such as `E0001`.
+pub fn explain(code: &str, out: &mut impl std::io::Write, err: &mut impl std::io::Write) -> i32 {
+    match diagnostic::CompileErrorKind::from_code(code) {
+        Some(kind) => {
+            write!(out, "{}", kind.explanation()).ok();
+            0
+        }
+        None => {
+            writeln!(err, "{} is not a known error code.", code).ok();
+            64
+        }
+    }
+}
+
+/// Compiles a script, or standard input for `-`, with `options` without running it and writes
+/// the disassembly of every function to `out`.
+pub fn dump_file(
+    file_path: String,
+    options: compiler::CompilerOptions,
+    out: &mut impl std::io::Write,
+    err: &mut impl std::io::Write,
+) -> i32 {
+    let source = match read_source(file_path.as_str(), &mut std::io::stdin().lock(), err) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+    let mut globals = global::GlobalSlots::new();
+    match compiler::compile_with_options(source.as_str(), options, &mut globals, err) {
+        Ok(function) => {
+            chunk::disassemble_function(&function.borrow(), &globals, out);
+            0
+        }
+        Err(_) => 65,
+    }
+}
+
+/// Compiles a script, or standard input for `-`, with `options` without running it and writes
+/// size figures for every function to `out`.
+pub fn bytecode_stats_file(
+    file_path: String,
+    options: compiler::CompilerOptions,
+    out: &mut impl std::io::Write,
+    err: &mut impl std::io::Write,
+) -> i32 {
+    let source = match read_source(file_path.as_str(), &mut std::io::stdin().lock(), err) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+    let mut globals = global::GlobalSlots::new();
+    let function = match compiler::compile_with_options(source.as_str(), options, &mut globals, err) {
+        Ok(function) => function,
+        Err(_) => return 65,
+    };
+    let stats = chunk::stats::function_stats(&function.borrow());
+    match stats {
+        Ok(stats) => {
+            chunk::stats::write_stats(&stats, out);
+            0
+        }
+        Err(e) => {
+            writeln!(err, "could not analyze {}: {:?}", file_path, e).ok();
+            70
+        }
+    }
+}
+
+const USAGE: &str = "Usage: loxide [--dump | --bytecode-stats | --disasm-after-run] [--strict] [--relaxed] [--snippets] [--warn-unused] [--warn-unreachable] [--optimize] [--infer-semicolons] [--backtrace] [--sandbox] [path | -]
+       loxide --compile [--strict] [--relaxed] [--snippets] [--warn-unused] [--warn-unreachable] [--optimize] [--infer-semicolons] path
+       loxide --explain CODE
+       loxide --version";
+
 fn main() {
     let _ = START_TIME.with(|start_time| start_time.get().elapsed());
     let mut stdout = std::io::stdout();
     let mut stderr = std::io::stderr();
     let mut vm = VM::new(&mut stdout, &mut stderr);
-    let mut args = std::env::args();
-    if args.len() == 1 {
-        repl(&mut vm);
-    } else if args.len() == 2 {
-        run_file(&mut vm, args.nth(1).unwrap());
-    } else {
-        eprintln!("Usage: loxide [path]");
+    if std::env::var_os("LOXIDE_TRACE_EXECUTION").is_some() {
+        vm.set_trace_execution(true);
+    }
+    let mut disasm_after_run = false;
+    let mut dump = false;
+    let mut compile = false;
+    let mut bytecode_stats = false;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => match args.next() {
+                Some(code) => {
+                    let code = explain(code.as_str(), &mut std::io::stdout(), &mut std::io::stderr());
+                    std::process::exit(code);
+                }
+                None => {
+                    eprintln!("Usage: loxide --explain CODE");
+                    std::process::exit(64);
+                }
+            },
+            "--version" => {
+                println!("loxide {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            "--disasm-after-run" => disasm_after_run = true,
+            "--dump" | "--disassemble" => dump = true,
+            "--compile" => compile = true,
+            "--bytecode-stats" => bytecode_stats = true,
+            "--strict" => vm.set_strict(true),
+            "--relaxed" => vm.set_relaxed(true),
+            "--snippets" => vm.set_snippets(true),
+            "--warn-unused" => vm.set_warn_unused(true),
+            "--warn-unreachable" => vm.set_warn_unreachable(true),
+            "--optimize" => vm.set_optimize(true),
+            "--infer-semicolons" => vm.set_infer_semicolons(true),
+            "--backtrace" => vm.set_backtrace(usize::MAX),
+            "--sandbox" => vm.sandbox_defaults(),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(64);
+            }
+        }
+    }
+    //a script piped in with no path runs as a whole, like `-`, rather than line by line
+    if path.is_none() && !std::io::stdin().is_terminal() {
+        path = Some("-".to_string());
+    }
+    match path {
+        None => repl(&mut vm),
+        //the bytecode is written next to the source, so there has to be a file
+        Some(path) if compile && path == "-" => {
+            eprintln!("{}", USAGE);
+            std::process::exit(64);
+        }
+        Some(path) if compile => {
+            let output = std::path::Path::new(path.as_str()).with_extension("loxc");
+            let output = output.to_string_lossy().to_string();
+            let options = vm.compiler_options();
+            let code = compile_file(path, output, options, &mut std::io::stderr());
+            std::process::exit(code);
+        }
+        Some(path) if dump => {
+            let options = vm.compiler_options();
+            let code = dump_file(path, options, &mut std::io::stdout(), &mut std::io::stderr());
+            std::process::exit(code);
+        }
+        Some(path) if bytecode_stats => {
+            let options = vm.compiler_options();
+            let code =
+                bytecode_stats_file(path, options, &mut std::io::stdout(), &mut std::io::stderr());
+            std::process::exit(code);
+        }
+        Some(path) if disasm_after_run => {
+            let code = run_file_and_disassemble(&mut vm, path, &mut std::io::stderr());
+            std::process::exit(code);
+        }
+        Some(path) if path == "-" => {
+            let code = run_reader(&mut vm, &mut std::io::stdin().lock());
+            std::process::exit(code);
+        }
+        Some(path) => {
+            let code = run_file(&mut vm, path);
+            std::process::exit(code);
+        }
     }
 }
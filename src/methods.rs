@@ -0,0 +1,260 @@
+use crate::object::{MapKey, NativeError, NativeFn, ObjArray, ObjString};
+use crate::value::value::Value;
+use crate::vm::{arity_error, native_error};
+
+//Methods of the built-in types. Each is a native that gets the receiver as its first argument,
+//followed by the arguments it was called with. Strings are indexed by character, not by byte,
+//so multi-byte UTF-8 text can't be split in the middle of a character.
+
+/// The built-in method `name` of strings, if there is one.
+pub fn string_method(name: &str) -> Option<NativeFn> {
+    match name {
+        "substring" => Some(substring),
+        "indexOf" => Some(index_of),
+        "toUpper" => Some(to_upper),
+        "toLower" => Some(to_lower),
+        "trim" => Some(trim),
+        "chars" => Some(chars),
+        "bytes" => Some(bytes),
+        _ => None,
+    }
+}
+
+/// The built-in method `name` of arrays, if there is one.
+pub fn array_method(name: &str) -> Option<NativeFn> {
+    match name {
+        "push" => Some(push),
+        "pop" => Some(pop),
+        "insert" => Some(insert),
+        "remove" => Some(remove),
+        _ => None,
+    }
+}
+
+/// The built-in method `name` of maps, if there is one.
+pub fn map_method(name: &str) -> Option<NativeFn> {
+    match name {
+        "contains" => Some(contains),
+        "keys" => Some(keys),
+        "values" => Some(values),
+        _ => None,
+    }
+}
+
+fn new_string(string: String) -> Value {
+    Value::string(ObjString::new(string))
+}
+
+//a character count or position; rejects fractions, negatives and NaN
+fn index_argument(value: &Value) -> Option<usize> {
+    match value.as_number() {
+        Ok(number) if number >= 0.0 && number.fract() == 0.0 => Some(number as usize),
+        _ => None,
+    }
+}
+
+fn substring(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string, start, length] if string.is_string() => {
+            let string = string.as_string().unwrap();
+            let (start, length) = match (index_argument(start), index_argument(length)) {
+                (Some(start), Some(length)) => (start, length),
+                _ => {
+                    return Err(native_error(
+                        "Substring start and length must be non-negative integers.",
+                    ))
+                }
+            };
+            let string = string.borrow();
+            if start.saturating_add(length) > string.char_len() {
+                return Err(native_error("Substring out of range."));
+            }
+            let substring = string.as_str().chars().skip(start).take(length).collect();
+            Ok(new_string(substring))
+        }
+        args => Err(arity_error(2, args.len() - 1)),
+    }
+}
+
+//the character position of the first occurrence, or -1
+fn index_of(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string, pattern] if string.is_string() && pattern.is_string() => {
+            let string = string.as_string().unwrap();
+            let pattern = pattern.as_string().unwrap();
+            let string = string.borrow();
+            let index = match string.as_str().find(pattern.borrow().as_str()) {
+                Some(byte) => string.as_str()[..byte].chars().count() as i64,
+                None => -1,
+            };
+            Ok(Value::int(index))
+        }
+        [_, _] => Err(native_error("Argument to 'indexOf' must be a string.")),
+        args => Err(arity_error(1, args.len() - 1)),
+    }
+}
+
+fn to_upper(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            Ok(new_string(string.as_string().unwrap().borrow().as_str().to_uppercase()))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+fn to_lower(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            Ok(new_string(string.as_string().unwrap().borrow().as_str().to_lowercase()))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+fn trim(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            Ok(new_string(string.as_string().unwrap().borrow().as_str().trim().to_string()))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+//one single-character string for each Unicode scalar value
+fn chars(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            let string = string.as_string().unwrap();
+            let chars = string
+                .borrow()
+                .as_str()
+                .chars()
+                .map(|c| new_string(c.to_string()))
+                .collect();
+            Ok(Value::array(ObjArray::new(chars)))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+//the UTF-8 encoding as numbers from 0 to 255
+fn bytes(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            let string = string.as_string().unwrap();
+            let bytes = string
+                .borrow()
+                .as_str()
+                .bytes()
+                .map(|byte| Value::int(byte as i64))
+                .collect();
+            Ok(Value::array(ObjArray::new(bytes)))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+//an index below `limit`, checked like `a[i]`
+fn array_position(value: &Value, limit: usize) -> Result<usize, NativeError> {
+    match value.as_number() {
+        Ok(index) if index.fract() == 0.0 => {
+            if index < 0.0 || index >= limit as f64 {
+                Err(native_error("Array index out of bounds."))
+            } else {
+                Ok(index as usize)
+            }
+        }
+        _ => Err(native_error("Array index must be an integer.")),
+    }
+}
+
+fn push(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, value] if array.is_array() => {
+            let array = array.as_array().unwrap();
+            array.borrow_mut().elements.push(value.clone());
+            Ok(Value::nil())
+        }
+        args => Err(arity_error(1, args.len() - 1)),
+    }
+}
+
+//removes and returns the last element
+fn pop(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array] if array.is_array() => {
+            array
+                .as_array()
+                .unwrap()
+                .borrow_mut()
+                .elements
+                .pop()
+                .ok_or_else(|| native_error("Can't pop from an empty array."))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+//moves the element at `index` and everything after it up one; an index of the length appends
+fn insert(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, index, value] if array.is_array() => {
+            let array = array.as_array().unwrap();
+            let len = array.borrow().elements.len();
+            let index = array_position(index, len + 1)?;
+            array.borrow_mut().elements.insert(index, value.clone());
+            Ok(Value::nil())
+        }
+        args => Err(arity_error(2, args.len() - 1)),
+    }
+}
+
+//removes and returns the element at `index`, moving everything after it down one
+fn remove(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, index] if array.is_array() => {
+            let array = array.as_array().unwrap();
+            let len = array.borrow().elements.len();
+            let index = array_position(index, len)?;
+            let removed = array.borrow_mut().elements.remove(index);
+            Ok(removed)
+        }
+        args => Err(arity_error(1, args.len() - 1)),
+    }
+}
+
+fn contains(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [map, key] if map.is_map() => {
+            let map = map.as_map().unwrap();
+            let key = MapKey::new(key.clone()).map_err(|_| {
+                native_error("Map key must be nil, a boolean, a number, or a string.")
+            })?;
+            let contains = map.borrow().entries.get(&key).is_some();
+            Ok(Value::bool_(contains))
+        }
+        args => Err(arity_error(1, args.len() - 1)),
+    }
+}
+
+//in the order they were first inserted, like `values`
+fn keys(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [map] if map.is_map() => {
+            Ok(Value::array(ObjArray::new(map.as_map().unwrap().borrow().keys())))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
+
+fn values(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [map] if map.is_map() => {
+            let map = map.as_map().unwrap();
+            let values = map.borrow().entries.iter().map(|(_, value)| value.clone()).collect();
+            Ok(Value::array(ObjArray::new(values)))
+        }
+        args => Err(arity_error(0, args.len() - 1)),
+    }
+}
@@ -1,14 +1,18 @@
-use std::{collections::HashMap, fmt::Display};
+use std::fmt::Display;
 
 use crate::{
     chunk::Chunk,
     gc::{Gc, Trace},
-    value::value::Value,
+    table::{FxHashMap, Table},
+    value::{value::Value, CastError, ValueType},
+    vm::Host,
 };
 
 #[repr(C)]
 pub struct ObjString {
     pub string: Box<str>,
+    //computed once when the string is created, so table lookups don't rehash the contents
+    hash: u64,
 }
 
 impl ObjString {
@@ -17,7 +21,8 @@ impl ObjString {
             Some(string) => string,
             None => {
                 let string = string.into_boxed_str();
-                let gc_string = Gc::new(ObjString {string: string.clone() });
+                let hash = hash_string(&string);
+                let gc_string = Gc::new(ObjString {string: string.clone(), hash });
                 crate::gc::intern_string(string, gc_string.clone());
                 gc_string
             }
@@ -27,6 +32,24 @@ impl ObjString {
     pub fn as_str(&self) -> &str {
         self.string.as_ref()
     }
+    /// The length in Unicode scalar values, which is what Lox means by a string's length.
+    pub fn char_len(&self) -> usize {
+        self.string.chars().count()
+    }
+    /// The length of the UTF-8 encoding, which is larger for text outside ASCII.
+    pub fn byte_len(&self) -> usize {
+        self.string.len()
+    }
+}
+
+//FNV-1a, as in clox
+fn hash_string(string: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in string.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
 }
 
 impl Display for ObjString {
@@ -45,7 +68,7 @@ impl Eq for ObjString {}
 
 impl std::hash::Hash for ObjString {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.string.hash(state);
+        state.write_u64(self.hash);
     }
 }
 
@@ -59,7 +82,6 @@ unsafe impl Trace for ObjString {
 pub struct ObjUpvalue {
     pub location: *mut Value,
     pub closed: Value,
-    pub next: Option<Gc<ObjUpvalue>>,
 }
 
 impl ObjUpvalue {
@@ -67,13 +89,8 @@ impl ObjUpvalue {
         Gc::new(ObjUpvalue {
             location,
             closed: Value::nil(),
-            next: None,
         })
     }
-
-    pub fn add_next(&mut self, next: Gc<ObjUpvalue>) {
-        self.next = Some(next);
-    }
 }
 
 impl Display for ObjUpvalue {
@@ -91,29 +108,15 @@ unsafe impl Trace for ObjUpvalue {
                 (&*self.location).trace();
             }
         }
-        self.next.as_ref().map(|x| x.trace());
     }
 
+    //an open upvalue's value belongs to the stack, which keeps it rooted itself
     fn root(&self) {
-        if self.location.is_null() {
-            self.closed.root();
-        } else {
-            unsafe {
-                (&*self.location).root();
-            }
-        }
-        self.next.as_ref().map(|x| x.root());
+        self.closed.root();
     }
 
     fn unroot(&self) {
-        if self.location.is_null() {
-            self.closed.unroot();
-        } else {
-            unsafe {
-                (&*self.location).unroot();
-            }
-        }
-        self.next.as_ref().map(|x| x.unroot());
+        self.closed.unroot();
     }
 }
 
@@ -121,6 +124,9 @@ unsafe impl Trace for ObjUpvalue {
 pub struct ObjFunction {
     pub arity: usize,
     pub upvalue_count: usize,
+    //the most locals, parameters included, that are in scope at once
+    pub local_count: usize,
+    pub is_getter: bool,
     pub chunk: Gc<Chunk>,
     pub name: Option<Gc<ObjString>>,
 }
@@ -130,6 +136,8 @@ impl ObjFunction {
         Gc::new(ObjFunction {
             arity: 0,
             upvalue_count: 0,
+            local_count: 0,
+            is_getter: false,
             name,
             chunk: Gc::new(Chunk::new()),
         })
@@ -164,6 +172,10 @@ unsafe impl Trace for ObjFunction {
 pub struct ObjClosure {
     pub function: Gc<ObjFunction>,
     pub upvalues: Vec<Gc<ObjUpvalue>>,
+    /// The class whose body lexically contains this closure. `OpCode::Method` sets it on methods,
+    /// and closures created while a method runs inherit it, so `super` inside them resolves
+    /// against this class's superclass no matter which subclass the method was inherited into.
+    pub class: Option<Gc<ObjClass>>,
 }
 
 impl ObjClosure {
@@ -171,6 +183,7 @@ impl ObjClosure {
         Gc::new(ObjClosure {
             function,
             upvalues: vec![], /*vec![std::ptr::null_mut(); upvalue_count]*/
+            class: None,
         })
     }
 }
@@ -185,30 +198,83 @@ unsafe impl Trace for ObjClosure {
     fn trace(&self) {
         self.function.trace();
         self.upvalues.trace();
+        if let Some(class) = &self.class {
+            class.trace();
+        }
     }
     fn root(&self) {
         self.function.root();
         self.upvalues.root();
+        if let Some(class) = &self.class {
+            class.root();
+        }
     }
     fn unroot(&self) {
         self.function.unroot();
         self.upvalues.unroot();
+        if let Some(class) = &self.class {
+            class.unroot();
+        }
     }
 }
 
 #[repr(C)]
 pub struct ObjClass {
     pub name: Gc<ObjString>,
-    pub methods: HashMap<Gc<ObjString>, Gc<ObjClosure>>,
+    pub methods: FxHashMap<Gc<ObjString>, Gc<ObjClosure>>,
+    /// Methods declared with a `class` prefix, called on the class itself with `this` bound to it.
+    pub static_methods: FxHashMap<Gc<ObjString>, Gc<ObjClosure>>,
+    pub superclass: Option<Gc<ObjClass>>,
 }
 
 impl ObjClass {
     pub fn new(name: Gc<ObjString>) -> Gc<ObjClass> {
         Gc::new(ObjClass {
             name,
-            methods: HashMap::new(),
+            methods: FxHashMap::default(),
+            static_methods: FxHashMap::default(),
+            superclass: None,
         })
     }
+
+    /// Finds the method `name` declared by `class` or by the nearest superclass that has one.
+    pub fn find_method(class: &Gc<ObjClass>, name: &Gc<ObjString>) -> Option<Gc<ObjClosure>> {
+        ObjClass::find_in_chain(class, |class| class.methods.get(name).cloned())
+    }
+
+    pub fn find_static_method(
+        class: &Gc<ObjClass>,
+        name: &Gc<ObjString>,
+    ) -> Option<Gc<ObjClosure>> {
+        ObjClass::find_in_chain(class, |class| class.static_methods.get(name).cloned())
+    }
+
+    fn find_in_chain(
+        class: &Gc<ObjClass>,
+        lookup: impl Fn(&ObjClass) -> Option<Gc<ObjClosure>>,
+    ) -> Option<Gc<ObjClosure>> {
+        let mut current = Some(class.clone());
+        while let Some(class) = current {
+            let class = class.borrow();
+            if let Some(method) = lookup(&class) {
+                return Some(method);
+            }
+            current = class.superclass.clone();
+        }
+        None
+    }
+
+    /// Whether `ancestor` is `class` itself or somewhere in its chain of superclasses.
+    pub fn is_subclass(class: &Gc<ObjClass>, ancestor: &Gc<ObjClass>) -> bool {
+        let mut current = Some(class.clone());
+        while let Some(class) = current {
+            if class == *ancestor {
+                return true;
+            }
+            current = class.borrow().superclass.clone();
+        }
+        false
+    }
 }
 
 impl Display for ObjClass {
@@ -221,28 +287,43 @@ unsafe impl Trace for ObjClass {
     fn trace(&self) {
         self.name.trace();
         self.methods.trace();
+        self.static_methods.trace();
+        if let Some(superclass) = &self.superclass {
+            superclass.trace();
+        }
     }
     fn root(&self) {
         self.name.root();
         self.methods.root();
+        self.static_methods.root();
+        if let Some(superclass) = &self.superclass {
+            superclass.root();
+        }
     }
     fn unroot(&self) {
         self.name.unroot();
         self.methods.unroot();
+        self.static_methods.unroot();
+        if let Some(superclass) = &self.superclass {
+            superclass.unroot();
+        }
     }
 }
 
 #[repr(C)]
 pub struct ObjInstance {
     pub class: Gc<ObjClass>,
-    pub fields: HashMap<Gc<ObjString>, Value>,
+    pub fields: Table<Gc<ObjString>, Value>,
+    //set by `freeze`; its fields can still be read but no longer set
+    pub frozen: bool,
 }
 
 impl ObjInstance {
     pub fn new(class: Gc<ObjClass>) -> Gc<ObjInstance> {
         Gc::new(ObjInstance {
             class,
-            fields: HashMap::new(),
+            fields: Table::new(),
+            frozen: false,
         })
     }
 }
@@ -303,14 +384,53 @@ unsafe impl Trace for ObjBoundMethod {
     }
 }
 
+pub enum NativeError {
+    //reported like any other runtime error
+    Runtime(String),
+    //stops the interpreter with this exit code
+    Exit(i32),
+}
+
+/// A native function gets its arguments and returns either a value or how it failed.
+pub type NativeFn = fn(*mut [Value]) -> Result<Value, NativeError>;
+
+/// A native that also gets the VM running it, to call back into Lox code.
+pub type HostFn = fn(&mut dyn Host, *mut [Value]) -> Result<Value, NativeError>;
+
+#[derive(Clone, Copy)]
+pub enum NativeFunction {
+    Plain(NativeFn),
+    Host(HostFn),
+}
+
 #[repr(C)]
 pub struct ObjNative {
-    pub function: fn(*mut [Value]) -> Value,
+    pub function: NativeFunction,
+    //set for a built-in method read off a value without calling it; the call passes it to
+    //`function` ahead of the arguments
+    pub receiver: Option<Value>,
 }
 
 impl ObjNative {
-    pub fn new(function: fn(*mut [Value]) -> Value) -> Gc<ObjNative> {
-        Gc::new(ObjNative { function })
+    pub fn new(function: NativeFn) -> Gc<ObjNative> {
+        Gc::new(ObjNative {
+            function: NativeFunction::Plain(function),
+            receiver: None,
+        })
+    }
+
+    pub fn host(function: HostFn) -> Gc<ObjNative> {
+        Gc::new(ObjNative {
+            function: NativeFunction::Host(function),
+            receiver: None,
+        })
+    }
+
+    pub fn bound(function: NativeFn, receiver: Value) -> Gc<ObjNative> {
+        Gc::new(ObjNative {
+            function: NativeFunction::Plain(function),
+            receiver: Some(receiver),
+        })
     }
 }
 
@@ -321,7 +441,181 @@ impl Display for ObjNative {
 }
 
 unsafe impl Trace for ObjNative {
-    fn trace(&self) {}
-    fn root(&self) {}
-    fn unroot(&self) {}
+    fn trace(&self) {
+        if let Some(receiver) = &self.receiver {
+            receiver.trace();
+        }
+    }
+    fn root(&self) {
+        if let Some(receiver) = &self.receiver {
+            receiver.root();
+        }
+    }
+    fn unroot(&self) {
+        if let Some(receiver) = &self.receiver {
+            receiver.unroot();
+        }
+    }
+}
+
+#[repr(C)]
+pub struct ObjArray {
+    pub elements: Vec<Value>,
+}
+
+impl ObjArray {
+    pub fn new(elements: Vec<Value>) -> Gc<ObjArray> {
+        Gc::new(ObjArray { elements })
+    }
+}
+
+impl Display for ObjArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[")?;
+        for (i, element) in self.elements.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            element.fmt(f)?;
+        }
+        f.write_str("]")
+    }
+}
+
+unsafe impl Trace for ObjArray {
+    fn trace(&self) {
+        self.elements.trace();
+    }
+    fn root(&self) {
+        self.elements.root();
+    }
+    fn unroot(&self) {
+        self.elements.unroot();
+    }
+}
+
+/// A value usable as a map key. Only nil, booleans, numbers and strings are hashable;
+/// numbers are compared by their bits so NaN can be found again, even though `nan == nan` is false.
+#[derive(Clone)]
+pub struct MapKey(Value);
+
+impl MapKey {
+    pub fn new(value: Value) -> Result<MapKey, CastError> {
+        match value.value_type() {
+            ValueType::Nil if value.is_nil() => Ok(MapKey(value)),
+            ValueType::Bool | ValueType::String => Ok(MapKey(value)),
+            #[cfg(feature = "integers")]
+            ValueType::Number if value.as_int().is_ok() => Ok(MapKey(value)),
+            ValueType::Number => {
+                let number = value.as_number().unwrap();
+                //a whole number is the same key as the integer it equals
+                #[cfg(feature = "integers")]
+                if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+                    return Ok(MapKey(Value::int(number as i64)));
+                }
+                //0 and -0 are equal, so they have to be the same key
+                //every NaN is the same key, whatever its sign or payload
+                let number = if number == 0.0 {
+                    0.0
+                } else if number.is_nan() {
+                    f64::NAN
+                } else {
+                    number
+                };
+                Ok(MapKey(Value::number(number)))
+            }
+            _ => Err(CastError),
+        }
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.0.value_type(), other.0.value_type()) {
+            #[cfg(feature = "integers")]
+            (ValueType::Number, ValueType::Number)
+                if self.0.as_int().is_ok() || other.0.as_int().is_ok() =>
+            {
+                self.0 == other.0
+            }
+            (ValueType::Number, ValueType::Number) => {
+                self.0.as_number().unwrap().to_bits() == other.0.as_number().unwrap().to_bits()
+            }
+            _ => self.0 == other.0,
+        }
+    }
+}
+
+impl Eq for MapKey {}
+
+impl std::hash::Hash for MapKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let value_type = self.0.value_type();
+        std::mem::discriminant(&value_type).hash(state);
+        match value_type {
+            ValueType::Bool => self.0.as_bool().unwrap().hash(state),
+            #[cfg(feature = "integers")]
+            ValueType::Number if self.0.as_int().is_ok() => self.0.as_int().unwrap().hash(state),
+            ValueType::Number => self.0.as_number().unwrap().to_bits().hash(state),
+            ValueType::String => self.0.as_string().unwrap().hash(state),
+            _ => (),
+        }
+    }
+}
+
+unsafe impl Trace for MapKey {
+    fn trace(&self) {
+        self.0.trace();
+    }
+    fn root(&self) {
+        self.0.root();
+    }
+    fn unroot(&self) {
+        self.0.unroot();
+    }
+}
+
+#[repr(C)]
+pub struct ObjMap {
+    pub entries: Table<MapKey, Value>,
+}
+
+impl ObjMap {
+    pub fn new(entries: Table<MapKey, Value>) -> Gc<ObjMap> {
+        Gc::new(ObjMap { entries })
+    }
+
+    /// The keys in the order they were first inserted.
+    pub fn keys(&self) -> Vec<Value> {
+        self.entries.iter().map(|(key, _)| key.value().clone()).collect()
+    }
+}
+
+impl Display for ObjMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: {}", key.value(), value)?;
+        }
+        f.write_str("}")
+    }
+}
+
+unsafe impl Trace for ObjMap {
+    fn trace(&self) {
+        self.entries.trace();
+    }
+    fn root(&self) {
+        self.entries.root();
+    }
+    fn unroot(&self) {
+        self.entries.unroot();
+    }
 }
@@ -6,7 +6,10 @@ pub enum TokenKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
     Plus,
@@ -22,27 +25,41 @@ pub enum TokenKind {
     GreaterEqual,
     Less,
     LessEqual,
+    DotDot,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PlusPlus,
+    MinusMinus,
+    QuestionQuestion,
     // Literals.
     Identifier,
     String,
     Number,
     // Keywords.
     And,
+    Catch,
     Class,
     Else,
     False,
     For,
     Fun,
     If,
+    In,
+    Is,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
+    Xor,
     Error,
     EOF,
 }
@@ -89,6 +106,35 @@ fn check_keyword(string: &str, keyword: &str, kind: TokenKind) -> TokenKind {
     }
 }
 
+//the character an escape sequence such as `\n` stands for, given the character after the backslash
+fn unescape(c: char) -> Option<char> {
+    match c {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '"' | '\\' => Some(c),
+        _ => None,
+    }
+}
+
+/// The text of a string literal token: the quotes are removed and, unless it's a raw string,
+/// escape sequences are replaced. The scanner has already rejected invalid escapes.
+pub fn string_value(lexeme: &str) -> String {
+    if let Some(raw) = lexeme.strip_prefix('r') {
+        return raw[1..raw.len() - 1].to_string();
+    }
+    let mut value = String::with_capacity(lexeme.len());
+    let mut chars = lexeme[1..lexeme.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next().and_then(unescape)),
+            c => value.push(c),
+        }
+    }
+    value
+}
+
 #[derive(Clone)]
 pub struct Scanner<'a> {
     string: &'a str,
@@ -203,7 +249,14 @@ impl<'a> Scanner<'a> {
             None => TokenKind::Identifier,
             Some(c) => match c {
                 'a' => check_keyword(chars.as_str(), "nd", TokenKind::And),
-                'c' => check_keyword(chars.as_str(), "lass", TokenKind::Class),
+                'c' => match chars.next() {
+                    None => TokenKind::Identifier,
+                    Some(c) => match c {
+                        'a' => check_keyword(chars.as_str(), "tch", TokenKind::Catch),
+                        'l' => check_keyword(chars.as_str(), "ass", TokenKind::Class),
+                        _ => TokenKind::Identifier,
+                    },
+                },
                 'e' => check_keyword(chars.as_str(), "lse", TokenKind::Else),
                 'f' => match chars.next() {
                     None => TokenKind::Identifier,
@@ -214,7 +267,15 @@ impl<'a> Scanner<'a> {
                         _ => TokenKind::Identifier,
                     },
                 },
-                'i' => check_keyword(chars.as_str(), "f", TokenKind::If),
+                'i' => match chars.next() {
+                    None => TokenKind::Identifier,
+                    Some(c) => match c {
+                        'f' => check_keyword(chars.as_str(), "", TokenKind::If),
+                        'n' => check_keyword(chars.as_str(), "", TokenKind::In),
+                        's' => check_keyword(chars.as_str(), "", TokenKind::Is),
+                        _ => TokenKind::Identifier,
+                    },
+                },
                 'n' => check_keyword(chars.as_str(), "il", TokenKind::Nil),
                 'o' => check_keyword(chars.as_str(), "r", TokenKind::Or),
                 'p' => check_keyword(chars.as_str(), "rint", TokenKind::Print),
@@ -223,13 +284,28 @@ impl<'a> Scanner<'a> {
                 't' => match chars.next() {
                     None => TokenKind::Identifier,
                     Some(c) => match c {
-                        'h' => check_keyword(chars.as_str(), "is", TokenKind::This),
-                        'r' => check_keyword(chars.as_str(), "ue", TokenKind::True),
+                        'h' => match chars.next() {
+                            None => TokenKind::Identifier,
+                            Some(c) => match c {
+                                'i' => check_keyword(chars.as_str(), "s", TokenKind::This),
+                                'r' => check_keyword(chars.as_str(), "ow", TokenKind::Throw),
+                                _ => TokenKind::Identifier,
+                            },
+                        },
+                        'r' => match chars.next() {
+                            None => TokenKind::Identifier,
+                            Some(c) => match c {
+                                'u' => check_keyword(chars.as_str(), "e", TokenKind::True),
+                                'y' => check_keyword(chars.as_str(), "", TokenKind::Try),
+                                _ => TokenKind::Identifier,
+                            },
+                        },
                         _ => TokenKind::Identifier,
                     },
                 },
                 'v' => check_keyword(chars.as_str(), "ar", TokenKind::Var),
                 'w' => check_keyword(chars.as_str(), "hile", TokenKind::While),
+                'x' => check_keyword(chars.as_str(), "or", TokenKind::Xor),
                 _ => TokenKind::Identifier,
             },
         }
@@ -279,15 +355,29 @@ impl<'a> Scanner<'a> {
         self.make_token(TokenKind::Number)
     }
 
-    fn string(&mut self) -> Token<'a> {
+    //a raw string (`r"..."`) takes backslashes literally, so it can't contain a quote
+    fn string(&mut self, raw: bool) -> Token<'a> {
+        let mut valid = true;
         loop {
             match self.advance() {
                 None => return self.error_token("Unterminated String."),
+                Some('\\') if !raw => match self.advance() {
+                    None => return self.error_token("Unterminated String."),
+                    Some(c) => {
+                        if c == '\n' {
+                            self.line += 1;
+                        }
+                        valid &= unescape(c).is_some();
+                    }
+                },
                 Some(c) => {
                     if c == '\n' {
                         self.line += 1;
-                    } else if c == '"' {
+                    } else if c == '"' && valid {
                         return self.make_token(TokenKind::String);
+                    } else if c == '"' {
+                        //reported once the whole string is consumed, so scanning resumes after it
+                        return self.error_token("Invalid escape sequence.");
                     }
                 }
             }
@@ -305,14 +395,56 @@ impl<'a> Scanner<'a> {
                 ')' => self.make_token(TokenKind::RightParen),
                 '{' => self.make_token(TokenKind::LeftBrace),
                 '}' => self.make_token(TokenKind::RightBrace),
+                '[' => self.make_token(TokenKind::LeftBracket),
+                ']' => self.make_token(TokenKind::RightBracket),
                 ';' => self.make_token(TokenKind::Semicolon),
                 ',' => self.make_token(TokenKind::Comma),
-                '.' => self.make_token(TokenKind::Dot),
-                '-' => self.make_token(TokenKind::Minus),
-                '+' => self.make_token(TokenKind::Plus),
-                '/' => self.make_token(TokenKind::Slash),
-                '*' => self.make_token(TokenKind::Star),
-                '"' => self.string(),
+                ':' => self.make_token(TokenKind::Colon),
+                '.' => {
+                    let kind = if self.match_char('.') {
+                        TokenKind::DotDot
+                    } else {
+                        TokenKind::Dot
+                    };
+                    self.make_token(kind)
+                }
+                '-' => {
+                    let kind = if self.match_char('=') {
+                        TokenKind::MinusEqual
+                    } else if self.match_char('-') {
+                        TokenKind::MinusMinus
+                    } else {
+                        TokenKind::Minus
+                    };
+                    self.make_token(kind)
+                }
+                '+' => {
+                    let kind = if self.match_char('=') {
+                        TokenKind::PlusEqual
+                    } else if self.match_char('+') {
+                        TokenKind::PlusPlus
+                    } else {
+                        TokenKind::Plus
+                    };
+                    self.make_token(kind)
+                }
+                '/' => {
+                    let kind = if self.match_char('=') {
+                        TokenKind::SlashEqual
+                    } else {
+                        TokenKind::Slash
+                    };
+                    self.make_token(kind)
+                }
+                '*' => {
+                    let kind = if self.match_char('=') {
+                        TokenKind::StarEqual
+                    } else {
+                        TokenKind::Star
+                    };
+                    self.make_token(kind)
+                }
+                '"' => self.string(false),
                 '=' => {
                     let kind = if self.match_char('=') {
                         TokenKind::EqualEqual
@@ -345,7 +477,19 @@ impl<'a> Scanner<'a> {
                     };
                     self.make_token(kind)
                 }
+                //there is no single `?` token
+                '?' => {
+                    if self.match_char('?') {
+                        self.make_token(TokenKind::QuestionQuestion)
+                    } else {
+                        self.error_token("Unexpected character.")
+                    }
+                }
                 '0'..='9' => self.number(),
+                'r' if self.peek() == Some('"') => {
+                    self.advance();
+                    self.string(true)
+                }
                 'a'..='z' | 'A'..='Z'| '_' => self.identifier(),
                 _ => self.error_token("Unexpected character."),
             },
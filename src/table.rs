@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+use crate::gc::Trace;
+
+/// The multiply-and-rotate hash rustc uses for its own tables. Much cheaper than the default
+/// SipHash, and strings hash in a single step since they carry a precomputed hash.
+#[derive(Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl FxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FX_SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+pub type FxHashMap<K, V> = HashMap<K, V, BuildHasherDefault<FxHasher>>;
+
+/// A hash table that remembers insertion order, so iterating it is deterministic.
+/// Lookups go through `indices`; `entries` holds the key/value pairs in the order they were first inserted.
+pub struct Table<K: Hash + Eq + Clone, V> {
+    entries: Vec<(K, V)>,
+    indices: FxHashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, V> Table<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![],
+            indices: FxHashMap::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.indices.get(key).map(|index| &self.entries[*index].1)
+    }
+
+    /// The position of `key` in insertion order. Entries are never removed, so a position stays
+    /// valid for as long as the table lives.
+    pub fn index_of(&self, key: &K) -> Option<usize> {
+        self.indices.get(key).copied()
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|(key, value)| (key, value))
+    }
+
+    /// Inserts or overwrites `key`. Overwriting keeps the key's original position.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.indices.get(&key) {
+            Some(index) => Some(std::mem::replace(&mut self.entries[*index].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+unsafe impl<K: Hash + Eq + Clone + Trace, V: Trace> Trace for Table<K, V> {
+    //the keys in `indices` are clones of the keys in `entries`, so only `entries` needs tracing
+    fn trace(&self) {
+        for (k, v) in self.entries.iter() {
+            k.trace();
+            v.trace();
+        }
+    }
+
+    fn root(&self) {
+        for (k, v) in self.entries.iter() {
+            k.root();
+            v.root();
+        }
+        for k in self.indices.keys() {
+            k.root();
+        }
+    }
+
+    fn unroot(&self) {
+        for (k, v) in self.entries.iter() {
+            k.unroot();
+            v.unroot();
+        }
+        for k in self.indices.keys() {
+            k.unroot();
+        }
+    }
+}
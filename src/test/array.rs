@@ -0,0 +1,133 @@
+#[test]
+fn contains() {
+    test_output!(
+        "./test/array/contains.lox",
+        "true\ntrue\ntrue\nfalse\nfalse\n"
+    );
+}
+
+#[test]
+fn contains_wrong_arity() {
+    test_error!(
+        "./test/array/contains_wrong_arity.lox",
+        "Expected 2 arguments but got 1.\n"
+    );
+}
+
+#[test]
+fn filter() {
+    test_output!("./test/array/filter.lox", "[2, 4, 6]\n[]\n[0, a, true]\n");
+}
+
+#[test]
+fn filter_non_array() {
+    test_error!(
+        "./test/array/filter_non_array.lox",
+        "First argument to 'filter' must be an array.\n"
+    );
+}
+
+#[test]
+fn identity() {
+    test_output!("./test/array/identity.lox", "true\nfalse\ntrue\nfalse\n");
+}
+
+#[test]
+fn index() {
+    test_output!("./test/array/index.lox", "zero\ntwo\ntwo\n3\n");
+}
+
+#[test]
+fn index_negative() {
+    test_error!(
+        "./test/array/index_negative.lox",
+        "Array index out of bounds.\n"
+    );
+}
+
+#[test]
+fn index_non_array() {
+    test_error!(
+        "./test/array/index_non_array.lox",
+        "Only arrays and maps can be indexed.\n"
+    );
+}
+
+#[test]
+fn index_not_integer() {
+    test_error!(
+        "./test/array/index_not_integer.lox",
+        "Array index must be an integer.\n"
+    );
+}
+
+#[test]
+fn index_of() {
+    test_output!("./test/array/index_of.lox", "0\n1\n2\n-1\n");
+}
+
+#[test]
+fn index_of_non_array() {
+    test_error!(
+        "./test/array/index_of_non_array.lox",
+        "First argument to 'index_of' must be an array.\n"
+    );
+}
+
+#[test]
+fn index_out_of_bounds() {
+    test_error!(
+        "./test/array/index_out_of_bounds.lox",
+        "Array index out of bounds.\n"
+    );
+}
+
+#[test]
+fn literal() {
+    test_output!(
+        "./test/array/literal.lox",
+        "[]\n[1, 2, 3]\n[a, nil, true, [1, 2]]\n[1, 2]\n"
+    );
+}
+
+#[test]
+fn map() {
+    test_output!(
+        "./test/array/map.lox",
+        "[2, 4, 6]\n[]\n[11, 12]\n[1, 2]\n[1, 2]\n[2, 4]\n"
+    );
+}
+
+#[test]
+fn map_callback_error() {
+    test_error!(
+        "./test/array/map_callback_error.lox",
+        "Only instances have properties.\n"
+    );
+}
+
+#[test]
+fn missing_bracket() {
+    test_error!(
+        "./test/array/missing_bracket.lox",
+        "[line 2] Error[E0002] at ';': Expect ']' after array elements.\n"
+    );
+}
+
+#[test]
+fn reduce() {
+    test_output!("./test/array/reduce.lox", "6\n42\n>123\n");
+}
+
+#[test]
+fn reduce_wrong_arity() {
+    test_error!(
+        "./test/array/reduce_wrong_arity.lox",
+        "Expected 3 arguments but got 2.\n"
+    );
+}
+
+#[test]
+fn set_index() {
+    test_output!("./test/array/set_index.lox", "new\n[1, new, 3]\nshared\n");
+}
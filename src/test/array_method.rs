@@ -0,0 +1,73 @@
+#[test]
+fn bound() {
+    test_output!("./test/array_method/bound.lox", "[x, y]\n");
+}
+
+#[test]
+fn collect() {
+    test_output!("./test/array_method/collect.lox", "10\n14\n");
+}
+
+#[test]
+fn insert_out_of_range() {
+    test_error!(
+        "./test/array_method/insert_out_of_range.lox",
+        "Array index out of bounds.\n"
+    );
+}
+
+#[test]
+fn insert_remove() {
+    test_output!(
+        "./test/array_method/insert_remove.lox",
+        "[1, 2, 3]\n[0, 1, 2, 3, 4]\n0\n4\n[1, 2, 3]\n"
+    );
+}
+
+#[test]
+fn pop_empty() {
+    test_error!(
+        "./test/array_method/pop_empty.lox",
+        "Can't pop from an empty array.\n"
+    );
+}
+
+#[test]
+fn push_pop() {
+    test_output!(
+        "./test/array_method/push_pop.lox",
+        "[1, two, [3]]\n3\n[3]\ntwo\n1\n[]\n"
+    );
+}
+
+#[test]
+fn remove_not_integer() {
+    test_error!(
+        "./test/array_method/remove_not_integer.lox",
+        "Array index must be an integer.\n"
+    );
+}
+
+#[test]
+fn remove_out_of_range() {
+    test_error!(
+        "./test/array_method/remove_out_of_range.lox",
+        "Array index out of bounds.\n"
+    );
+}
+
+#[test]
+fn unknown() {
+    test_error!(
+        "./test/array_method/unknown.lox",
+        "Only instances have methods.\n"
+    );
+}
+
+#[test]
+fn wrong_arity() {
+    test_error!(
+        "./test/array_method/wrong_arity.lox",
+        "Expected 1 arguments but got 0.\n"
+    );
+}
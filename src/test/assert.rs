@@ -0,0 +1,28 @@
+#[test]
+fn fail() {
+    test_error!(
+        "./test/assert/fail.lox",
+        "Assertion failed: math is broken\n"
+    );
+}
+
+#[test]
+fn fail_without_message() {
+    test_error!(
+        "./test/assert/fail_without_message.lox",
+        "Assertion failed.\n"
+    );
+}
+
+#[test]
+fn missing_argument() {
+    test_error!(
+        "./test/assert/missing_argument.lox",
+        "Expected 1 or 2 arguments but got 0.\n"
+    );
+}
+
+#[test]
+fn pass() {
+    test_output!("./test/assert/pass.lox", "");
+}
@@ -0,0 +1,12 @@
+#[test]
+fn fail() {
+    test_error!(
+        "./test/assert_throws/fail.lox",
+        "Expected an error but none was thrown.\n"
+    );
+}
+
+#[test]
+fn pass() {
+    test_output!("./test/assert_throws/pass.lox", "true\n3\ncaptured\n");
+}
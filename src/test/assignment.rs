@@ -3,6 +3,22 @@ fn associativity() {
     test_output!("./test/assignment/associativity.lox", "c\nc\nc\n");
 }
 
+#[test]
+fn compound() {
+    test_output!(
+        "./test/assignment/compound.lox",
+        "15\n12\n24\n6\n12\n13\nconcat\n30\n"
+    );
+}
+
+#[test]
+fn compound_invalid() {
+    test_error!(
+        "./test/assignment/compound_invalid.lox",
+        "[line 3] Error[E0001] at '+=': Invalid assignment target.\n"
+    );
+}
+
 #[test]
 fn global() {
     test_output!("./test/assignment/global.lox", "before\nafter\narg\narg\n");
@@ -12,7 +28,31 @@ fn global() {
 fn grouping() {
     test_error!(
         "./test/assignment/grouping.lox",
-        "[line 2] Error at '=': Invalid assignment target.\n"
+        "[line 2] Error[E0001] at '=': Invalid assignment target.\n"
+    );
+}
+
+#[test]
+fn increment() {
+    test_output!(
+        "./test/assignment/increment.lox",
+        "1\n2\n3\n3\n1\n1\n2\n2\n0.1\n1.1\n20\n-1\n0\n"
+    );
+}
+
+#[test]
+fn increment_invalid() {
+    test_error!(
+        "./test/assignment/increment_invalid.lox",
+        "[line 3] Error[E0001] at '++': Invalid increment target.\n"
+    );
+}
+
+#[test]
+fn increment_postfix_invalid() {
+    test_error!(
+        "./test/assignment/increment_postfix_invalid.lox",
+        "[line 2] Error[E0001] at '++': Invalid increment target.\n"
     );
 }
 
@@ -20,7 +60,7 @@ fn grouping() {
 fn infix_operator() {
     test_error!(
         "./test/assignment/infix_operator.lox",
-        "[line 3] Error at '=': Invalid assignment target.\n"
+        "[line 3] Error[E0001] at '=': Invalid assignment target.\n"
     );
 }
 
@@ -33,7 +73,7 @@ fn local() {
 fn prefix_operator() {
     test_error!(
         "./test/assignment/prefix_operator.lox",
-        "[line 2] Error at '=': Invalid assignment target.\n"
+        "[line 2] Error[E0001] at '=': Invalid assignment target.\n"
     );
 }
 
@@ -46,7 +86,7 @@ fn syntax() {
 fn to_this() {
     test_error!(
         "./test/assignment/to_this.lox",
-        "[line 3] Error at '=': Invalid assignment target.\n"
+        "[line 3] Error[E0001] at '=': Invalid assignment target.\n"
     );
 }
 
@@ -3,6 +3,14 @@ fn empty() {
     test_output!("./test/block/empty.lox", "ok\n");
 }
 
+#[test]
+fn many_locals() {
+    test_output!(
+        "./test/block/many_locals.lox",
+        "10\nbefore\nafter\ncaptured\n"
+    );
+}
+
 #[test]
 fn scope() {
     test_output!("./test/block/scope.lox", "inner\nouter\n");
@@ -7,7 +7,7 @@ fn empty() {
 fn inherit_self() {
     test_error!(
         "./test/class/inherit_self.lox",
-        "[line 1] Error at 'Foo': A class can't inherit from itself.\n"
+        "[line 1] Error[E0011] at 'Foo': A class can't inherit from itself.\n"
     );
 }
 
@@ -28,7 +28,7 @@ fn local_inherit_other() {
 fn local_inherit_self() {
     test_error!(
         "./test/class/local_inherit_self.lox",
-        "[line 2] Error at 'Foo': A class can't inherit from itself.\n[line 5] Error at end: Expect '}' after block.\n"
+        "[line 2] Error[E0011] at 'Foo': A class can't inherit from itself.\n[line 5] Error[E0002] at end: Expect '}' after block.\n"
     );
 }
 
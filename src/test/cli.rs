@@ -0,0 +1,354 @@
+#[test]
+fn bytecode_stats() {
+    use crate::bytecode_stats_file;
+    use crate::compiler::CompilerOptions;
+    let mut out = vec![];
+    let mut err = vec![];
+    let code = bytecode_stats_file(
+        "./test/cli/stats.lox".to_string(),
+        CompilerOptions::default(),
+        &mut out,
+        &mut err,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+    assert_eq!(
+        std::str::from_utf8(out.as_slice()).unwrap(),
+        "\
+function         instructions  constants  max stack  locals  upvalues
+<script>                   15          2          4       0         0
+<fn add>                    6          0          5       2         0
+<fn counter>                6          1          4       2         0
+<fn increment>              9          0          3       0         1
+"
+    );
+}
+
+#[test]
+fn bytecode_stats_optimized() {
+    use crate::bytecode_stats_file;
+    use crate::compiler::CompilerOptions;
+    let stats = |optimize| {
+        let mut out = vec![];
+        let mut err = vec![];
+        let options = CompilerOptions {
+            optimize,
+            ..CompilerOptions::default()
+        };
+        let code = bytecode_stats_file(
+            "./test/cli/optimizable.lox".to_string(),
+            options,
+            &mut out,
+            &mut err,
+        );
+        assert_eq!(code, 0);
+        String::from_utf8(out).unwrap()
+    };
+    //the two `Pop`s that end the block become one `PopN`
+    assert!(stats(false).contains("<script>                   27 "));
+    assert!(stats(true).contains("<script>                   26 "));
+}
+
+#[test]
+fn disasm_after_run() {
+    use crate::run_file_and_disassemble;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut listing = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    run_file_and_disassemble(
+        &mut vm,
+        "./test/cli/disasm_after_run.lox".to_string(),
+        &mut listing,
+    );
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "3\n");
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+    let listing = std::str::from_utf8(listing.as_slice()).unwrap();
+    assert!(listing.starts_with("== <script> ==\n"));
+    assert!(listing.contains("== <fn add> ==\n"));
+    assert!(listing.contains(" Add\n"));
+    assert!(listing.contains(" Print\n"));
+}
+
+#[test]
+fn disasm_after_run_optimized() {
+    use crate::run_file_and_disassemble;
+    use crate::vm::VM;
+    let listing = |optimize| {
+        let mut out = vec![];
+        let mut err = vec![];
+        let mut listing = vec![];
+        let mut vm = VM::new(&mut out, &mut err);
+        vm.set_optimize(optimize);
+        let code = run_file_and_disassemble(
+            &mut vm,
+            "./test/cli/optimizable.lox".to_string(),
+            &mut listing,
+        );
+        assert_eq!(code, 0);
+        assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "3\n");
+        assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+        String::from_utf8(listing).unwrap()
+    };
+    //the listing is of the code that ran, so the peephole pass has shortened it
+    let (naive, optimized) = (listing(false), listing(true));
+    assert!(optimized.lines().count() < naive.lines().count());
+    assert!(optimized.contains(" PopN 2\n"));
+    assert!(!naive.contains(" PopN "));
+}
+
+#[test]
+fn dump() {
+    use crate::compiler::CompilerOptions;
+    use crate::dump_file;
+    let mut listing = vec![];
+    let mut err = vec![];
+    let code = dump_file(
+        "./test/cli/dump.lox".to_string(),
+        CompilerOptions::default(),
+        &mut listing,
+        &mut err,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+    let listing = std::str::from_utf8(listing.as_slice()).unwrap();
+    assert!(listing.starts_with("== <script> ==\n"));
+    assert!(listing.contains("== <fn greet> ==\n"));
+    assert!(listing.contains(" Closure "));
+    assert!(listing.contains(" Add\n"));
+    //nothing ran
+    assert!(!listing.contains("hi bob"));
+}
+
+#[test]
+fn dump_compile_error() {
+    use crate::compiler::CompilerOptions;
+    use crate::dump_file;
+    let mut listing = vec![];
+    let mut err = vec![];
+    let code = dump_file(
+        "./test/unexpected_character.lox".to_string(),
+        CompilerOptions::default(),
+        &mut listing,
+        &mut err,
+    );
+    assert_eq!(code, 65);
+    assert_eq!(listing, b"");
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "[line 3] Error[E0004]: Unexpected character.\n"
+    );
+}
+
+#[test]
+fn dump_optimized() {
+    use crate::compiler::CompilerOptions;
+    use crate::dump_file;
+    let mut listing = vec![];
+    let mut err = vec![];
+    let options = CompilerOptions {
+        optimize: true,
+        ..CompilerOptions::default()
+    };
+    let code = dump_file(
+        "./test/cli/optimizable.lox".to_string(),
+        options,
+        &mut listing,
+        &mut err,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+    assert!(std::str::from_utf8(listing.as_slice()).unwrap().contains(" PopN 2\n"));
+}
+
+#[test]
+fn exit_code_compile_error() {
+    assert_eq!(super::run_configured("./test/unexpected_character.lox", |_| ()).0, 65);
+}
+
+#[test]
+fn exit_code_runtime_error() {
+    assert_eq!(super::run_configured("./test/field/undefined.lox", |_| ()).0, 70);
+}
+
+#[test]
+fn exit_code_success() {
+    assert_eq!(super::run_configured("./test/empty_file.lox", |_| ()).0, 0);
+}
+
+#[test]
+fn explain() {
+    use crate::explain;
+    let mut out = vec![];
+    let mut err = vec![];
+    assert_eq!(explain("E0001", &mut out, &mut err), 0);
+    assert_eq!(err, b"");
+    let out = std::str::from_utf8(out.as_slice()).unwrap();
+    assert!(out.starts_with("The left-hand side of `=` is not something that can be assigned to.\n"));
+    assert!(out.contains("    a = c;\n"));
+}
+
+#[test]
+fn explain_codes_are_unique() {
+    use crate::diagnostic::CompileErrorKind;
+    for kind in CompileErrorKind::ALL {
+        assert_eq!(CompileErrorKind::from_code(kind.code()), Some(kind));
+        assert!(!kind.explanation().is_empty());
+    }
+}
+
+#[test]
+fn explain_unknown_code() {
+    use crate::explain;
+    let mut out = vec![];
+    let mut err = vec![];
+    assert_eq!(explain("E9999", &mut out, &mut err), 64);
+    assert_eq!(out, b"");
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "E9999 is not a known error code.\n"
+    );
+}
+
+#[test]
+fn read_source_from_stdin() {
+    use crate::read_source;
+    let mut err = vec![];
+    let source = read_source("-", &mut "print 1;".as_bytes(), &mut err);
+    assert_eq!(source, Ok("print 1;".to_string()));
+    //a path that isn't `-` never touches the input
+    let source = read_source("./test/empty_file.lox", &mut "print 1;".as_bytes(), &mut err);
+    assert_eq!(source, Ok("".to_string()));
+    let source = read_source("./test/missing.lox", &mut "print 1;".as_bytes(), &mut err);
+    assert_eq!(source, Err(74));
+    assert!(std::str::from_utf8(err.as_slice())
+        .unwrap()
+        .starts_with("could not read file ./test/missing.lox: "));
+}
+
+#[test]
+fn run_compiled_file() {
+    use crate::compiler::CompilerOptions;
+    use crate::vm::VM;
+    use crate::{compile_file, run_file};
+    let output = std::env::temp_dir().join(format!("loxide-{}.loxc", std::process::id()));
+    let output = output.to_string_lossy().to_string();
+    let mut err = vec![];
+    let code = compile_file(
+        "./test/serialize/script.lox".to_string(),
+        output.clone(),
+        CompilerOptions::default(),
+        &mut err,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(err, b"");
+
+    let mut expected = vec![];
+    let mut vm = VM::new(&mut expected, &mut err);
+    assert_eq!(run_file(&mut vm, "./test/serialize/script.lox".to_string()), 0);
+    let mut out = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert_eq!(run_file(&mut vm, output.clone()), 0);
+    std::fs::remove_file(output).ok();
+    assert_eq!(err, b"");
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn run_compiled_file_with_options() {
+    use crate::compiler::CompilerOptions;
+    use crate::vm::VM;
+    use crate::{compile_file, run_file};
+    let output = std::env::temp_dir().join(format!("loxide-{}-options.loxc", std::process::id()));
+    let output = output.to_string_lossy().to_string();
+    let mut err = vec![];
+    //the script leaves out its semicolons, so it only compiles with them inferred
+    let options = CompilerOptions {
+        infer_semicolons: true,
+        ..CompilerOptions::default()
+    };
+    let code = compile_file(
+        "./test/semicolon/newline.lox".to_string(),
+        output.clone(),
+        options,
+        &mut err,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(err, b"");
+
+    let mut out = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert_eq!(run_file(&mut vm, output.clone()), 0);
+    std::fs::remove_file(output).ok();
+    assert_eq!(err, b"");
+    assert_eq!(out, b"1\n3\n7\n30\nblock\ndone\nboth\n");
+}
+
+#[test]
+fn run_compiled_file_wrong_version() {
+    test_error!(
+        "./test/cli/wrong_version.loxc",
+        "Could not load bytecode: format version 0 is not supported (expected 21).\n"
+    );
+}
+
+#[test]
+fn run_reader() {
+    use crate::run_reader;
+    use crate::vm::VM;
+    let source = std::fs::read("./test/cli/stdin.lox").unwrap();
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let code = run_reader(&mut vm, &mut source.as_slice());
+    drop(vm);
+    assert_eq!(code, 0);
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "hi stdin\n");
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
+#[test]
+fn run_reader_compiled() {
+    use crate::chunk::serialize::serialize_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    use crate::run_reader;
+    use crate::vm::VM;
+    let mut globals = GlobalSlots::new();
+    //bytecode is recognised by its header on standard input too
+    let source = std::fs::read_to_string("./test/cli/stdin.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let code = run_reader(&mut vm, &mut bytes.as_slice());
+    drop(vm);
+    assert_eq!(code, 0);
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "hi stdin\n");
+}
+
+#[test]
+fn trace_execution() {
+    use crate::run_file;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.set_trace_execution(true);
+    run_file(&mut vm, "./test/cli/trace.lox".to_string());
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "3\n");
+    //`1 + 2` is folded into a single push
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "          [ <script> ]\n\
+         0000    1 PushByte 3\n\
+         \x20         [ <script> ][ 3 ]\n\
+         0002    2 Print\n\
+         \x20         [ <script> ]\n\
+         0003    | Nil\n\
+         \x20         [ <script> ][ nil ]\n\
+         0004    | Return\n"
+    );
+}
@@ -76,6 +76,6 @@ fn return_in_nested_function() {
 fn return_value() {
     test_error!(
         "./test/constructor/return_value.lox",
-        "[line 3] Error at 'return': Can't return a value from an initializer.\n"
+        "[line 3] Error[E0010] at 'return': Can't return a value from an initializer.\n"
     );
 }
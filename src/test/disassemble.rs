@@ -0,0 +1,424 @@
+#[test]
+fn all_functions() {
+    use crate::chunk::disassemble_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/all_functions.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    disassemble_function(&function.borrow(), &globals, &mut listing);
+    let listing = std::str::from_utf8(listing.as_slice()).unwrap();
+    let headers: Vec<&str> = listing.lines().filter(|line| line.starts_with("==")).collect();
+    //each function comes right after the one it's nested in
+    assert_eq!(
+        headers,
+        vec![
+            "== <script> ==",
+            "== <fn outer> ==",
+            "== <fn inner> ==",
+            "== <fn init> ==",
+            "== <fn show> ==",
+        ]
+    );
+    assert!(listing.contains("== <fn inner> ==\n0000    3 PushByte 1\n"));
+}
+
+#[test]
+fn anonymous_function() {
+    use crate::chunk::disassemble_function;
+    use crate::global::GlobalSlots;
+    use crate::object::ObjFunction;
+    use crate::value::value::Value;
+    let globals = GlobalSlots::new();
+    let script = ObjFunction::new(None);
+    let nested = ObjFunction::new(None);
+    script
+        .borrow()
+        .chunk
+        .borrow_mut()
+        .add_constant(Value::function(nested));
+    let mut listing = vec![];
+    disassemble_function(&script.borrow(), &globals, &mut listing);
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "== <script> ==\n== <anonymous> ==\n"
+    );
+}
+
+#[test]
+fn block_locals() {
+    use crate::chunk::verify::verify_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/block_locals.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    //the three locals leave the stack in one instruction
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    2 PushByte 1\n\
+         0002    3 PushByte 2\n\
+         0004    4 PushByte 3\n\
+         0006    6 PopN 3\n\
+         0008    | Nil\n\
+         0009    | Return\n"
+    );
+    assert_eq!(verify_function(&function.borrow()), Ok(()));
+}
+
+#[test]
+fn chunk_to_writer() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/global.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    1 PushByte 1\n\
+         0002    2 DefineGlobal 'a'\n\
+         0005    | GetGlobal 'a'\n\
+         0008    | PushByte 2\n\
+         0010    | Add\n\
+         0011    3 Print\n\
+         0012    | Nil\n\
+         0013    | Return\n"
+    );
+}
+
+#[test]
+fn closure_lines() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/closure_lines.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let outer = function.borrow().chunk.borrow().constants[0].as_function().unwrap();
+    let mut listing = vec![];
+    outer.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    //the upvalue lines are numbered by the offset of their own two bytes
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    2 PushByte 1\n\
+         0002    3 PushByte 2\n\
+         0004    8 Closure 0 <fn inner>\n\
+         0006    | local 1\n\
+         0008    | local 2\n\
+         0010    | GetLocal 3\n\
+         0012    9 Return\n\
+         0013   10 Nil\n\
+         0014    | Return\n"
+    );
+}
+
+//with integers, a whole-number float takes a constant slot
+#[cfg(not(feature = "integers"))]
+#[test]
+fn constant_folding() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/constant_folding.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    //division by zero is left to run, and so is anything with a variable in it
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    1 PushByte 14\n\
+         0002    2 DefineGlobal 'a'\n\
+         0005    | PushByte 3\n\
+         0007    | Negate\n\
+         0008    3 DefineGlobal 'b'\n\
+         0011    | True\n\
+         0012    4 DefineGlobal 'c'\n\
+         0015    | GetGlobal 'a'\n\
+         0018    | PushByte 1\n\
+         0020    | Add\n\
+         0021    | PushByte 2\n\
+         0023    | Multiply\n\
+         0024    5 DefineGlobal 'd'\n\
+         0027    | PushByte 1\n\
+         0029    | PushByte 0\n\
+         0031    | Divide\n\
+         0032    6 DefineGlobal 'e'\n\
+         0035    | Nil\n\
+         0036    | Return\n"
+    );
+    //the literals that were folded away gave their constant slots back
+    assert_eq!(function.borrow().chunk.borrow().constants.len(), 0);
+}
+
+#[test]
+fn else_if() {
+    use crate::chunk::verify::verify_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/else_if.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    //both `else` jumps land past the whole chain, and the bare `if` has no jump after its body
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    1 PushByte 1\n\
+         0002    2 DefineGlobal 'a'\n\
+         0005    | GetGlobal 'a'\n\
+         0008    | PushByte 1\n\
+         0010    | Equal\n\
+         0011    | PopJumpIfFalse 6\n\
+         0014    | Constant 0\n\
+         0016    3 Print\n\
+         0017    | Jump 18\n\
+         0020    | GetGlobal 'a'\n\
+         0023    | PushByte 2\n\
+         0025    | Equal\n\
+         0026    | PopJumpIfFalse 6\n\
+         0029    | Constant 1\n\
+         0031    4 Print\n\
+         0032    | Jump 3\n\
+         0035    | Constant 2\n\
+         0037    5 Print\n\
+         0038    | GetGlobal 'a'\n\
+         0041    | PopJumpIfFalse 4\n\
+         0044    | GetGlobal 'a'\n\
+         0047    6 Print\n\
+         0048    | Nil\n\
+         0049    | Return\n"
+    );
+    assert_eq!(verify_function(&function.borrow()), Ok(()));
+}
+
+#[test]
+fn not_equal_condition() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/not_equal_condition.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+    //no `Not` between the `Equal` and the jump, and no jump over an `else` that isn't there
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    1 PushByte 1\n\
+         0002    2 DefineGlobal 'a'\n\
+         0005    | PushByte 2\n\
+         0007    3 DefineGlobal 'b'\n\
+         0010    | GetGlobal 'a'\n\
+         0013    | GetGlobal 'b'\n\
+         0016    | Equal\n\
+         0017    | PopJumpIfTrue 3\n\
+         0020    | Constant 0\n\
+         0022    4 Print\n\
+         0023    | Nil\n\
+         0024    | Return\n"
+    );
+}
+
+#[test]
+fn operand_lines_are_ignored() {
+    use crate::chunk::{Chunk, OpCode};
+    use crate::global::GlobalSlots;
+    use crate::value::value::Value;
+    let globals = GlobalSlots::new();
+    //an operand byte on a later line than its opcode mustn't make the next instruction look
+    //like it's on the same line as this one
+    let mut chunk = Chunk::new();
+    let constant = chunk.add_constant(Value::number(1.0)) as u8;
+    chunk.add_byte(OpCode::Constant as u8, 1);
+    chunk.add_byte(constant, 2);
+    chunk.add_byte(OpCode::Print as u8, 2);
+    chunk.add_byte(OpCode::Nil as u8, 2);
+    chunk.add_byte(OpCode::Return as u8, 2);
+    let mut listing = vec![];
+    chunk.disassemble_to(&globals, &mut listing);
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0000    1 Constant 0\n\
+         0002    2 Print\n\
+         0003    | Nil\n\
+         0004    | Return\n"
+    );
+    let mut listing = vec![];
+    assert_eq!(chunk.disassemble_instruction_to(&globals, &mut listing, 2), Some(3));
+    assert_eq!(std::str::from_utf8(listing.as_slice()).unwrap(), "0002    2 Print\n");
+}
+
+#[test]
+fn instruction_to_writer() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/disassemble/global.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut listing = vec![];
+    let next = function.borrow().chunk.borrow().disassemble_instruction_to(&globals, &mut listing, 2);
+    assert_eq!(next, Some(5));
+    assert_eq!(
+        std::str::from_utf8(listing.as_slice()).unwrap(),
+        "0002    2 DefineGlobal 'a'\n"
+    );
+}
+
+#[test]
+fn peephole() {
+    use crate::chunk::verify::verify_function;
+    use crate::compiler::{compile_with_options, CompilerOptions};
+    use crate::global::GlobalSlots;
+    let source = std::fs::read_to_string("./test/disassemble/peephole.lox").unwrap();
+    let listing = |optimize| {
+        let options = CompilerOptions {
+            optimize,
+            ..CompilerOptions::default()
+        };
+        let mut globals = GlobalSlots::new();
+        let function =
+            compile_with_options(source.as_str(), options, &mut globals, &mut std::io::sink())
+                .ok()
+                .unwrap();
+        assert_eq!(verify_function(&function.borrow()), Ok(()));
+        let mut listing = vec![];
+        function.borrow().chunk.borrow().disassemble_to(&globals, &mut listing);
+        String::from_utf8(listing).unwrap()
+    };
+    assert_eq!(
+        listing(false),
+        "0000    1 True\n\
+         0001    2 DefineGlobal 'a'\n\
+         0004    | GetGlobal 'a'\n\
+         0007    | JumpIfFalse 75\n\
+         0010    | Pop\n\
+         0011    3 GetGlobal 'a'\n\
+         0014    | PopJumpIfFalse 18\n\
+         0017    | GetGlobal 'a'\n\
+         0020    | PopJumpIfTrue 6\n\
+         0023    | PushByte 1\n\
+         0025    | Print\n\
+         0026    | Jump 3\n\
+         0029    | PushByte 2\n\
+         0031    | Print\n\
+         0032    | Jump 3\n\
+         0035    | PushByte 3\n\
+         0037    4 Print\n\
+         0038    5 PushByte 1\n\
+         0040    6 PushByte 0\n\
+         0042    | GetLocal 2\n\
+         0044    | PushByte 2\n\
+         0046    | Less\n\
+         0047    | JumpIfFalse 24\n\
+         0050    | Pop\n\
+         0051    | Jump 11\n\
+         0054    | GetLocal 2\n\
+         0056    | PushByte 1\n\
+         0058    | Add\n\
+         0059    | SetLocal 2\n\
+         0061    | Pop\n\
+         0062    | Loop 23\n\
+         0065    | GetLocal 2\n\
+         0067    | GetLocal 1\n\
+         0069    | Add\n\
+         0070    7 Print\n\
+         0071    | Loop 20\n\
+         0074    | Pop\n\
+         0075    | Pop\n\
+         0076    8 Pop\n\
+         0077    | False\n\
+         0078    | SetGlobal 'a'\n\
+         0081    9 Pop\n\
+         0082   10 Loop 81\n\
+         0085    | Pop\n\
+         0086    | Nil\n\
+         0087    | Return\n"
+    );
+    //the inner `if` jumps straight past the outer `else`, and the pops ending the loop, the `for`
+    //and the block become one `PopN` that moves the loop back to the `while` a byte closer
+    assert_eq!(
+        listing(true),
+        "0000    1 True\n\
+         0001    2 DefineGlobal 'a'\n\
+         0004    | GetGlobal 'a'\n\
+         0007    | JumpIfFalse 74\n\
+         0010    | Pop\n\
+         0011    3 GetGlobal 'a'\n\
+         0014    | PopJumpIfFalse 18\n\
+         0017    | GetGlobal 'a'\n\
+         0020    | PopJumpIfTrue 6\n\
+         0023    | PushByte 1\n\
+         0025    | Print\n\
+         0026    | Jump 9\n\
+         0029    | PushByte 2\n\
+         0031    | Print\n\
+         0032    | Jump 3\n\
+         0035    | PushByte 3\n\
+         0037    4 Print\n\
+         0038    5 PushByte 1\n\
+         0040    6 PushByte 0\n\
+         0042    | GetLocal 2\n\
+         0044    | PushByte 2\n\
+         0046    | Less\n\
+         0047    | JumpIfFalse 24\n\
+         0050    | Pop\n\
+         0051    | Jump 11\n\
+         0054    | GetLocal 2\n\
+         0056    | PushByte 1\n\
+         0058    | Add\n\
+         0059    | SetLocal 2\n\
+         0061    | Pop\n\
+         0062    | Loop 23\n\
+         0065    | GetLocal 2\n\
+         0067    | GetLocal 1\n\
+         0069    | Add\n\
+         0070    7 Print\n\
+         0071    | Loop 20\n\
+         0074    | PopN 3\n\
+         0076    8 False\n\
+         0077    | SetGlobal 'a'\n\
+         0080    9 Pop\n\
+         0081   10 Loop 80\n\
+         0084    | Pop\n\
+         0085    | Nil\n\
+         0086    | Return\n"
+    );
+}
+
+#[test]
+fn peephole_jump_to_next() {
+    use crate::chunk::{Chunk, OpCode};
+    let mut chunk = Chunk::new();
+    for byte in [OpCode::Jump as u8, 0, 0, OpCode::Nil as u8, OpCode::Return as u8] {
+        chunk.add_byte(byte, 1);
+    }
+    chunk.peephole();
+    assert_eq!(chunk.code, vec![OpCode::Nil as u8, OpCode::Return as u8]);
+}
+
+#[test]
+fn peephole_same_output() {
+    use crate::run_file;
+    use crate::vm::VM;
+    let run = |path: &str, optimize| {
+        let mut out = vec![];
+        let mut err = vec![];
+        let mut vm = VM::new(&mut out, &mut err);
+        vm.set_optimize(optimize);
+        let code = run_file(&mut vm, path.to_string());
+        drop(vm);
+        (code, out, err)
+    };
+    for path in [
+        "./test/disassemble/peephole.lox",
+        "./test/for/closure_in_body.lox",
+        "./test/while/closure_in_body.lox",
+        "./test/logical_operator/and.lox",
+        "./test/block/many_locals.lox",
+    ] {
+        assert_eq!(run(path, true), run(path, false), "{}", path);
+    }
+}
@@ -0,0 +1,83 @@
+#[test]
+fn after_error() {
+    use crate::vm::{InterpretError, VM};
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.interpret("fun f(x) { return x + 1; }".to_string()).unwrap();
+    assert!(matches!(
+        vm.eval_expression("f(nil) * 100"),
+        Err(InterpretError::Runtime)
+    ));
+    //the failed call's frame is gone, so it doesn't get the next result
+    let value = vm.eval_expression("1 + 2").unwrap();
+    assert_eq!(value.as_number().unwrap(), 3.0);
+}
+
+#[test]
+fn arithmetic() {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let value = vm.eval_expression("1 + 2 * 3").unwrap();
+    assert_eq!(value.as_number().unwrap(), 7.0);
+    let value = vm.eval_expression("(10 - 4) / 4 > 1").unwrap();
+    assert!(value == crate::value::value::Value::bool_(true));
+    drop(vm);
+    assert!(out.is_empty());
+    assert!(err.is_empty());
+}
+
+#[test]
+fn errors() {
+    use crate::vm::{InterpretError, VM};
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(matches!(
+        vm.eval_expression("print 1;"),
+        Err(InterpretError::Compile)
+    ));
+    assert!(matches!(
+        vm.eval_expression("1; 2"),
+        Err(InterpretError::Compile)
+    ));
+    assert!(matches!(
+        vm.eval_expression("-\"a\""),
+        Err(InterpretError::Runtime)
+    ));
+    drop(vm);
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "[line 1] Error[E0003] at 'print': Expect expression.\n\
+         [line 1] Error[E0002] at ';': Expect end of expression.\n\
+         Operand must be a number.\n"
+    );
+}
+
+#[test]
+fn sees_globals() {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.interpret("var name = \"lox\"; fun twice(x) { return x + x; }".to_string())
+        .unwrap();
+    let value = vm.eval_expression("twice(name)").unwrap();
+    assert_eq!(value.to_string(), Some("loxlox".to_string()));
+    //nothing is left behind on the stack for the next script
+    vm.interpret("print twice(1);".to_string()).unwrap();
+    drop(vm);
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "2\n");
+}
+
+#[test]
+fn string() {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let value = vm.eval_expression("\"con\" + \"cat\"").unwrap();
+    assert_eq!(value.to_string(), Some("concat".to_string()));
+}
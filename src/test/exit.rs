@@ -0,0 +1,31 @@
+#[test]
+fn assert_throws() {
+    assert_eq!(
+        super::run_configured("./test/exit/assert_throws.lox", |_| ()),
+        (3, "".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn code() {
+    assert_eq!(
+        super::run_configured("./test/exit/code.lox", |_| ()),
+        (3, "before\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn default() {
+    assert_eq!(
+        super::run_configured("./test/exit/default.lox", |_| ()),
+        (0, "before\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn not_integer() {
+    test_error!(
+        "./test/exit/not_integer.lox",
+        "Exit code must be an integer.\n"
+    );
+}
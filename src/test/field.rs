@@ -11,6 +11,47 @@ fn call_nonfunction_field() {
     );
 }
 
+#[test]
+fn compound_assignment() {
+    test_output!(
+        "./test/field/compound_assignment.lox",
+        "5\n4\n1\n40\nab\n"
+    );
+}
+
+#[test]
+fn fields_keep_insertion_order() {
+    use crate::object::{ObjClass, ObjInstance, ObjString};
+    use crate::value::value::Value;
+    let class = ObjClass::new(ObjString::new("Foo".to_string()));
+    let instance = ObjInstance::new(class);
+    let names = ["pear", "apple", "zucchini", "mango", "banana"];
+    for (i, name) in names.iter().enumerate() {
+        let name = ObjString::new(name.to_string());
+        instance.borrow_mut().fields.insert(name, Value::number(i as f64));
+    }
+    //overwriting a field keeps its original position
+    let apple = ObjString::new("apple".to_string());
+    instance.borrow_mut().fields.insert(apple, Value::nil());
+    let instance = instance.borrow();
+    let order: Vec<String> = instance.fields.iter().map(|(name, _)| name.to_string()).collect();
+    assert_eq!(order, names);
+    assert_eq!(instance.fields.len(), names.len());
+}
+
+#[test]
+fn freeze() {
+    test_output!("./test/field/freeze.lox", "1\n2\n3\n10\n5\n1\n");
+}
+
+#[test]
+fn freeze_non_instance() {
+    test_error!(
+        "./test/field/freeze_non_instance.lox",
+        "Argument to 'freeze' must be an instance.\n"
+    );
+}
+
 #[test]
 fn get_and_set_method() {
     test_output!(
@@ -67,6 +108,14 @@ fn get_on_string() {
     );
 }
 
+#[test]
+fn increment() {
+    test_output!(
+        "./test/field/increment.lox",
+        "0.5\n1.5\n2.5\n2.5\n3.5\n1.5\n1.5\n3.5\n2\n"
+    );
+}
+
 #[test]
 fn many() {
     test_output!(
@@ -101,6 +150,24 @@ fn set_evaluation_order() {
     );
 }
 
+#[test]
+fn set_frozen() {
+    test_output_and_error!(
+        "./test/field/set_frozen.lox",
+        "value\n",
+        "Cannot modify a frozen instance.\n"
+    );
+}
+
+#[test]
+fn set_frozen_in_method() {
+    test_output_and_error!(
+        "./test/field/set_frozen_in_method.lox",
+        "1\n",
+        "Cannot modify a frozen instance.\n"
+    );
+}
+
 #[test]
 fn set_on_bool() {
     test_error!(
@@ -153,3 +220,17 @@ fn set_on_string() {
 fn undefined() {
     test_error!("./test/field/undefined.lox", "Undefined property 'bar'.\n");
 }
+
+#[test]
+fn unset() {
+    test_output_and_error!(
+        "./test/field/unset.lox",
+        "nil\n",
+        "Undefined property 'y'.\n"
+    );
+}
+
+#[test]
+fn unset_method() {
+    test_output!("./test/field/unset_method.lox", "method\nfield\n");
+}
@@ -2,7 +2,7 @@
 fn class_in_body() {
     test_error!(
         "./test/for/class_in_body.lox",
-        "[line 2] Error at 'class': Expect expression.\n"
+        "[line 2] Error[E0003] at 'class': Expect expression.\n"
     );
 }
 
@@ -15,7 +15,51 @@ fn closure_in_body() {
 fn fun_in_body() {
     test_error!(
         "./test/for/fun_in_body.lox",
-        "[line 2] Error at 'fun': Expect expression.\n"
+        "[line 2] Error[E0003] at 'fun': Expect expression.\n"
+    );
+}
+
+#[test]
+fn in_array() {
+    test_output!("./test/for/in_array.lox", "10\na\nb\nouter\n");
+}
+
+#[test]
+fn in_closure() {
+    test_output!("./test/for/in_closure.lox", "a\nb\nc\n");
+}
+
+#[test]
+fn in_local() {
+    test_output!("./test/for/in_local.lox", "12\n");
+}
+
+#[test]
+fn in_non_array() {
+    test_error!(
+        "./test/for/in_non_array.lox",
+        "Can only iterate over arrays and maps.\n"
+    );
+}
+
+#[test]
+fn range() {
+    test_output!(
+        "./test/for/range.lox",
+        "0\n1\n2\ncount\n10\n11\ndone\n"
+    );
+}
+
+#[test]
+fn range_closure() {
+    test_output!("./test/for/range_closure.lox", "0\n1\n2\n");
+}
+
+#[test]
+fn range_non_number() {
+    test_error!(
+        "./test/for/range_non_number.lox",
+        "Operand must be a number.\n"
     );
 }
 
@@ -36,20 +80,20 @@ fn scope() {
 
 #[test]
 fn statement_condition() {
-    test_error!("./test/for/statement_condition.lox", "[line 3] Error at '{': Expect expression.\n[line 3] Error at ')': Expect ';' after expression.\n");
+    test_error!("./test/for/statement_condition.lox", "[line 3] Error[E0003] at '{': Expect expression.\n[line 3] Error[E0002] at ')': Expect ';' after expression.\n");
 }
 
 #[test]
 fn statement_increment() {
     test_error!(
         "./test/for/statement_increment.lox",
-        "[line 2] Error at '{': Expect expression.\n"
+        "[line 2] Error[E0003] at '{': Expect expression.\n"
     );
 }
 
 #[test]
 fn statement_initializer() {
-    test_error!("./test/for/statement_initializer.lox", "[line 3] Error at '{': Expect expression.\n[line 3] Error at ')': Expect ';' after expression.\n");
+    test_error!("./test/for/statement_initializer.lox", "[line 3] Error[E0003] at '{': Expect expression.\n[line 3] Error[E0002] at ')': Expect ';' after expression.\n");
 }
 
 #[test]
@@ -64,6 +108,6 @@ fn syntax() {
 fn var_in_body() {
     test_error!(
         "./test/for/var_in_body.lox",
-        "[line 2] Error at 'var': Expect expression.\n"
+        "[line 2] Error[E0003] at 'var': Expect expression.\n"
     );
 }
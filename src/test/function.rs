@@ -1,6 +1,6 @@
 #[test]
 fn body_must_be_block() {
-    test_error!("./test/function/body_must_be_block.lox", "[line 3] Error at '123': Expect '{' before function body.\n[line 4] Error at end: Expect '}' after block.\n");
+    test_error!("./test/function/body_must_be_block.lox", "[line 3] Error[E0002] at '123': Expect '{' before function body.\n[line 4] Error[E0002] at end: Expect '}' after block.\n");
 }
 
 #[test]
@@ -41,7 +41,7 @@ fn missing_arguments() {
 fn missing_comma_in_parameters() {
     test_error!(
         "./test/function/missing_comma_in_parameters.lox",
-        "[line 3] Error at 'c': Expect ')' after parameters.\n[line 4] Error at end: Expect '}' after block.\n"
+        "[line 3] Error[E0002] at 'c': Expect ')' after parameters.\n[line 4] Error[E0002] at end: Expect '}' after block.\n"
     );
 }
 
@@ -76,11 +76,41 @@ fn recursion() {
     test_output!("./test/function/recursion.lox", "21\n");
 }
 
+#[test]
+fn run_after_runtime_error() {
+    use crate::vm::{InterpretError, VM};
+    let source = std::fs::read_to_string("./test/function/run_after_error.lox").unwrap();
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let failing = vm.compile(source.as_str()).ok().unwrap();
+    let good = vm.compile("print \"ok\";").ok().unwrap();
+    assert!(matches!(vm.run_function(failing), Err(InterpretError::Runtime)));
+    assert!(vm.run_function(good).is_ok());
+    drop(vm);
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "ok\n");
+}
+
+#[test]
+fn run_compiled_twice() {
+    use crate::vm::VM;
+    let source = std::fs::read_to_string("./test/function/run_compiled.lox").unwrap();
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let function = vm.compile(source.as_str()).ok().unwrap();
+    assert!(vm.interpret("var count = 0;".to_string()).is_ok());
+    assert!(vm.run_function(function.clone()).is_ok());
+    assert!(vm.run_function(function).is_ok());
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "1\n2\n");
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
 #[test]
 fn too_many_arguments() {
     test_error!(
         "./test/function/too_many_arguments.lox",
-        "[line 260] Error at 'a': Can't have more than 255 arguments.\n"
+        "[line 260] Error[E0014] at 'a': Can't have more than 255 arguments.\n"
     );
 }
 
@@ -88,6 +118,6 @@ fn too_many_arguments() {
 fn too_many_parameters() {
     test_error!(
         "./test/function/too_many_parameters.lox",
-        "[line 257] Error at 'a': Can't have more than 255 parameters.\n"
+        "[line 257] Error[E0014] at 'a': Can't have more than 255 parameters.\n"
     );
 }
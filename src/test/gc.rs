@@ -0,0 +1,35 @@
+#[test]
+fn drop_frees_everything() {
+    use crate::gc::allocations;
+    use crate::vm::VM;
+    //a thread of its own, so nothing another test allocated is counted
+    std::thread::spawn(|| {
+        let source = std::fs::read_to_string("./test/gc/drop.lox").unwrap();
+        let mut out = vec![];
+        let mut err = vec![];
+        {
+            let mut vm = VM::new(&mut out, &mut err);
+            assert!(vm.interpret(source).is_ok());
+            assert!(allocations() > 0);
+        }
+        assert_eq!(allocations(), 0);
+        assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "a\n2\ndone\n");
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn ptr_eq() {
+    use crate::gc::Gc;
+    use crate::object::{ObjArray, ObjClass, ObjInstance, ObjString};
+    //equal contents in separate allocations aren't the same object
+    let a = ObjArray::new(vec![]);
+    let b = ObjArray::new(vec![]);
+    assert!(!Gc::ptr_eq(&a, &b));
+    assert!(Gc::ptr_eq(&a, &a.clone()));
+    let class = ObjClass::new(ObjString::new("A".to_string()));
+    let instance = ObjInstance::new(class.clone());
+    assert!(!Gc::ptr_eq(&instance, &ObjInstance::new(class)));
+    assert!(Gc::ptr_eq(&instance, &instance.clone()));
+}
@@ -0,0 +1,43 @@
+#[test]
+fn call_result() {
+    test_output!("./test/getter/call_result.lox", "3\n");
+}
+
+#[test]
+fn field_shadows_getter() {
+    test_output!("./test/getter/field_shadows_getter.lox", "getter\nfield\n");
+}
+
+#[test]
+fn getter() {
+    test_output!("./test/getter/getter.lox", "12\n27\n");
+}
+
+#[test]
+fn inherited() {
+    test_output!("./test/getter/inherited.lox", "Base derived\n");
+}
+
+#[test]
+fn init_getter() {
+    test_error!(
+        "./test/getter/init_getter.lox",
+        "[line 2] Error[E0010] at 'init': An initializer can't be a getter.\n"
+    );
+}
+
+#[test]
+fn method_unaffected() {
+    test_output!(
+        "./test/getter/method_unaffected.lox",
+        "<fn method>\nmethod\ngetter\n"
+    );
+}
+
+#[test]
+fn runtime_error() {
+    test_error!(
+        "./test/getter/runtime_error.lox",
+        "Undefined property 'missing'.\n"
+    );
+}
@@ -2,7 +2,7 @@
 fn class_in_else() {
     test_error!(
         "./test/if/class_in_else.lox",
-        "[line 2] Error at 'class': Expect expression.\n"
+        "[line 2] Error[E0003] at 'class': Expect expression.\n"
     );
 }
 
@@ -10,7 +10,7 @@ fn class_in_else() {
 fn class_in_then() {
     test_error!(
         "./test/if/class_in_then.lox",
-        "[line 2] Error at 'class': Expect expression.\n"
+        "[line 2] Error[E0003] at 'class': Expect expression.\n"
     );
 }
 
@@ -24,11 +24,16 @@ fn else_() {
     test_output!("./test/if/else.lox", "good\ngood\nblock\n");
 }
 
+#[test]
+fn else_if() {
+    test_output!("./test/if/else_if.lox", "one\ntwo\nthree\nmany\n5\n");
+}
+
 #[test]
 fn fun_in_else() {
     test_error!(
         "./test/if/fun_in_else.lox",
-        "[line 2] Error at 'fun': Expect expression.\n"
+        "[line 2] Error[E0003] at 'fun': Expect expression.\n"
     );
 }
 
@@ -36,7 +41,7 @@ fn fun_in_else() {
 fn fun_in_then() {
     test_error!(
         "./test/if/fun_in_then.lox",
-        "[line 2] Error at 'fun': Expect expression.\n"
+        "[line 2] Error[E0003] at 'fun': Expect expression.\n"
     );
 }
 
@@ -45,16 +50,32 @@ fn if_() {
     test_output!("./test/if/if.lox", "good\nblock\ntrue\n");
 }
 
+#[test]
+fn negated_condition() {
+    test_output!(
+        "./test/if/negated_condition.lox",
+        "ne\neq\nle\ngt\nlt\nnot\nor\nor2\n3\n0\n1\nand\nfalse\n"
+    );
+}
+
 #[test]
 fn truth() {
     test_output!("./test/if/truth.lox", "false\nnil\ntrue\n0\nempty\n");
 }
 
+#[test]
+fn truthy_instance() {
+    test_output!(
+        "./test/if/truthy_instance.lox",
+        "empty\nfull\ntrue\nfalse\nplain\n"
+    );
+}
+
 #[test]
 fn var_in_else() {
     test_error!(
         "./test/if/var_in_else.lox",
-        "[line 2] Error at 'var': Expect expression.\n"
+        "[line 2] Error[E0003] at 'var': Expect expression.\n"
     );
 }
 
@@ -62,6 +83,6 @@ fn var_in_else() {
 fn var_in_then() {
     test_error!(
         "./test/if/var_in_then.lox",
-        "[line 2] Error at 'var': Expect expression.\n"
+        "[line 2] Error[E0003] at 'var': Expect expression.\n"
     );
 }
@@ -1,3 +1,11 @@
+#[test]
+fn chain() {
+    test_output!(
+        "./test/inheritance/chain.lox",
+        "A\nB.name\nC A.shout B.name\n<fn shout>\nA.create\nA.name\n"
+    );
+}
+
 #[test]
 fn constructor() {
     test_output!("./test/inheritance/constructor.lox", "value\n");
@@ -36,7 +44,7 @@ fn inherit_methods() {
 fn parenthesized_superclass() {
     test_error!(
         "./test/inheritance/parenthesized_superclass.lox",
-        "[line 4] Error at '(': Expect superclass name.\n"
+        "[line 4] Error[E0002] at '(': Expect superclass name.\n"
     );
 }
 
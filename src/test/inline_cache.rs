@@ -0,0 +1,24 @@
+#[test]
+fn field_order() {
+    test_output_and_error!(
+        "./test/inline_cache/field_order.lox",
+        "first x\nsecond x\nthird x\nfirst x\nfirst y\nsecond y\n",
+        "Undefined property 'y'.\n"
+    );
+}
+
+#[test]
+fn polymorphic() {
+    test_output!(
+        "./test/inline_cache/polymorphic.lox",
+        "a field\n<fn name>\nc getter\nd fallback\na field\n<fn name>\nc getter\nd fallback\n"
+    );
+}
+
+#[test]
+fn shadowed_method() {
+    test_output!(
+        "./test/inline_cache/shadowed_method.lox",
+        "method\n1\nmethod\n"
+    );
+}
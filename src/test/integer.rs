@@ -0,0 +1,48 @@
+#[test]
+fn arithmetic() {
+    test_output!(
+        "./test/integer/arithmetic.lox",
+        "100000000\n123456790\n-1000000\n-5000000\n30000000\ntrue\ntrue\ntrue\n7000000\n-7000000\n"
+    );
+}
+
+#[test]
+fn equality() {
+    test_output!(
+        "./test/integer/equality.lox",
+        "true\ntrue\nfalse\ntrue\ntrue\none\ntwo\n2\n"
+    );
+}
+
+#[test]
+fn natives() {
+    test_output!(
+        "./test/integer/natives.lox",
+        "30\n5000000\n3\n-4\n-4\n3\n2000000\n3.00\nababab\n"
+    );
+}
+
+#[test]
+fn promotion() {
+    test_output!(
+        "./test/integer/promotion.lox",
+        "1e+08\n1e+08\n3.5\n1.5\n3.5\n-3.5\n9.22337e+18\n-9.22337e+18\n9.22337e+18\n1e+20\n9.22337e+18\n0.5\n"
+    );
+}
+
+#[test]
+fn serialize_round_trip() {
+    use crate::chunk::serialize::{deserialize_function, serialize_function};
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+
+    let mut globals = GlobalSlots::new();
+    let function = compile("print 10000000000;", &mut globals, &mut std::io::sink())
+        .ok()
+        .unwrap();
+    let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    let function = deserialize_function(&bytes, &mut globals).ok().unwrap();
+    let function = function.borrow();
+    let constant = function.chunk.borrow().constants[0].as_int();
+    assert!(constant.is_ok_and(|integer| integer == 10000000000));
+}
@@ -0,0 +1,20 @@
+#[test]
+fn inheritance() {
+    test_output!(
+        "./test/is/inheritance.lox",
+        "true\ntrue\nfalse\nfalse\ntrue\ntrue\ntrue\nfalse\ntrue\n"
+    );
+}
+
+#[test]
+fn non_instance() {
+    test_output!("./test/is/non_instance.lox", "false\nfalse\nfalse\nfalse\n");
+}
+
+#[test]
+fn not_a_class() {
+    test_error!(
+        "./test/is/not_a_class.lox",
+        "Right operand of 'is' must be a class.\n"
+    );
+}
@@ -1,8 +1,100 @@
+#[test]
+fn deep_recursion() {
+    //only the innermost calls are listed
+    test_error!(
+        "./test/limit/deep_recursion.lox",
+        "Stack overflow.\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         [line 2] in count\n\
+         ... 54 more calls\n"
+    );
+}
+
 #[test]
 fn loop_too_large() {
     test_error!(
         "./test/limit/loop_too_large.lox",
-        "[line 2351] Error at '}': Loop body too large.\n"
+        "[line 2351] Error[E0015] at '}': Loop body too large.\n"
+    );
+}
+
+#[test]
+fn many_globals() {
+    use crate::vm::VM;
+    //global names are operands of their own, not constants, so far more than 256 of them fit
+    //in one chunk
+    let mut source = String::new();
+    for i in 0..600 {
+        source += &format!("var global{} = {};\n", i, i % 200);
+    }
+    source += "fun total() {\n  var sum = 0;\n";
+    for i in 0..600 {
+        source += &format!("  sum = sum + global{};\n", i);
+    }
+    source += "  return sum;\n}\n";
+    source += "global599 = 1;\nprint global599;\nprint total();\n";
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.interpret(source).is_ok());
+    drop(vm);
+    let total = (0..599).map(|i| i % 200).sum::<i32>() + 1;
+    assert_eq!(
+        std::str::from_utf8(out.as_slice()).unwrap(),
+        format!("1\n{}\n", total)
+    );
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
+#[test]
+fn max_frames() {
+    use crate::run_file;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.set_max_frames(8);
+    run_file(&mut vm, "./test/limit/max_frames.lox".to_string());
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "5\n");
+    //short enough to list every call
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "Stack overflow.\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 3] in depth\n\
+         [line 7] in script\n"
+    );
+}
+
+#[test]
+fn mutual_recursion() {
+    test_error!(
+        "./test/limit/mutual_recursion.lox",
+        "Stack overflow.\n\
+         [line 2] in ping\n\
+         [line 6] in pong\n\
+         [line 2] in ping\n\
+         [line 6] in pong\n\
+         [line 2] in ping\n\
+         [line 6] in pong\n\
+         [line 2] in ping\n\
+         [line 6] in pong\n\
+         [line 2] in ping\n\
+         [line 6] in pong\n\
+         ... 54 more calls\n"
     );
 }
 
@@ -10,20 +102,35 @@ fn loop_too_large() {
 fn no_reuse_constants() {
     test_error!(
         "./test/limit/no_reuse_constants.lox",
-        "[line 35] Error at '1': Too many constants in one chunk.\n"
+        "[line 35] Error[E0012] at '1001': Too many constants in one chunk.\n"
     );
 }
 
 #[test]
 fn stack_overflow() {
-    test_error!("./test/limit/stack_overflow.lox", "Stack overflow.\n");
+    //the value stack fills up first; the innermost call was declaring `a16`
+    test_error!(
+        "./test/limit/stack_overflow.lox",
+        "Stack overflow.\n\
+         [line 17] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         [line 18] in foo\n\
+         ... 6 more calls\n"
+    );
 }
 
 #[test]
 fn too_many_constants() {
     test_error!(
         "./test/limit/too_many_constants.lox",
-        "[line 35] Error at '\"oops\"': Too many constants in one chunk.\n"
+        "[line 35] Error[E0012] at '\"oops\"': Too many constants in one chunk.\n"
     );
 }
 
@@ -31,7 +138,7 @@ fn too_many_constants() {
 fn too_many_locals() {
     test_error!(
         "./test/limit/too_many_locals.lox",
-        "[line 52] Error at 'oops': Too many local variables in function.\n"
+        "[line 52] Error[E0013] at 'oops': Too many local variables in function.\n"
     );
 }
 
@@ -39,6 +146,6 @@ fn too_many_locals() {
 fn too_many_upvalues() {
     test_error!(
         "./test/limit/too_many_upvalues.lox",
-        "[line 102] Error at 'oops': Too many closure variables in function.\n"
+        "[line 102] Error[E0013] at 'oops': Too many closure variables in function.\n"
     );
 }
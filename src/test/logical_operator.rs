@@ -1,3 +1,11 @@
+#[test]
+fn and() {
+    test_output!(
+        "./test/logical_operator/and.lox",
+        "false\n1\nfalse\ntrue\n3\ntrue\nfalse\n"
+    );
+}
+
 #[test]
 fn and_truth() {
     test_output!(
@@ -7,18 +15,18 @@ fn and_truth() {
 }
 
 #[test]
-fn and() {
+fn coalesce() {
     test_output!(
-        "./test/logical_operator/and.lox",
-        "false\n1\nfalse\ntrue\n3\ntrue\nfalse\n"
+        "./test/logical_operator/coalesce.lox",
+        "5\nfalse\n0\n\nnil\nlast\n1\nside\n2\n3\ndefault\n"
     );
 }
 
 #[test]
-fn or_truth() {
+fn coalesce_bool_method() {
     test_output!(
-        "./test/logical_operator/or_truth.lox",
-        "ok\nok\ntrue\n0\ns\n"
+        "./test/logical_operator/coalesce_bool_method.lox",
+        "true\nfallback\n"
     );
 }
 
@@ -29,3 +37,27 @@ fn or() {
         "1\n1\ntrue\nfalse\nfalse\nfalse\ntrue\n"
     );
 }
+
+#[test]
+fn or_truth() {
+    test_output!(
+        "./test/logical_operator/or_truth.lox",
+        "ok\nok\ntrue\n0\ns\n"
+    );
+}
+
+#[test]
+fn single_question() {
+    test_error!(
+        "./test/logical_operator/single_question.lox",
+        "[line 1] Error[E0004]: Unexpected character.\n"
+    );
+}
+
+#[test]
+fn xor() {
+    test_output!(
+        "./test/logical_operator/xor.lox",
+        "true\ntrue\nfalse\nfalse\ntrue\nfalse\nfalse\ntrue\nafter\ntrue\ntrue\ntrue\nbefore\n"
+    );
+}
@@ -0,0 +1,61 @@
+#[test]
+fn for_in() {
+    test_output!("./test/map/for_in.lox", "3\n[a, b, aa, bb]\n");
+}
+
+#[test]
+fn has() {
+    test_output!("./test/map/has.lox", "true\ntrue\nfalse\nfalse\n");
+}
+
+#[test]
+fn has_non_map() {
+    test_error!(
+        "./test/map/has_non_map.lox",
+        "First argument to 'map_has' must be a map.\n"
+    );
+}
+
+#[test]
+fn has_unhashable_key() {
+    test_error!(
+        "./test/map/has_unhashable_key.lox",
+        "Map key must be nil, a boolean, a number, or a string.\n"
+    );
+}
+
+#[test]
+fn index() {
+    test_output!("./test/map/index.lox", "1\ntwo\nnil\nzero\nzero\n");
+}
+
+#[test]
+fn new() {
+    test_output!("./test/map/new.lox", "{}\n{a: 1, 2: true, nil: c}\n");
+}
+
+#[test]
+fn order() {
+    test_output!(
+        "./test/map/order.lox",
+        "{zebra: 1, 3: three, apple: 3, true: 4, -1: 5}\n[zebra, 3, apple, true, -1]\n[1, three, 3, 4, 5]\nzebra\n3\napple\ntrue\n-1\n"
+    );
+}
+
+#[test]
+fn set_index() {
+    test_output!("./test/map/set_index.lox", "{a: 3, b: 2}\n");
+}
+
+#[test]
+fn undefined_key() {
+    test_error!("./test/map/undefined_key.lox", "Undefined key 'b'.\n");
+}
+
+#[test]
+fn unhashable_key() {
+    test_error!(
+        "./test/map/unhashable_key.lox",
+        "Map key must be nil, a boolean, a number, or a string.\n"
+    );
+}
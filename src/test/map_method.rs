@@ -0,0 +1,36 @@
+#[test]
+fn contains() {
+    test_output!(
+        "./test/map_method/contains.lox",
+        "true\ntrue\ntrue\nfalse\nfalse\nfalse\n"
+    );
+}
+
+#[test]
+fn contains_unhashable_key() {
+    test_error!(
+        "./test/map_method/contains_unhashable_key.lox",
+        "Map key must be nil, a boolean, a number, or a string.\n"
+    );
+}
+
+#[test]
+fn fresh() {
+    test_output!("./test/map_method/fresh.lox", "false\n[a]\nfalse\n");
+}
+
+#[test]
+fn keys_values() {
+    test_output!(
+        "./test/map_method/keys_values.lox",
+        "[]\n[]\n[one, two, 3]\n[1, 2, three]\ntrue\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn wrong_arity() {
+    test_error!(
+        "./test/map_method/wrong_arity.lox",
+        "Expected 0 arguments but got 1.\n"
+    );
+}
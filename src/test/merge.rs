@@ -0,0 +1,68 @@
+//both halves are compiled with the globals of the VM that runs them
+#[cfg(test)]
+fn compile_chunk(
+    vm: &mut crate::vm::VM<'_, Vec<u8>, Vec<u8>>,
+    source: &str,
+) -> crate::chunk::Chunk {
+    let function = vm.compile(source).unwrap();
+    let function = function.borrow();
+    let chunk = function.chunk.borrow().clone();
+    chunk
+}
+
+#[test]
+fn merged_chunks_run_in_order() {
+    use crate::gc::Gc;
+    use crate::object::ObjFunction;
+    use crate::vm::VM;
+
+    //both halves use constants, closures and property caches, so all of them get relocated
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    let first = compile_chunk(
+        &mut vm,
+        "var greeting = \"hello\";
+        class Point { init(x) { this.x = x; } }
+        if (greeting == \"hello\") print Point(1).x;",
+    );
+    let second = compile_chunk(
+        &mut vm,
+        "fun shout(text) { return text + \"!\"; }
+        print shout(greeting);
+        var p = Point(2.5);
+        print p.x;
+        for (var i = 0; i < 2; i = i + 1) print \"loop\";",
+    );
+    let merged = first.merge(&second).unwrap();
+    assert_eq!(
+        merged.constants.len(),
+        first.constants.len() + second.constants.len()
+    );
+
+    let function = ObjFunction::new(None);
+    function.borrow_mut().chunk = Gc::new(merged);
+    function.borrow_mut().local_count = 1;
+    assert!(vm.run_function(function).is_ok());
+    assert_eq!(
+        std::str::from_utf8(out.as_slice()).unwrap(),
+        "1\nhello!\n2.5\nloop\nloop\n"
+    );
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
+#[test]
+fn too_many_constants() {
+    use crate::chunk::merge::MergeError;
+    use crate::chunk::Chunk;
+    use crate::value::value::Value;
+
+    let mut chunk = Chunk::new();
+    for i in 0..200 {
+        chunk.add_constant(Value::number(i as f64));
+    }
+    assert_eq!(
+        chunk.merge(&chunk).err(),
+        Some(MergeError::TooManyConstants)
+    );
+}
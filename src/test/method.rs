@@ -52,7 +52,7 @@ fn refer_to_name() {
 fn too_many_arguments() {
     test_error!(
         "./test/method/too_many_arguments.lox",
-        "[line 259] Error at 'a': Can't have more than 255 arguments.\n"
+        "[line 259] Error[E0014] at 'a': Can't have more than 255 arguments.\n"
     );
 }
 
@@ -60,6 +60,6 @@ fn too_many_arguments() {
 fn too_many_parameters() {
     test_error!(
         "./test/method/too_many_parameters.lox",
-        "[line 258] Error at 'a': Can't have more than 255 parameters.\n"
+        "[line 258] Error[E0014] at 'a': Can't have more than 255 parameters.\n"
     );
 }
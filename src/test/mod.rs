@@ -42,6 +42,28 @@ macro_rules! test_output_and_error {
     };
 }
 
+//runs the script at `path` on a VM that `setup` configures first, for the options the macros
+//above don't set, and returns the exit code, the output and the errors
+#[cfg(test)]
+pub fn run_configured(
+    path: &str,
+    setup: impl FnOnce(&mut crate::vm::VM<'_, Vec<u8>, Vec<u8>>),
+) -> (i32, String, String) {
+    use crate::run_file;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    setup(&mut vm);
+    let code = run_file(&mut vm, path.to_string());
+    drop(vm);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
 #[test]
 fn empty_file() {
     test_output!("./test/empty_file.lox", "");
@@ -59,34 +81,71 @@ fn precedence() {
 fn unexpected_character() {
     test_error!(
         "./test/unexpected_character.lox",
-        "[line 3] Error: Unexpected character.\n"
+        "[line 3] Error[E0004]: Unexpected character.\n"
     );
 }
 
+mod array;
+mod array_method;
+mod assert;
+mod assert_throws;
 mod assignment;
 mod block;
 mod bool;
 mod call;
 mod class;
+mod cli;
 mod closure;
 mod comments;
 mod constructor;
+mod disassemble;
+mod eval;
+mod exit;
 mod field;
 mod for_;
 mod function;
+mod gc;
+mod getter;
 mod if_;
 mod inheritance;
+mod inline_cache;
+#[cfg(feature = "integers")]
+mod integer;
+mod is;
 mod limit;
 mod logical_operator;
+mod map;
+mod map_method;
+mod merge;
 mod method;
+mod nan;
 mod nil;
 mod number;
 mod operator;
 mod print;
+mod property_fallback;
 mod regression;
+mod relaxed;
+mod repl;
 mod return_;
+mod sandbox;
+mod semicolon;
+mod serialize;
+mod snippet;
+mod static_method;
+mod strict;
 mod string;
+mod string_method;
 mod super_;
+mod tail_call;
 mod this;
+mod throw;
+mod time;
+mod to_string;
+mod truthiness;
+mod try_;
+mod unreachable;
+mod unused;
 mod variable;
+mod verify;
 mod while_;
@@ -0,0 +1,50 @@
+//these scripts make NaN with `0/0`, which is an error unless division follows IEEE 754
+#[cfg(test)]
+pub(super) fn run_ieee(path: &str) -> (String, String) {
+    use crate::run_file;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.set_ieee_division(true);
+    run_file(&mut vm, path.to_string());
+    (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+#[test]
+fn array() {
+    assert_eq!(
+        run_ieee("./test/nan/array.lox"),
+        (
+            "false\ntrue\nfalse\nfalse\n-1\nNaN\n".to_string(),
+            "".to_string()
+        )
+    );
+}
+
+#[test]
+fn comparison() {
+    assert_eq!(
+        run_ieee("./test/nan/comparison.lox"),
+        ("false\nfalse\ntrue\ntrue\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn divide_by_zero() {
+    assert_eq!(
+        run_ieee("./test/nan/divide_by_zero.lox"),
+        ("inf\n-inf\nNaN\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn map_key() {
+    assert_eq!(
+        run_ieee("./test/nan/map_key.lox"),
+        (
+            "first\ntrue\ntrue\nsecond\n{NaN: second}\n".to_string(),
+            "".to_string()
+        )
+    );
+}
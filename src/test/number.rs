@@ -2,7 +2,85 @@
 fn decimal_point_at_eof() {
     test_error!(
         "./test/number/decimal_point_at_eof.lox",
-        "[line 2] Error at end: Expect property name after '.'.\n"
+        "[line 2] Error[E0002] at end: Expect property name after '.'.\n"
+    );
+}
+
+#[test]
+fn divide_by_zero() {
+    test_error!("./test/number/divide_by_zero.lox", "Division by zero.\n");
+}
+
+#[test]
+fn divide_by_zero_nonnum() {
+    test_error!(
+        "./test/number/divide_by_zero_nonnum.lox",
+        "Left operand of '/' must be a number, got string.\n"
+    );
+}
+
+#[test]
+fn floor_div() {
+    test_output!(
+        "./test/number/floor_div.lox",
+        "3\n-4\n-4\n2\n3\n3.5\n-3.5\n"
+    );
+}
+
+#[test]
+fn floor_div_by_zero() {
+    test_error!("./test/number/floor_div_by_zero.lox", "Division by zero.\n");
+}
+
+#[test]
+fn floor_div_non_number() {
+    test_error!(
+        "./test/number/floor_div_non_number.lox",
+        "Arguments to 'floor_div' must be numbers.\n"
+    );
+}
+
+//with integers, whole-number literals print in full
+#[cfg(not(feature = "integers"))]
+#[test]
+fn format() {
+    test_output!(
+        "./test/number/format.lox",
+        "0.3\n0.333333\n0.666667\n100\n123456\n1.23457e+06\n1e+06\n1e+06\n1e+21\n-2.5e+18\n0.0001\n1e-05\n1.23457e-05\n1e-16\n3.14159\n-42.5\n"
+    );
+}
+
+//with integers, -0 is the integer 0
+#[cfg(not(feature = "integers"))]
+#[test]
+fn format_number() {
+    test_output!(
+        "./test/number/format_number.lox",
+        "3.14\n2.7\n0\n2\n-1.00\n42.000\n1000000.0\n-0.0\n0.30000000000000004\n"
+    );
+}
+
+#[test]
+fn format_number_fractional_decimals() {
+    test_error!(
+        "./test/number/format_number_fractional_decimals.lox",
+        "Decimal places must be an integer from 0 to 100.\n"
+    );
+}
+
+#[test]
+fn format_number_negative_decimals() {
+    test_error!(
+        "./test/number/format_number_negative_decimals.lox",
+        "Decimal places must be an integer from 0 to 100.\n"
+    );
+}
+
+#[test]
+fn format_number_non_number() {
+    test_error!(
+        "./test/number/format_number_non_number.lox",
+        "First argument to 'format_number' must be a number.\n"
     );
 }
 
@@ -10,10 +88,12 @@ fn decimal_point_at_eof() {
 fn leading_dot() {
     test_error!(
         "./test/number/leading_dot.lox",
-        "[line 2] Error at '.': Expect expression.\n"
+        "[line 2] Error[E0003] at '.': Expect expression.\n"
     );
 }
 
+//with integers, -0 is the integer 0
+#[cfg(not(feature = "integers"))]
 #[test]
 fn literals() {
     test_output!(
@@ -24,16 +104,39 @@ fn literals() {
 
 #[test]
 fn nan_equality() {
-    test_output!(
-        "./test/number/nan_equality.lox",
-        "false\ntrue\nfalse\ntrue\n"
+    use super::nan::run_ieee;
+    assert_eq!(
+        run_ieee("./test/number/nan_equality.lox"),
+        ("false\ntrue\nfalse\ntrue\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn small_integers_use_no_constants() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    use crate::vm::VM;
+    let mut globals = GlobalSlots::new();
+    let source = "print 0; print 255; print 256; print 1.5; print -3; print 7 * 6;";
+    let function = compile(source, &mut globals, &mut std::io::sink()).ok().unwrap();
+    //only 256 and 1.5 don't fit in a byte
+    assert_eq!(function.borrow().chunk.borrow().constants.len(), 2);
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.run_function(function).is_ok());
+    drop(vm);
+    assert_eq!(
+        std::str::from_utf8(out.as_slice()).unwrap(),
+        "0\n255\n256\n1.5\n-3\n42\n"
     );
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
 }
 
 #[test]
 fn trailing_dot() {
     test_error!(
         "./test/number/trailing_dot.lox",
-        "[line 2] Error at ';': Expect property name after '.'.\n"
+        "[line 2] Error[E0002] at ';': Expect property name after '.'.\n"
     );
 }
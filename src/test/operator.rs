@@ -1,3 +1,8 @@
+#[test]
+fn add() {
+    test_output!("./test/operator/add.lox", "579\nstring\n");
+}
+
 #[test]
 fn add_bool_nil() {
     test_error!(
@@ -47,8 +52,11 @@ fn add_string_nil() {
 }
 
 #[test]
-fn add() {
-    test_output!("./test/operator/add.lox", "579\nstring\n");
+fn compare_in_condition_nonnum() {
+    test_error!(
+        "./test/operator/compare_in_condition_nonnum.lox",
+        "Right operand of '>=' must be a number, got nil.\n"
+    );
 }
 
 #[test]
@@ -56,11 +64,27 @@ fn comparison() {
     test_output!("./test/operator/comparison.lox", "true\nfalse\nfalse\ntrue\ntrue\nfalse\nfalse\nfalse\ntrue\nfalse\ntrue\ntrue\nfalse\nfalse\nfalse\nfalse\ntrue\ntrue\ntrue\ntrue\n");
 }
 
+//with integers, -0 is the integer 0
+#[cfg(not(feature = "integers"))]
+
+#[test]
+fn constant_folding() {
+    test_output!(
+        "./test/operator/constant_folding.lox",
+        "14\n14\n-0.25\n-0.25\n-0\n-0\n-10\n-10\ntrue\ntrue\ntrue\nabc\nabc\ntrue\nfalse\n"
+    );
+}
+
+#[test]
+fn divide() {
+    test_output!("./test/operator/divide.lox", "4\n1\n");
+}
+
 #[test]
 fn divide_nonnum_num() {
     test_error!(
         "./test/operator/divide_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '/' must be a number, got string.\n"
     );
 }
 
@@ -68,13 +92,60 @@ fn divide_nonnum_num() {
 fn divide_num_nonnum() {
     test_error!(
         "./test/operator/divide_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '/' must be a number, got string.\n"
     );
 }
 
 #[test]
-fn divide() {
-    test_output!("./test/operator/divide.lox", "4\n1\n");
+fn equality_table() {
+    use crate::object::{ObjArray, ObjClass, ObjInstance, ObjMap, ObjString};
+    use crate::table::Table;
+    use crate::value::value::Value;
+    //only uses constructors both `Value` representations have, so the same table is checked
+    //with and without --features nan-boxing
+    let string = |s: &str| Value::string(ObjString::new(s.to_string()));
+    let class = ObjClass::new(ObjString::new("A".to_string()));
+    let instance = ObjInstance::new(class.clone());
+    let array = ObjArray::new(vec![]);
+    let values = [
+        Value::nil(),
+        Value::uninitialized(),
+        Value::bool_(true),
+        Value::bool_(false),
+        Value::number(1.0),
+        Value::number(0.0),
+        string("1"),
+        string("a"),
+        Value::class(class.clone()),
+        Value::instance(instance.clone()),
+        Value::instance(ObjInstance::new(class)),
+        Value::array(array.clone()),
+        Value::array(ObjArray::new(vec![])),
+        Value::map(ObjMap::new(Table::new())),
+    ];
+    for (i, a) in values.iter().enumerate() {
+        for (j, b) in values.iter().enumerate() {
+            assert_eq!(a == b, i == j, "{} == {}", a, b);
+        }
+    }
+    assert!(Value::number(0.0) == Value::number(-0.0));
+    assert!(string("a") == string(&"ab"[..1]));
+    assert!(Value::instance(instance.clone()) == Value::instance(instance));
+    assert!(Value::array(array.clone()) == Value::array(array));
+    assert!(Value::int(1) == Value::number(1.0));
+    let nan = Value::number(f64::NAN);
+    assert!(nan.is_number());
+    assert!(nan != nan.clone());
+    //the negative NaN x86 produces for 0/0 is still a number
+    assert!(Value::number(-f64::NAN) != Value::number(-f64::NAN));
+}
+
+#[test]
+fn equals() {
+    test_output!(
+        "./test/operator/equals.lox",
+        "true\ntrue\nfalse\ntrue\nfalse\ntrue\nfalse\nfalse\nfalse\nfalse\n"
+    );
 }
 
 #[test]
@@ -86,23 +157,47 @@ fn equals_class() {
 }
 
 #[test]
-fn equals_method() {
-    test_output!("./test/operator/equals_method.lox", "true\nfalse\n");
+fn equals_custom() {
+    test_output!(
+        "./test/operator/equals_custom.lox",
+        "true\nfalse\nfalse\ntrue\ntrue\nfalse\nfalse\n"
+    );
 }
 
 #[test]
-fn equals() {
+fn equals_custom_recursion() {
     test_output!(
-        "./test/operator/equals.lox",
-        "true\ntrue\nfalse\ntrue\nfalse\ntrue\nfalse\nfalse\nfalse\nfalse\n"
+        "./test/operator/equals_custom_recursion.lox",
+        "equals\nfalse\nequals\ntrue\n"
     );
 }
 
+#[test]
+fn equals_custom_truthiness() {
+    test_output!(
+        "./test/operator/equals_custom_truthiness.lox",
+        "true\nfalse\ntrue\n"
+    );
+}
+
+#[test]
+fn equals_instance() {
+    test_output!(
+        "./test/operator/equals_instance.lox",
+        "true\nfalse\nfalse\ntrue\n"
+    );
+}
+
+#[test]
+fn equals_method() {
+    test_output!("./test/operator/equals_method.lox", "true\nfalse\n");
+}
+
 #[test]
 fn greater_nonnum_num() {
     test_error!(
         "./test/operator/greater_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '>' must be a number, got string.\n"
     );
 }
 
@@ -110,7 +205,7 @@ fn greater_nonnum_num() {
 fn greater_num_nonnum() {
     test_error!(
         "./test/operator/greater_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '>' must be a number, got string.\n"
     );
 }
 
@@ -118,7 +213,7 @@ fn greater_num_nonnum() {
 fn greater_or_equal_nonnum_num() {
     test_error!(
         "./test/operator/greater_or_equal_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '>=' must be a number, got string.\n"
     );
 }
 
@@ -126,7 +221,7 @@ fn greater_or_equal_nonnum_num() {
 fn greater_or_equal_num_nonnum() {
     test_error!(
         "./test/operator/greater_or_equal_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '>=' must be a number, got string.\n"
     );
 }
 
@@ -134,7 +229,7 @@ fn greater_or_equal_num_nonnum() {
 fn less_nonnum_num() {
     test_error!(
         "./test/operator/less_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '<' must be a number, got string.\n"
     );
 }
 
@@ -142,7 +237,7 @@ fn less_nonnum_num() {
 fn less_num_nonnum() {
     test_error!(
         "./test/operator/less_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '<' must be a number, got string.\n"
     );
 }
 
@@ -150,7 +245,7 @@ fn less_num_nonnum() {
 fn less_or_equal_nonnum_num() {
     test_error!(
         "./test/operator/less_or_equal_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '<=' must be a number, got string.\n"
     );
 }
 
@@ -158,15 +253,51 @@ fn less_or_equal_nonnum_num() {
 fn less_or_equal_num_nonnum() {
     test_error!(
         "./test/operator/less_or_equal_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '<=' must be a number, got string.\n"
     );
 }
 
+#[test]
+fn multiline_error() {
+    //the operator is reported on its own line, not where its right operand ends
+    assert_eq!(
+        super::run_configured(
+            "./test/operator/multiline_error.lox",
+            |vm| vm.set_backtrace(usize::MAX),
+        ).2,
+        "Operands must be two numbers or two strings.\n[line 3] in total\n[line 7] in script\n"
+    );
+}
+
+#[test]
+fn multiline_error_default() {
+    test_error!(
+        "./test/operator/multiline_error.lox",
+        "Operands must be two numbers or two strings.\n"
+    );
+}
+
+#[test]
+fn multiline_negate() {
+    assert_eq!(
+        super::run_configured(
+            "./test/operator/multiline_negate.lox",
+            |vm| vm.set_backtrace(usize::MAX),
+        ).2,
+        "Operand must be a number.\n[line 2] in script\n"
+    );
+}
+
+#[test]
+fn multiply() {
+    test_output!("./test/operator/multiply.lox", "15\n3.702\n");
+}
+
 #[test]
 fn multiply_nonnum_num() {
     test_error!(
         "./test/operator/multiply_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '*' must be a number, got boolean.\n"
     );
 }
 
@@ -174,13 +305,13 @@ fn multiply_nonnum_num() {
 fn multiply_num_nonnum() {
     test_error!(
         "./test/operator/multiply_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '*' must be a number, got string.\n"
     );
 }
 
 #[test]
-fn multiply() {
-    test_output!("./test/operator/multiply.lox", "15\n3.702\n");
+fn negate() {
+    test_output!("./test/operator/negate.lox", "-3\n3\n-3\n");
 }
 
 #[test]
@@ -192,8 +323,19 @@ fn negate_nonnum() {
 }
 
 #[test]
-fn negate() {
-    test_output!("./test/operator/negate.lox", "-3\n3\n-3\n");
+fn negated_compare_nonnum() {
+    test_error!(
+        "./test/operator/negated_compare_nonnum.lox",
+        "Right operand of '>' must be a number, got nil.\n"
+    );
+}
+
+#[test]
+fn not() {
+    test_output!(
+        "./test/operator/not.lox",
+        "false\ntrue\ntrue\nfalse\nfalse\ntrue\nfalse\nfalse\n"
+    );
 }
 
 #[test]
@@ -210,18 +352,15 @@ fn not_equals() {
 }
 
 #[test]
-fn not() {
-    test_output!(
-        "./test/operator/not.lox",
-        "false\ntrue\ntrue\nfalse\nfalse\ntrue\nfalse\nfalse\n"
-    );
+fn subtract() {
+    test_output!("./test/operator/subtract.lox", "1\n0\n");
 }
 
 #[test]
 fn subtract_nonnum_num() {
     test_error!(
         "./test/operator/subtract_nonnum_num.lox",
-        "Operands must be numbers.\n"
+        "Left operand of '-' must be a number, got string.\n"
     );
 }
 
@@ -229,11 +368,6 @@ fn subtract_nonnum_num() {
 fn subtract_num_nonnum() {
     test_error!(
         "./test/operator/subtract_num_nonnum.lox",
-        "Operands must be numbers.\n"
+        "Right operand of '-' must be a number, got string.\n"
     );
 }
-
-#[test]
-fn subtract() {
-    test_output!("./test/operator/subtract.lox", "1\n0\n");
-}
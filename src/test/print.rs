@@ -2,6 +2,6 @@
 fn missing_argument() {
     test_error!(
         "./test/print/missing_argument.lox",
-        "[line 2] Error at ';': Expect expression.\n"
+        "[line 2] Error[E0003] at ';': Expect expression.\n"
     );
 }
@@ -0,0 +1,36 @@
+#[test]
+fn call() {
+    test_output!(
+        "./test/property_fallback/call.lox",
+        "greet(bob)\nwave(alice)\n"
+    );
+}
+
+#[test]
+fn get() {
+    test_output!(
+        "./test/property_fallback/get.lox",
+        "get anything\nget other\nreal\nmethod\n"
+    );
+}
+
+#[test]
+fn no_recursion() {
+    test_error!(
+        "./test/property_fallback/no_recursion.lox",
+        "Undefined property 'missing'.\n"
+    );
+}
+
+#[test]
+fn set() {
+    test_output!(
+        "./test/property_fallback/set.lox",
+        "1\n2\n{x: 1, y: 2}\nreplaced\n"
+    );
+}
+
+#[test]
+fn unset() {
+    test_output!("./test/property_fallback/unset.lox", "record\nnil\n");
+}
@@ -0,0 +1,105 @@
+#[test]
+fn named_after_positional() {
+    assert_eq!(
+        super::run_configured(
+            "./test/relaxed/named_after_positional.lox",
+            |vm| vm.set_relaxed(true),
+        ),
+        (65, "".to_string(), "[line 4] Error[E0016] at '4': Positional arguments can't follow named arguments.\n".to_string())
+    );
+}
+
+#[test]
+fn named_arguments() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/named_arguments.lox", |vm| vm.set_relaxed(true)),
+        (
+            0,
+            "12\n10\nann oslo\n30\na\nb\nab\n9\na,b\ndone\n".to_string(),
+            "".to_string()
+        )
+    );
+}
+
+#[test]
+fn named_arguments_default() {
+    test_error!(
+        "./test/relaxed/named_arguments.lox",
+        "[line 9] Error[E0002] at ':': Expect ')' after arguments.\n[line 10] Error[E0002] at ':': Expect ')' after arguments.\n[line 11] Error[E0002] at ':': Expect ')' after arguments.\n[line 22] Error[E0002] at ':': Expect ')' after arguments.\n[line 29] Error[E0002] at ':': Expect ')' after arguments.\n[line 34] Error[E0002] at ':': Expect ')' after arguments.\n[line 41] Error[E0002] at ':': Expect ')' after arguments.\n[line 43] Error[E0002] at ':': Expect ')' after arguments.\n"
+    );
+}
+
+#[test]
+fn named_missing() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/named_missing.lox", |vm| vm.set_relaxed(true)),
+        (65, "".to_string(), "[line 4] Error[E0016] at ')': Missing an argument for parameter 'width'.\n".to_string())
+    );
+}
+
+#[test]
+fn named_not_function() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/named_not_function.lox", |vm| vm.set_relaxed(true)),
+        (65, "".to_string(), "[line 6] Error[E0016] at 'width': Named arguments need a function declared in scope.\n[line 11] Error[E0016] at 'width': Named arguments need a function declared in scope.\n".to_string())
+    );
+}
+
+#[test]
+fn named_twice() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/named_twice.lox", |vm| vm.set_relaxed(true)),
+        (65, "".to_string(), "[line 4] Error[E0016] at 'width': Parameter 'width' already has an argument.\n".to_string())
+    );
+}
+
+#[test]
+fn named_unknown() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/named_unknown.lox", |vm| vm.set_relaxed(true)),
+        (65, "".to_string(), "[line 4] Error[E0016] at 'depth': No parameter named 'depth'.\n".to_string())
+    );
+}
+
+#[test]
+fn print_many() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/print_many.lox", |vm| vm.set_relaxed(true)),
+        (0, "a 1 nil true\n3 three\na\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn print_many_default() {
+    test_error!(
+        "./test/relaxed/print_many.lox",
+        "[line 2] Error[E0002] at ',': Expect ';' after value.\n[line 3] Error[E0002] at ',': Expect ';' after value.\n"
+    );
+}
+
+#[test]
+fn unary_plus() {
+    assert_eq!(
+        super::run_configured("./test/relaxed/unary_plus.lox", |vm| vm.set_relaxed(true)),
+        (0, "true\n-3\n-3\n6\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn unary_plus_default() {
+    test_error!(
+        "./test/relaxed/unary_plus.lox",
+        "[line 1] Error[E0003] at '+': Expect expression.\n[line 2] Error[E0003] at '+': Expect expression.\n[line 3] Error[E0003] at '+': Expect expression.\n[line 5] Error[E0003] at '+': Expect expression.\n"
+    );
+}
+
+#[test]
+fn unary_plus_non_number() {
+    assert_eq!(
+        super::run_configured(
+            "./test/relaxed/unary_plus_non_number.lox",
+            |vm| vm.set_relaxed(true),
+        ),
+        (70, "".to_string(), "Operand must be a number.\n".to_string())
+    );
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+fn run_lines(lines: &[&str]) -> (String, String) {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.set_repl_mode(true);
+    for line in lines {
+        vm.interpret(line.to_string()).ok();
+    }
+    (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+#[test]
+fn expression_prints_value() {
+    assert_eq!(run_lines(&["1 + 2\n"]), ("3\n".to_string(), "".to_string()));
+}
+
+#[test]
+fn off_outside_repl() {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.interpret("1 + 2\n".to_string()).ok();
+    assert_eq!(
+        std::str::from_utf8(err.as_slice()).unwrap(),
+        "[line 2] Error[E0002] at end: Expect ';' after expression.\n"
+    );
+}
+
+#[test]
+fn statements_unchanged() {
+    assert_eq!(
+        run_lines(&["var a = 1;\n", "a = 2;\n", "print a;\n", "a\n"]),
+        ("2\n2\n".to_string(), "".to_string())
+    );
+}
@@ -17,7 +17,7 @@ fn after_while() {
 fn at_top_level() {
     test_error!(
         "./test/return/at_top_level.lox",
-        "[line 1] Error at 'return': Can't return from top-level code.\n"
+        "[line 1] Error[E0009] at 'return': Can't return from top-level code.\n"
     );
 }
 
@@ -0,0 +1,92 @@
+#[test]
+fn deep_recursion() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/deep_recursion.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "".to_string(),
+            "Stack overflow.\n".to_string() + &"[line 1] in f\n".repeat(10) + "... 54 more calls\n"
+        )
+    );
+}
+
+#[test]
+fn doubling() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/doubling.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "".to_string(),
+            "Concatenated string is too long.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn endless_output() {
+    let (code, out, err) = super::run_configured(
+        "./test/sandbox/endless_output.lox",
+        |vm| vm.sandbox_defaults(),
+    );
+    assert_eq!(code, 70);
+    assert!(out.len() <= 1 << 20 && out.len() > (1 << 20) - 20);
+    assert!(out.ends_with("spam spam spam spam\n"));
+    assert_eq!(err, "Output limit exceeded.\n");
+}
+
+#[test]
+fn huge_join() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/huge_join.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "".to_string(),
+            "Joined string is too long.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn huge_string() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/huge_string.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "".to_string(),
+            "Repeated string is too long.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn infinite_loop() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/infinite_loop.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "start\n".to_string(),
+            "Step budget exhausted.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn sleep() {
+    assert_eq!(
+        super::run_configured("./test/sandbox/sleep.lox", |vm| vm.sandbox_defaults()),
+        (
+            70,
+            "".to_string(),
+            "Undefined variable 'sleep'.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn well_behaved_script() {
+    //the limits leave room for ordinary scripts
+    assert_eq!(
+        super::run_configured("./test/function/recursion.lox", |vm| vm.sandbox_defaults()),
+        (0, "21\n".to_string(), "".to_string())
+    );
+}
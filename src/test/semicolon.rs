@@ -0,0 +1,43 @@
+#[test]
+fn newline() {
+    assert_eq!(
+        super::run_configured("./test/semicolon/newline.lox", |vm| vm.set_infer_semicolons(true)),
+        (0, "1\n3\n7\n30\nblock\ndone\nboth\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn newline_default() {
+    test_error!(
+        "./test/semicolon/newline.lox",
+        "[line 2] Error[E0002] at 'var': Expect ';' after variable declaration.\n[line 4] Error[E0002] at 'print': Expect ';' after variable declaration.\n[line 5] Error[E0002] at 'print': Expect ';' after value.\n[line 7] Error[E0002] at 'fun': Expect ';' after value.\n[line 9] Error[E0002] at '}': Expect ';' after return value.\n[line 15] Error[E0002] at 'var': Expect ';' after value.\n[line 16] Error[E0002] at 'print': Expect ';' after variable declaration.\n[line 18] Error[E0002] at '{': Expect ';' after value.\n[line 18] Error[E0002] at '}': Expect ';' after value.\n[line 21] Error[E0002] at end: Expect ';' after value.\n[line 21] Error[E0002] at end: Expect '}' after block.\n"
+    );
+}
+
+#[test]
+fn return_() {
+    assert_eq!(
+        super::run_configured("./test/semicolon/return.lox", |vm| vm.set_infer_semicolons(true)),
+        (0, "nil\nsame line\n".to_string(), "".to_string())
+    );
+}
+
+#[test]
+fn same_line() {
+    assert_eq!(
+        super::run_configured("./test/semicolon/same_line.lox", |vm| vm.set_infer_semicolons(true)),
+        (
+            65,
+            "".to_string(),
+            "[line 1] Error[E0002] at 'print': Expect ';' after variable declaration.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn throw() {
+    assert_eq!(
+        super::run_configured("./test/semicolon/throw.lox", |vm| vm.set_infer_semicolons(true)),
+        (0, "oops\n".to_string(), "".to_string())
+    );
+}
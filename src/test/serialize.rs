@@ -0,0 +1,178 @@
+#[test]
+fn special_floats_round_trip() {
+    use crate::chunk::{Chunk, OpCode};
+    use crate::gc::Gc;
+    use crate::object::ObjFunction;
+    use crate::value::value::Value;
+    use crate::vm::VM;
+
+    let numbers = [-0.0, 1e300, f64::NAN, f64::INFINITY];
+    let mut chunk = Chunk::new();
+    for number in numbers {
+        let constant = chunk.add_constant(Value::number(number)) as u8;
+        chunk.add_byte(OpCode::Constant as u8, 1);
+        chunk.add_byte(constant, 1);
+        chunk.add_byte(OpCode::Print as u8, 1);
+    }
+    //NaN != NaN has to survive the trip too
+    for _ in 0..2 {
+        chunk.add_byte(OpCode::Constant as u8, 2);
+        chunk.add_byte(2, 2);
+    }
+    chunk.add_byte(OpCode::Equal as u8, 2);
+    chunk.add_byte(OpCode::Print as u8, 2);
+    chunk.add_byte(OpCode::Nil as u8, 3);
+    chunk.add_byte(OpCode::Return as u8, 3);
+
+    let loaded = Chunk::from_bytes(&chunk.to_bytes().unwrap()).unwrap();
+    for (constant, number) in loaded.constants.iter().zip(numbers) {
+        assert_eq!(constant.as_number().unwrap().to_bits(), number.to_bits());
+    }
+
+    let function = ObjFunction::new(None);
+    function.borrow_mut().chunk = Gc::new(loaded);
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.run_function(function).is_ok());
+    assert_eq!(
+        std::str::from_utf8(out.as_slice()).unwrap(),
+        "-0\n1e+300\nNaN\ninf\nfalse\n"
+    );
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
+#[test]
+fn truncated_input() {
+    use crate::chunk::serialize::SerializeError;
+    use crate::chunk::Chunk;
+    assert!(Chunk::from_bytes(&[1, 0]) == Err(SerializeError::Truncated));
+}
+
+#[cfg(test)]
+fn run_serialized(bytes: &[u8]) -> (String, String) {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.interpret_bytecode(bytes).is_ok());
+    (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+}
+
+#[test]
+fn bad_magic() {
+    use crate::chunk::serialize::{deserialize_function, SerializeError};
+    use crate::global::GlobalSlots;
+    let bytes = b"LOXX\x01\x00\x00\x00";
+    let loaded = deserialize_function(bytes, &mut GlobalSlots::new());
+    assert!(loaded.err() == Some(SerializeError::BadMagic));
+}
+
+#[test]
+fn corrupt_constant_index() {
+    use crate::chunk::serialize::{deserialize_function, serialize_function, SerializeError};
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let function = compile("print 1.5;", &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    //the script's code starts right after the header, the (empty) global name table, the name
+    //tag, arity, upvalue count, local count, getter flag and code length; its first instruction
+    //is `Constant 0`
+    bytes[4 + 4 + 4 + 1 + 4 + 4 + 4 + 1 + 4 + 1] = 9;
+    assert!(deserialize_function(&bytes, &mut globals).err() == Some(SerializeError::InvalidConstant(0)));
+}
+
+#[test]
+fn corrupt_local_and_upvalue_operands() {
+    use crate::chunk::serialize::{deserialize_function, serialize_function, SerializeError};
+    use crate::chunk::verify::StackError;
+    use crate::chunk::{Chunk, OpCode};
+    use crate::gc::Gc;
+    use crate::global::GlobalSlots;
+    use crate::object::ObjFunction;
+    //only the callee is on the stack, and the script has no upvalues
+    for op in [OpCode::GetLocal, OpCode::GetUpvalue] {
+        let mut chunk = Chunk::new();
+        for byte in [op as u8, 1, OpCode::Print as u8, OpCode::Nil as u8, OpCode::Return as u8] {
+            chunk.add_byte(byte, 1);
+        }
+        let function = ObjFunction::new(None);
+        function.borrow_mut().chunk = Gc::new(chunk);
+        let mut globals = GlobalSlots::new();
+        let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+        let expected = SerializeError::InvalidCode(StackError::InvalidOperand(0));
+        assert!(deserialize_function(&bytes, &mut globals).err() == Some(expected));
+    }
+}
+
+//with integers, -0 is the integer 0
+#[cfg(not(feature = "integers"))]
+#[test]
+fn script_round_trip() {
+    use crate::chunk::serialize::serialize_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/serialize/script.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    let (out, err) = run_serialized(&bytes);
+    assert_eq!(out, "2\nhello, lox hey!\n-0\nfalse\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn version_mismatch() {
+    use crate::chunk::serialize::{
+        deserialize_function, serialize_function, SerializeError, FORMAT_VERSION,
+    };
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let function = compile("print 1.5;", &mut globals, &mut std::io::sink()).ok().unwrap();
+    let mut bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    bytes[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    let expected = SerializeError::VersionMismatch(FORMAT_VERSION + 1);
+    assert!(deserialize_function(&bytes, &mut globals).err() == Some(expected));
+}
+
+#[test]
+fn globals_reassigned_on_load() {
+    use crate::chunk::serialize::serialize_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    //these slots aren't the VM's, so `first` and `contains` end up elsewhere when it loads them
+    let mut globals = GlobalSlots::new();
+    globals.slot("unrelated");
+    let source = "var first = 1; print contains([first], 1);";
+    let function = compile(source, &mut globals, &mut std::io::sink()).ok().unwrap();
+    let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    let (out, err) = run_serialized(&bytes);
+    assert_eq!(out, "true\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn mutated_files_fail_cleanly() {
+    use crate::chunk::serialize::serialize_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    use crate::vm::VM;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/serialize/script.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    let bytes = serialize_function(&function.borrow(), &globals).unwrap();
+    //every mutation either fails to load or runs, maybe into a runtime error, without panicking
+    for index in 0..bytes.len() {
+        for mask in [0x01, 0x80, 0xff] {
+            let mut mutated = bytes.clone();
+            mutated[index] ^= mask;
+            let mut out = vec![];
+            let mut err = vec![];
+            let mut vm = VM::new(&mut out, &mut err);
+            vm.set_step_budget(Some(10_000));
+            let _ = vm.interpret_bytecode(&mutated);
+        }
+    }
+}
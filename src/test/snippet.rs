@@ -0,0 +1,42 @@
+#[test]
+fn at_end() {
+    assert_eq!(
+        super::run_configured("./test/snippet/at_end.lox", |vm| vm.set_snippets(true)).2,
+        "[line 4] Error[E0003] at end: Expect expression.\nprint a +\n         ^\n"
+    );
+}
+
+#[test]
+fn default() {
+    test_error!(
+        "./test/snippet/default.lox",
+        "[line 2] Error[E0002] at '2': Expect ';' after value.\n"
+    );
+}
+
+#[test]
+fn missing_semicolon() {
+    assert_eq!(
+        super::run_configured("./test/snippet/missing_semicolon.lox", |vm| vm.set_snippets(true)).2,
+        "[line 2] Error[E0002] at '3': Expect ';' after value.\nprint a + 2 3;\n            ^\n"
+    );
+}
+
+#[test]
+fn tab() {
+    assert_eq!(
+        super::run_configured("./test/snippet/tab.lox", |vm| vm.set_snippets(true)).2,
+        "[line 3] Error[E0002] at 'longName': Expect ';' after value.\n\tprint longName longName;\n\t               ^^^^^^^^\n"
+    );
+}
+
+#[test]
+fn unexpected_character() {
+    assert_eq!(
+        super::run_configured(
+            "./test/snippet/unexpected_character.lox",
+            |vm| vm.set_snippets(true),
+        ).2,
+        "[line 2] Error[E0004]: Unexpected character.\n"
+    );
+}
@@ -0,0 +1,59 @@
+#[test]
+fn call() {
+    test_output!("./test/static_method/call.lox", "9\n16\n");
+}
+
+#[test]
+fn getter() {
+    test_output!("./test/static_method/getter.lox", "3\n");
+}
+
+#[test]
+fn inherited() {
+    test_output!("./test/static_method/inherited.lox", "Base\n");
+}
+
+#[test]
+fn instance_method_unaffected() {
+    test_output!(
+        "./test/static_method/instance_method_unaffected.lox",
+        "method\n"
+    );
+}
+
+#[test]
+fn not_on_instance() {
+    test_error!(
+        "./test/static_method/not_on_instance.lox",
+        "Undefined property 'create'.\n"
+    );
+}
+
+#[test]
+fn super_in_static() {
+    test_error!(
+        "./test/static_method/super_in_static.lox",
+        "[line 9] Error[E0008] at 'super': Can't use 'super' in a static method.\n"
+    );
+}
+
+#[test]
+fn super_in_static_getter() {
+    test_error!(
+        "./test/static_method/super_in_static_getter.lox",
+        "[line 9] Error[E0008] at 'super': Can't use 'super' in a static method.\n"
+    );
+}
+
+#[test]
+fn this_is_class() {
+    test_output!("./test/static_method/this_is_class.lox", "0\n0\n<fn origin>\n");
+}
+
+#[test]
+fn undefined() {
+    test_error!(
+        "./test/static_method/undefined.lox",
+        "Only instances have methods.\n"
+    );
+}
@@ -0,0 +1,57 @@
+#[test]
+fn missing_return() {
+    assert_eq!(
+        super::run_configured("./test/strict/missing_return.lox", |vm| vm.set_strict(true)),
+        (
+            0,
+            "-1\n".to_string(),
+            "[line 4] Warning at '}': Function 'sign' can reach the end of its body without returning a value.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn missing_return_default() {
+    test_output!("./test/strict/missing_return.lox", "-1\n");
+}
+
+#[test]
+fn uninitialized_global() {
+    assert_eq!(
+        super::run_configured("./test/strict/uninitialized_global.lox", |vm| vm.set_strict(true)),
+        (
+            70,
+            "assigned\n".to_string(),
+            "Can't read an uninitialized variable.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn uninitialized_global_default() {
+    test_output!("./test/strict/uninitialized_global.lox", "assigned\nnil\n");
+}
+
+#[test]
+fn uninitialized_local() {
+    assert_eq!(
+        super::run_configured("./test/strict/uninitialized_local.lox", |vm| vm.set_strict(true)),
+        (
+            70,
+            "".to_string(),
+            "Can't read an uninitialized variable.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn uninitialized_upvalue() {
+    assert_eq!(
+        super::run_configured("./test/strict/uninitialized_upvalue.lox", |vm| vm.set_strict(true)),
+        (
+            70,
+            "".to_string(),
+            "Can't read an uninitialized variable.\n".to_string()
+        )
+    );
+}
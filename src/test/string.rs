@@ -1,3 +1,27 @@
+#[test]
+fn byte_len_non_string() {
+    test_error!(
+        "./test/string/byte_len_non_string.lox",
+        "Argument to 'byte_len' must be a string.\n"
+    );
+}
+
+#[test]
+fn comparison() {
+    test_output!(
+        "./test/string/comparison.lox",
+        "true\nfalse\nfalse\ntrue\nfalse\ntrue\ntrue\ntrue\nfalse\ntrue\ntrue\n"
+    );
+}
+
+#[test]
+fn comparison_mixed() {
+    test_error!(
+        "./test/string/comparison_mixed.lox",
+        "Left operand of '<' must be a number, got string.\n"
+    );
+}
+
 #[test]
 fn error_after_multiline() {
     test_error!(
@@ -6,6 +30,82 @@ fn error_after_multiline() {
     );
 }
 
+#[test]
+fn escapes() {
+    test_output!(
+        "./test/string/escapes.lox",
+        "tab\tseparated\na \"quoted\" word\nback\\slash\ntwo\nlines\ntrue\n"
+    );
+}
+
+#[test]
+fn invalid_escape() {
+    test_error!(
+        "./test/string/invalid_escape.lox",
+        "[line 1] Error[E0004]: Invalid escape sequence.\n"
+    );
+}
+
+#[test]
+fn join() {
+    test_output!("./test/string/join.lox", "a, b, c\nsolo\n[]\n1-nil-true\n");
+}
+
+#[test]
+fn join_non_array() {
+    test_error!(
+        "./test/string/join_non_array.lox",
+        "First argument to 'join' must be an array.\n"
+    );
+}
+
+#[test]
+fn join_non_string_separator() {
+    test_error!(
+        "./test/string/join_non_string_separator.lox",
+        "Separator must be a string.\n"
+    );
+}
+
+#[test]
+fn len() {
+    test_output!(
+        "./test/string/len.lox",
+        "5\n5\n5\n6\n0\ntrue\n3\n1\n"
+    );
+}
+
+#[test]
+fn len_non_string() {
+    test_error!(
+        "./test/string/len_non_string.lox",
+        "Argument to 'len' must be a string, array or map.\n"
+    );
+}
+
+#[test]
+fn literal_shared_between_functions() {
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    use crate::value::value::Value;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/string/shared_literal.lox").unwrap();
+    let script = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    //string literals are interned as they're compiled, so both functions point at one ObjString
+    let literals: Vec<Value> = script
+        .borrow()
+        .chunk
+        .borrow()
+        .constants
+        .iter()
+        .filter_map(|constant| constant.as_function().ok())
+        .flat_map(|function| function.borrow().chunk.borrow().constants.clone())
+        .filter(|constant| constant.to_string().as_deref() == Some("shared"))
+        .collect();
+    assert_eq!(literals.len(), 2);
+    assert!(literals[0] == literals[1]);
+}
+
 #[test]
 fn literals() {
     test_output!("./test/string/literals.lox", "()\na string\nA~¶Þॐஃ\n");
@@ -16,10 +116,47 @@ fn multiline() {
     test_output!("./test/string/multiline.lox", "1\n2\n3\n");
 }
 
+#[test]
+fn raw() {
+    test_output!(
+        "./test/string/raw.lox",
+        "C:\\path\\no\\escapes\nnot\\na newline\ntrue\nvariable\n"
+    );
+}
+
+#[test]
+fn repeat() {
+    test_output!("./test/string/repeat.lox", "-----\nababab\n[]\n[]\néé\n");
+}
+
+#[test]
+fn repeat_fraction() {
+    test_error!(
+        "./test/string/repeat_fraction.lox",
+        "Repeat count must be a non-negative integer.\n"
+    );
+}
+
+#[test]
+fn repeat_negative() {
+    test_error!(
+        "./test/string/repeat_negative.lox",
+        "Repeat count must be a non-negative integer.\n"
+    );
+}
+
+#[test]
+fn repeat_number_first() {
+    test_error!(
+        "./test/string/repeat_number_first.lox",
+        "Right operand of '*' must be a number, got string.\n"
+    );
+}
+
 #[test]
 fn unterminated() {
     test_error!(
         "./test/string/unterminated.lox",
-        "[line 2] Error: Unterminated String.\n"
+        "[line 2] Error[E0004]: Unterminated String.\n"
     );
 }
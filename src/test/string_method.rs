@@ -0,0 +1,92 @@
+#[test]
+fn bound() {
+    test_output!("./test/string_method/bound.lox", "<native fn>\nABC\nel\n");
+}
+
+#[test]
+fn bytes() {
+    test_output!(
+        "./test/string_method/bytes.lox",
+        "[97, 98, 99]\n[195, 169]\n[]\ntrue\n"
+    );
+}
+
+#[test]
+fn case() {
+    test_output!(
+        "./test/string_method/case.lox",
+        "HELLO, WORLD\nhello, world\nSTRASSE\nécole\n[]\n"
+    );
+}
+
+#[test]
+fn chars() {
+    test_output!(
+        "./test/string_method/chars.lox",
+        "a\nb\nc\n3\n[n, a, ï, v, e]\n5\nï\n[]\n"
+    );
+}
+
+#[test]
+fn index_of() {
+    test_output!(
+        "./test/string_method/index_of.lox",
+        "2\n3\n-1\n0\n-1\n0\n6\n"
+    );
+}
+
+#[test]
+fn index_of_non_string() {
+    test_error!(
+        "./test/string_method/index_of_non_string.lox",
+        "Argument to 'indexOf' must be a string.\n"
+    );
+}
+
+#[test]
+fn substring() {
+    test_output!(
+        "./test/string_method/substring.lox",
+        "ell\n[]\n[]\n[]\néllo\n語\n"
+    );
+}
+
+#[test]
+fn substring_negative() {
+    test_error!(
+        "./test/string_method/substring_negative.lox",
+        "Substring start and length must be non-negative integers.\n"
+    );
+}
+
+#[test]
+fn substring_out_of_range() {
+    test_error!(
+        "./test/string_method/substring_out_of_range.lox",
+        "Substring out of range.\n"
+    );
+}
+
+#[test]
+fn trim() {
+    test_output!(
+        "./test/string_method/trim.lox",
+        "[padded]\n[inner  space]\n[]\n[]\n"
+    );
+}
+
+#[test]
+fn unknown() {
+    test_error!(
+        "./test/string_method/unknown.lox",
+        "Only instances have methods.\n"
+    );
+}
+
+#[test]
+fn wrong_arity() {
+    test_error!(
+        "./test/string_method/wrong_arity.lox",
+        "Expected 0 arguments but got 1.\n"
+    );
+}
@@ -41,6 +41,19 @@ fn extra_arguments() {
     );
 }
 
+#[test]
+fn field() {
+    test_error!("./test/super/field.lox", "Undefined property 'x'.\n");
+}
+
+#[test]
+fn getter() {
+    test_output!(
+        "./test/super/getter.lox",
+        "C, B.area, A.area 2\nB.area, A.area 2\nB.area, A.area 2\n"
+    );
+}
+
 #[test]
 fn indirectly_inherited() {
     test_output!(
@@ -61,7 +74,7 @@ fn missing_arguments() {
 fn no_superclass_bind() {
     test_error!(
         "./test/super/no_superclass_bind.lox",
-        "[line 3] Error at 'super': Can't use 'super' in a class with no superclass.\n"
+        "[line 3] Error[E0008] at 'super': Can't use 'super' in a class with no superclass.\n"
     );
 }
 
@@ -69,7 +82,7 @@ fn no_superclass_bind() {
 fn no_superclass_call() {
     test_error!(
         "./test/super/no_superclass_call.lox",
-        "[line 3] Error at 'super': Can't use 'super' in a class with no superclass.\n"
+        "[line 3] Error[E0008] at 'super': Can't use 'super' in a class with no superclass.\n"
     );
 }
 
@@ -85,7 +98,7 @@ fn no_superclass_method() {
 fn parenthesized() {
     test_error!(
         "./test/super/parenthesized.lox",
-        "[line 8] Error at ')': Expect '.' after 'super'.\n"
+        "[line 8] Error[E0002] at ')': Expect '.' after 'super'.\n"
     );
 }
 
@@ -99,7 +112,7 @@ fn reassign_superclass() {
 
 #[test]
 fn super_at_top_level() {
-    test_error!("./test/super/super_at_top_level.lox", "[line 1] Error at 'super': Can't use 'super' outside of a class.\n[line 2] Error at 'super': Can't use 'super' outside of a class.\n");
+    test_error!("./test/super/super_at_top_level.lox", "[line 1] Error[E0008] at 'super': Can't use 'super' outside of a class.\n[line 2] Error[E0008] at 'super': Can't use 'super' outside of a class.\n");
 }
 
 #[test]
@@ -115,11 +128,21 @@ fn super_in_inherited_method() {
     test_output!("./test/super/super_in_inherited_method.lox", "A\n");
 }
 
+#[test]
+fn super_in_local_class() {
+    test_output!("./test/super/super_in_local_class.lox", "D(B) A\n");
+}
+
+#[test]
+fn super_in_nested_closure() {
+    test_output!("./test/super/super_in_nested_closure.lox", "Base\n");
+}
+
 #[test]
 fn super_in_top_level_function() {
     test_error!(
         "./test/super/super_in_top_level_function.lox",
-        "[line 1] Error at 'super': Can't use 'super' outside of a class.\n"
+        "[line 1] Error[E0008] at 'super': Can't use 'super' outside of a class.\n"
     );
 }
 
@@ -127,7 +150,7 @@ fn super_in_top_level_function() {
 fn super_without_dot() {
     test_error!(
         "./test/super/super_without_dot.lox",
-        "[line 6] Error at ';': Expect '.' after 'super'.\n"
+        "[line 6] Error[E0002] at ';': Expect '.' after 'super'.\n"
     );
 }
 
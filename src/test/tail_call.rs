@@ -0,0 +1,48 @@
+#[test]
+fn closes_upvalues() {
+    test_output!("./test/tail_call/closes_upvalues.lox", "2\n");
+}
+
+#[test]
+fn deep() {
+    test_output!("./test/tail_call/deep.lox", "10000\n");
+}
+
+#[test]
+fn disassembly() {
+    use crate::compiler::CompilerOptions;
+    use crate::dump_file;
+    let mut listing = vec![];
+    let mut err = vec![];
+    dump_file(
+        "./test/tail_call/deep.lox".to_string(),
+        CompilerOptions::default(),
+        &mut listing,
+        &mut err,
+    );
+    let listing = std::str::from_utf8(listing.as_slice()).unwrap();
+    assert!(listing.contains(" TailCall 2\n"));
+    //`print count(...)` isn't in tail position
+    assert!(listing.contains(" Call 2\n"));
+}
+
+#[test]
+fn mutual() {
+    test_output!("./test/tail_call/mutual.lox", "false\ntrue\n");
+}
+
+#[test]
+fn not_a_function() {
+    test_output!(
+        "./test/tail_call/not_a_function.lox",
+        "Foo instance\ntrue\n1000\n"
+    );
+}
+
+#[test]
+fn wrong_arity() {
+    test_error!(
+        "./test/tail_call/wrong_arity.lox",
+        "Expected 1 arguments but got 0.\n"
+    );
+}
@@ -20,7 +20,7 @@ fn nested_closure() {
 fn this_at_top_level() {
     test_error!(
         "./test/this/this_at_top_level.lox",
-        "[line 1] Error at 'this': Can't use 'this' outside of a class.\n"
+        "[line 1] Error[E0007] at 'this': Can't use 'this' outside of a class.\n"
     );
 }
 
@@ -33,6 +33,6 @@ fn this_in_method() {
 fn this_in_top_level_function() {
     test_error!(
         "./test/this/this_in_top_level_function.lox",
-        "[line 2] Error at 'this': Can't use 'this' outside of a class.\n"
+        "[line 2] Error[E0007] at 'this': Can't use 'this' outside of a class.\n"
     );
 }
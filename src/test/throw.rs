@@ -0,0 +1,22 @@
+#[test]
+fn caught() {
+    test_output!("./test/throw/caught.lox", "boom\n1\nis negative\n2\n");
+}
+
+#[test]
+fn missing_semicolon() {
+    test_error!(
+        "./test/throw/missing_semicolon.lox",
+        "[line 2] Error[E0002] at end: Expect ';' after thrown value.\n"
+    );
+}
+
+#[test]
+fn uncaught() {
+    test_output_and_error!("./test/throw/uncaught.lox", "before\n", "custom error\n");
+}
+
+#[test]
+fn uncaught_instance() {
+    test_error!("./test/throw/uncaught_instance.lox", "Failure: disk full\n");
+}
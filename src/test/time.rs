@@ -0,0 +1,28 @@
+#[test]
+fn sleep() {
+    test_output!("./test/time/sleep.lox", "true\ntrue\nnil\n");
+}
+
+#[test]
+fn sleep_negative() {
+    test_error!(
+        "./test/time/sleep_negative.lox",
+        "Sleep duration must be a non-negative number.\n"
+    );
+}
+
+#[test]
+fn sleep_non_number() {
+    test_error!(
+        "./test/time/sleep_non_number.lox",
+        "Sleep duration must be a non-negative number.\n"
+    );
+}
+
+#[test]
+fn time_millis_arity() {
+    test_error!(
+        "./test/time/time_millis_arity.lox",
+        "Expected 0 arguments but got 1.\n"
+    );
+}
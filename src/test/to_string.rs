@@ -0,0 +1,44 @@
+#[test]
+fn cycle() {
+    test_output!(
+        "./test/to_string/cycle.lox",
+        "[1, [...]]\n[1, [...]]\n{self: [{...}]}\n"
+    );
+}
+
+#[test]
+fn default() {
+    test_output!(
+        "./test/to_string/default.lox",
+        "Plain instance\nPlain instance\n12niltrue\n"
+    );
+}
+
+#[test]
+fn error_in_str() {
+    test_error!(
+        "./test/to_string/error_in_str.lox",
+        "'toString' must return a string, got nil.\n"
+    );
+}
+
+#[test]
+fn method() {
+    test_output!(
+        "./test/to_string/method.lox",
+        "(1, 2)\n(1, 2)!\n[(1, 2), (3, 4)]\n(1, 2) (1, 2)\n(5, 6)\n"
+    );
+}
+
+#[test]
+fn non_string() {
+    test_error!(
+        "./test/to_string/non_string.lox",
+        "'toString' must return a string, got number.\n"
+    );
+}
+
+#[test]
+fn recursion() {
+    test_output!("./test/to_string/recursion.lox", "Loop(Loop instance)\n");
+}
@@ -0,0 +1,28 @@
+#[cfg(test)]
+fn run_values(pythonic: bool) -> String {
+    use crate::run_file;
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    vm.set_pythonic_truthiness(pythonic);
+    run_file(&mut vm, "./test/truthiness/values.lox".to_string());
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn lox_truthiness() {
+    assert_eq!(
+        run_values(false),
+        "falsey\nfalsey\ntruthy\ntruthy\ntruthy\ntruthy\ntruthy\ntruthy\ntruthy\ntruthy\nfalse\n"
+    );
+}
+
+#[test]
+fn pythonic_truthiness() {
+    assert_eq!(
+        run_values(true),
+        "falsey\nfalsey\nfalsey\nfalsey\nfalsey\nfalsey\ntruthy\ntruthy\ntruthy\ntruthy\ntrue\n"
+    );
+}
@@ -0,0 +1,49 @@
+#[test]
+fn divide_by_zero() {
+    test_output!(
+        "./test/try/divide_by_zero.lox",
+        "before\nDivision by zero.\nafter\n"
+    );
+}
+
+#[test]
+fn missing_catch() {
+    test_error!(
+        "./test/try/missing_catch.lox",
+        "[line 4] Error[E0002] at 'print': Expect 'catch' after try block.\n"
+    );
+}
+
+#[test]
+fn native_callback() {
+    test_output!(
+        "./test/try/native_callback.lox",
+        "caught nope 1\n[1, -1, 0.5]\n"
+    );
+}
+
+#[test]
+fn nested_call() {
+    test_output!(
+        "./test/try/nested_call.lox",
+        "before\nOnly instances have properties.\ndone\nCan only call functions and classes.\ncaptured\nreturned\nRight operand of '-' must be a number, got nil.\ncaught Only instances have properties.\n"
+    );
+}
+
+#[test]
+fn nested_try() {
+    test_output!(
+        "./test/try/nested_try.lox",
+        "inner Division by zero.\nouter Only instances have properties.\n0\nDivision by zero.\n2\n"
+    );
+}
+
+#[test]
+fn property_error() {
+    test_output!("./test/try/property_error.lox", "Undefined property 'x'.\n");
+}
+
+#[test]
+fn uncaught() {
+    test_output_and_error!("./test/try/uncaught.lox", "try\n", "Division by zero.\n");
+}
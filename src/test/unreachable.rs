@@ -0,0 +1,34 @@
+#[test]
+fn after_return() {
+    assert_eq!(
+        super::run_configured(
+            "./test/unreachable/after_return.lox",
+            |vm| vm.set_warn_unreachable(true),
+        ),
+        (
+            0,
+            "before\n1\nthen\nblock\n".to_string(),
+            "[line 4] Warning: unreachable code.\n[line 11] Warning: unreachable code.\n[line 19] Warning: unreachable code.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn after_return_default() {
+    test_output!("./test/unreachable/after_return.lox", "before\n1\nthen\nblock\n");
+}
+
+#[test]
+fn reachable() {
+    assert_eq!(
+        super::run_configured(
+            "./test/unreachable/reachable.lox",
+            |vm| vm.set_warn_unreachable(true),
+        ),
+        (
+            0,
+            "big\nsmall\n0\nbody\n".to_string(),
+            "".to_string()
+        )
+    );
+}
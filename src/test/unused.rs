@@ -0,0 +1,16 @@
+#[test]
+fn locals() {
+    assert_eq!(
+        super::run_configured("./test/unused/locals.lox", |vm| vm.set_warn_unused(true)),
+        (
+            0,
+            "1\n2\n5\n".to_string(),
+            "[line 3] Warning: unused local variable 'unused'.\n[line 8] Warning: unused local variable 'never'.\n[line 20] Warning: unused local variable 'shadowed'.\n[line 28] Warning: unused local variable 'item'.\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn locals_default() {
+    test_output!("./test/unused/locals.lox", "1\n2\n5\n");
+}
@@ -2,7 +2,66 @@
 fn collide_with_parameter() {
     test_error!(
         "./test/variable/collide_with_parameter.lox",
-        "[line 2] Error at 'a': Already a variable with this name in this scope.\n"
+        "[line 2] Error[E0005] at 'a': Already a variable with this name in this scope.\n"
+    );
+}
+
+#[test]
+fn definition_order() {
+    use crate::vm::VM;
+    let source = std::fs::read_to_string("./test/variable/definition_order.lox").unwrap();
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.interpret(source).is_ok());
+    let globals: Vec<_> = vm
+        .defined_globals()
+        .into_iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect();
+    assert_eq!(
+        globals,
+        ["first = <fn first>", "beta = 2", "Alpha = Alpha", "zeta = z", "gamma = <fn gamma>"]
+    );
+}
+
+#[test]
+fn destructure_global() {
+    test_output!(
+        "./test/variable/destructure_global.lox",
+        "left\nright\nmade\n3\n"
+    );
+}
+
+#[test]
+fn destructure_local() {
+    test_output!(
+        "./test/variable/destructure_local.lox",
+        "[2, 1]\nouteryz\nchanged\n"
+    );
+}
+
+#[test]
+fn destructure_missing_initializer() {
+    test_error!(
+        "./test/variable/destructure_missing_initializer.lox",
+        "[line 1] Error[E0002] at ';': Expect '=' after variable names.\n"
+    );
+}
+
+#[test]
+fn destructure_own_initializer() {
+    test_error!(
+        "./test/variable/destructure_own_initializer.lox",
+        "[line 2] Error[E0006] at 'b': Can't read local variable in its own initializer.\n"
+    );
+}
+
+#[test]
+fn destructure_too_short() {
+    test_error!(
+        "./test/variable/destructure_too_short.lox",
+        "Array index out of bounds.\n"
     );
 }
 
@@ -10,7 +69,7 @@ fn collide_with_parameter() {
 fn duplicate_local() {
     test_error!(
         "./test/variable/duplicate_local.lox",
-        "[line 3] Error at 'a': Already a variable with this name in this scope.\n"
+        "[line 3] Error[E0005] at 'a': Already a variable with this name in this scope.\n"
     );
 }
 
@@ -18,7 +77,7 @@ fn duplicate_local() {
 fn duplicate_parameter() {
     test_error!(
         "./test/variable/duplicate_parameter.lox",
-        "[line 2] Error at 'arg': Already a variable with this name in this scope.\n"
+        "[line 2] Error[E0005] at 'arg': Already a variable with this name in this scope.\n"
     );
 }
 
@@ -27,6 +86,55 @@ fn early_bound() {
     test_output!("./test/variable/early_bound.lox", "outer\nouter\n");
 }
 
+#[test]
+fn globals() {
+    test_output!("./test/variable/globals.lox", "[]\n[first, second]\n2\n");
+}
+
+#[test]
+fn globals_across_scripts() {
+    use crate::vm::VM;
+    //like the REPL, where each line is a script of its own
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut vm = VM::new(&mut out, &mut err);
+    assert!(vm.interpret("var a = 1;".to_string()).is_ok());
+    assert!(vm.interpret("var b = 2;".to_string()).is_ok());
+    assert!(vm.interpret("print globals();".to_string()).is_ok());
+    assert_eq!(std::str::from_utf8(out.as_slice()).unwrap(), "[a, b]\n");
+    assert_eq!(std::str::from_utf8(err.as_slice()).unwrap(), "");
+}
+
+#[test]
+fn globals_arity() {
+    test_error!(
+        "./test/variable/globals_arity.lox",
+        "Expected 0 arguments but got 1.\n"
+    );
+}
+
+#[test]
+fn globals_belong_to_their_vm() {
+    use crate::vm::VM;
+    let mut out = vec![];
+    let mut err = vec![];
+    let mut first = VM::new(&mut out, &mut err);
+    assert!(first.interpret("var onlyInFirst = 1;".to_string()).is_ok());
+    let mut other_out = vec![];
+    let mut other_err = vec![];
+    let second = VM::new(&mut other_out, &mut other_err);
+    //a new VM starts with just the natives, whatever other VMs have compiled
+    assert_eq!(
+        second.global_slots().names().len(),
+        first.global_slots().names().len() - 1
+    );
+    assert!(!second
+        .global_slots()
+        .names()
+        .iter()
+        .any(|name| &**name == "onlyInFirst"));
+}
+
 #[test]
 fn in_middle_of_block() {
     test_output!(
@@ -108,7 +216,7 @@ fn unreached_undefined() {
 fn use_false_as_var() {
     test_error!(
         "./test/variable/use_false_as_var.lox",
-        "[line 2] Error at 'false': Expect variable name.\n"
+        "[line 2] Error[E0002] at 'false': Expect variable name.\n"
     );
 }
 
@@ -121,7 +229,7 @@ fn use_global_in_initializer() {
 fn use_local_in_initializer() {
     test_error!(
         "./test/variable/use_local_in_initializer.lox",
-        "[line 3] Error at 'a': Can't read local variable in its own initializer.\n"
+        "[line 3] Error[E0006] at 'a': Can't read local variable in its own initializer.\n"
     );
 }
 
@@ -129,7 +237,7 @@ fn use_local_in_initializer() {
 fn use_nil_as_var() {
     test_error!(
         "./test/variable/use_nil_as_var.lox",
-        "[line 2] Error at 'nil': Expect variable name.\n"
+        "[line 2] Error[E0002] at 'nil': Expect variable name.\n"
     );
 }
 
@@ -137,6 +245,6 @@ fn use_nil_as_var() {
 fn use_this_as_var() {
     test_error!(
         "./test/variable/use_this_as_var.lox",
-        "[line 2] Error at 'this': Expect variable name.\n"
+        "[line 2] Error[E0002] at 'this': Expect variable name.\n"
     );
 }
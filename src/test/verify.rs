@@ -0,0 +1,85 @@
+#[test]
+fn balanced_program() {
+    use crate::chunk::verify::verify_function;
+    use crate::compiler::compile;
+    use crate::global::GlobalSlots;
+    let mut globals = GlobalSlots::new();
+    let source = std::fs::read_to_string("./test/verify/balanced.lox").unwrap();
+    let function = compile(source.as_str(), &mut globals, &mut std::io::sink()).ok().unwrap();
+    assert_eq!(verify_function(&function.borrow()), Ok(()));
+}
+
+#[test]
+fn mismatched_branches() {
+    use crate::chunk::verify::{verify_chunk, StackError};
+    use crate::chunk::{Chunk, OpCode};
+    //if (true) leaves an extra value on the stack that the else branch doesn't
+    let mut chunk = Chunk::new();
+    for byte in [OpCode::True as u8, OpCode::JumpIfFalse as u8, 0, 5] {
+        chunk.add_byte(byte, 1);
+    }
+    for byte in [
+        OpCode::Nil as u8,
+        OpCode::Nil as u8,
+        OpCode::Jump as u8,
+        0,
+        1,
+    ] {
+        chunk.add_byte(byte, 1);
+    }
+    for byte in [OpCode::Nil as u8, OpCode::Return as u8] {
+        chunk.add_byte(byte, 1);
+    }
+    assert_eq!(verify_chunk(&chunk, 1), Err(StackError::Mismatch(10)));
+}
+
+#[test]
+fn opcode_stack_effects() {
+    use crate::chunk::OpCode;
+    assert_eq!(OpCode::Constant.stack_effect(0), 1);
+    assert_eq!(OpCode::Pop.stack_effect(0), -1);
+    assert_eq!(OpCode::Add.stack_effect(0), -1);
+    assert_eq!(OpCode::Not.stack_effect(0), 0);
+    assert_eq!(OpCode::SetIndex.stack_effect(0), -2);
+    assert_eq!(OpCode::Closure.stack_effect(3), 1);
+    //the callee and three arguments become one result
+    assert_eq!(OpCode::Call.stack_effect(3), -3);
+    assert_eq!(OpCode::Call.stack_effect(0), 0);
+    assert_eq!(OpCode::Invoke.stack_effect(2), -2);
+    assert_eq!(OpCode::Array.stack_effect(4), -3);
+    assert_eq!(OpCode::Array.stack_effect(0), 1);
+    assert_eq!(OpCode::PopN.stack_effect(3), -3);
+    assert_eq!(OpCode::Dup.stack_effect(0), 1);
+    assert_eq!(OpCode::Swap.stack_effect(0), 0);
+    assert_eq!(OpCode::Over.stack_effect(0), 1);
+    assert_eq!(OpCode::PopJumpIfFalse.stack_effect(0), -1);
+    assert_eq!(OpCode::PopJumpIfTrue.operand_bytes(), 2);
+    assert_eq!(OpCode::PushHandler.stack_effect(0), 0);
+    assert_eq!(OpCode::PushHandler.operand_bytes(), 2);
+    assert_eq!(OpCode::PopHandler.stack_effect(0), 0);
+    assert_eq!(OpCode::Throw.stack_effect(0), -1);
+    assert_eq!(OpCode::Iterable.stack_effect(0), 0);
+    assert_eq!(OpCode::JumpIfNotNil.stack_effect(0), 0);
+    assert_eq!(OpCode::JumpIfNotNil.operand_bytes(), 2);
+    assert_eq!(OpCode::GreaterEqual.stack_effect(0), -1);
+    assert_eq!(OpCode::LessEqual.stack_effect(0), -1);
+    assert_eq!(OpCode::Jump.operand_bytes(), 2);
+    assert_eq!(OpCode::Closure.operand_bytes(), 1);
+    assert_eq!(OpCode::Return.operand_bytes(), 0);
+}
+
+#[test]
+fn underflow() {
+    use crate::chunk::verify::{verify_chunk, StackError};
+    use crate::chunk::{Chunk, OpCode};
+    let mut chunk = Chunk::new();
+    for byte in [
+        OpCode::Nil as u8,
+        OpCode::Pop as u8,
+        OpCode::Pop as u8,
+        OpCode::Pop as u8,
+    ] {
+        chunk.add_byte(byte, 1);
+    }
+    assert_eq!(verify_chunk(&chunk, 1), Err(StackError::Underflow(3)));
+}
@@ -2,7 +2,7 @@
 fn class_in_body() {
     test_error!(
         "./test/while/class_in_body.lox",
-        "[line 2] Error at 'class': Expect expression.\n"
+        "[line 2] Error[E0003] at 'class': Expect expression.\n"
     );
 }
 
@@ -15,7 +15,7 @@ fn closure_in_body() {
 fn fun_in_body() {
     test_error!(
         "./test/while/fun_in_body.lox",
-        "[line 2] Error at 'fun': Expect expression.\n"
+        "[line 2] Error[E0003] at 'fun': Expect expression.\n"
     );
 }
 
@@ -34,10 +34,15 @@ fn syntax() {
     test_output!("./test/while/syntax.lox", "1\n2\n3\n0\n1\n2\n");
 }
 
+#[test]
+fn truthy_instance() {
+    test_output!("./test/while/truthy_instance.lox", "2\n1\n0\n");
+}
+
 #[test]
 fn var_in_body() {
     test_error!(
         "./test/while/var_in_body.lox",
-        "[line 2] Error at 'var': Expect expression.\n"
+        "[line 2] Error[E0003] at 'var': Expect expression.\n"
     );
 }
@@ -13,20 +13,90 @@ pub enum ValueType {
     Instance,
     BoundMethod,
     Native,
+    Array,
+    Map,
 }
 
-#[cfg(not(nan_boxing))]
+impl ValueType {
+    /// What error messages call a value of this type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueType::Nil => "nil",
+            ValueType::Bool => "boolean",
+            ValueType::Number => "number",
+            ValueType::String => "string",
+            ValueType::Upvalue => "upvalue",
+            ValueType::Function | ValueType::Closure => "function",
+            ValueType::Class => "class",
+            ValueType::Instance => "instance",
+            ValueType::BoundMethod => "method",
+            ValueType::Native => "native function",
+            ValueType::Array => "array",
+            ValueType::Map => "map",
+        }
+    }
+}
+
+/// Formats a number the way clox's `printf("%g")` does: six significant digits, no trailing
+/// zeros, and an exponent once the magnitude is below 1e-4 or at least 1e6.
+pub fn format_number(number: f64) -> String {
+    if number == 0.0 || !number.is_finite() {
+        return format!("{}", number);
+    }
+    //rounding to six digits first can carry into the next power of ten, e.g. 999999.5
+    let scientific = format!("{:.5e}", number);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if !(-4..6).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_zeros(mantissa), sign, exponent.abs())
+    } else {
+        trim_zeros(&format!("{:.*}", (5 - exponent) as usize, number)).to_string()
+    }
+}
+
+fn trim_zeros(digits: &str) -> &str {
+    if digits.contains('.') {
+        digits.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        digits
+    }
+}
+
+/// `op` on two integers, if the answer is an integer too. Overflow and division that doesn't
+/// come out even give `None`, and the caller does the arithmetic with floats instead.
+#[cfg(feature = "integers")]
+pub fn integer_arithmetic(op: crate::chunk::OpCode, a: i64, b: i64) -> Option<value::Value> {
+    use crate::chunk::OpCode;
+    use value::Value;
+    match op {
+        OpCode::Add => a.checked_add(b).map(Value::int),
+        OpCode::Subtract => a.checked_sub(b).map(Value::int),
+        OpCode::Multiply => a.checked_mul(b).map(Value::int),
+        OpCode::Divide if b != 0 && a.checked_rem(b) == Some(0) => Some(Value::int(a / b)),
+        OpCode::Greater => Some(Value::bool_(a > b)),
+        OpCode::Less => Some(Value::bool_(a < b)),
+        OpCode::GreaterEqual => Some(Value::bool_(a >= b)),
+        OpCode::LessEqual => Some(Value::bool_(a <= b)),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "nan-boxing"))]
 pub mod value {
     use super::CastError;
     use super::ValueType;
     use crate::gc::{Gc, Trace};
     use crate::object::*;
     use std::fmt::{Display, Formatter};
-    #[derive(Clone, PartialEq)]
+    #[derive(Clone)]
     pub enum Value {
         Nil,
         Bool(bool),
         Number(f64),
+        //a whole number kept exact; arithmetic with a `Number` gives a `Number`
+        #[cfg(feature = "integers")]
+        Int(i64),
         String(Gc<ObjString>),
         _Upvalue(Gc<ObjUpvalue>),
         Function(Gc<ObjFunction>),
@@ -35,6 +105,41 @@ pub mod value {
         Instance(Gc<ObjInstance>),
         BoundMethod(Gc<ObjBoundMethod>),
         Native(Gc<ObjNative>),
+        Array(Gc<ObjArray>),
+        Map(Gc<ObjMap>),
+        //what `var a;` leaves in a variable under --strict; reading it is a runtime error
+        Uninitialized,
+    }
+
+    //must agree with the nan-boxed `eq` below: values of different types are never equal,
+    //numbers compare as floats so NaN isn't equal to itself (two integers compare exactly),
+    //strings are interned so comparing
+    //the objects compares their text, and every other object is only equal to itself
+    impl PartialEq for Value {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Self::Nil, Self::Nil) | (Self::Uninitialized, Self::Uninitialized) => true,
+                (Self::Bool(a), Self::Bool(b)) => a == b,
+                (Self::Number(a), Self::Number(b)) => a == b,
+                #[cfg(feature = "integers")]
+                (Self::Int(a), Self::Int(b)) => a == b,
+                #[cfg(feature = "integers")]
+                (Self::Int(a), Self::Number(b)) | (Self::Number(b), Self::Int(a)) => {
+                    *a as f64 == *b
+                }
+                (Self::String(a), Self::String(b)) => Gc::ptr_eq(a, b),
+                (Self::_Upvalue(a), Self::_Upvalue(b)) => Gc::ptr_eq(a, b),
+                (Self::Function(a), Self::Function(b)) => Gc::ptr_eq(a, b),
+                (Self::Closure(a), Self::Closure(b)) => Gc::ptr_eq(a, b),
+                (Self::Class(a), Self::Class(b)) => Gc::ptr_eq(a, b),
+                (Self::Instance(a), Self::Instance(b)) => Gc::ptr_eq(a, b),
+                (Self::BoundMethod(a), Self::BoundMethod(b)) => Gc::ptr_eq(a, b),
+                (Self::Native(a), Self::Native(b)) => Gc::ptr_eq(a, b),
+                (Self::Array(a), Self::Array(b)) => Gc::ptr_eq(a, b),
+                (Self::Map(a), Self::Map(b)) => Gc::ptr_eq(a, b),
+                _ => false,
+            }
+        }
     }
 
     impl Display for Value {
@@ -42,7 +147,9 @@ pub mod value {
             match self {
                 Self::Nil => write!(f, "nil"),
                 Self::Bool(b) => write!(f, "{}", b),
-                Self::Number(num) => write!(f, "{}", num),
+                Self::Number(num) => f.write_str(&super::format_number(*num)),
+                #[cfg(feature = "integers")]
+                Self::Int(integer) => write!(f, "{}", integer),
                 Self::String(string) => string.borrow().fmt(f),
                 Self::_Upvalue(upvalue) => upvalue.borrow().fmt(f),
                 Self::Function(function) => function.borrow().fmt(f),
@@ -51,6 +158,9 @@ pub mod value {
                 Self::Instance(instance) => instance.borrow().fmt(f),
                 Self::BoundMethod(bound_method) => bound_method.borrow().fmt(f),
                 Self::Native(native) => native.borrow().fmt(f),
+                Self::Array(array) => array.borrow().fmt(f),
+                Self::Map(map) => map.borrow().fmt(f),
+                Self::Uninitialized => write!(f, "uninitialized"),
             }
         }
     }
@@ -65,9 +175,11 @@ pub mod value {
 
         pub fn value_type(&self) -> ValueType {
             match self {
-                Value::Nil => ValueType::Nil,
+                Value::Nil | Value::Uninitialized => ValueType::Nil,
                 Value::Bool(_) => ValueType::Bool,
                 Value::Number(_) => ValueType::Number,
+                #[cfg(feature = "integers")]
+                Value::Int(_) => ValueType::Number,
                 Value::String(_) => ValueType::String,
                 Value::_Upvalue(_) => ValueType::Upvalue,
                 Value::Function(_) => ValueType::Function,
@@ -76,6 +188,8 @@ pub mod value {
                 Value::Instance(_) => ValueType::Instance,
                 Value::BoundMethod(_) => ValueType::BoundMethod,
                 Value::Native(_) => ValueType::Native,
+                Value::Array(_) => ValueType::Array,
+                Value::Map(_) => ValueType::Map,
             }
         }
 
@@ -91,6 +205,18 @@ pub mod value {
             Value::Number(number)
         }
 
+        /// An integer if they're enabled, otherwise the same number as a float.
+        pub fn int(integer: i64) -> Value {
+            #[cfg(feature = "integers")]
+            return Value::Int(integer);
+            #[cfg(not(feature = "integers"))]
+            Value::Number(integer as f64)
+        }
+
+        pub fn uninitialized() -> Value {
+            Value::Uninitialized
+        }
+
         pub fn string(string: Gc<ObjString>) -> Value {
             Value::String(string)
         }
@@ -123,9 +249,19 @@ pub mod value {
             Value::Native(native)
         }
 
+        pub fn array(array: Gc<ObjArray>) -> Value {
+            Value::Array(array)
+        }
+
+        pub fn map(map: Gc<ObjMap>) -> Value {
+            Value::Map(map)
+        }
+
         pub fn is_number(&self) -> bool {
             match self {
                 Value::Number(_) => true,
+                #[cfg(feature = "integers")]
+                Value::Int(_) => true,
                 _ => false,
             }
         }
@@ -137,6 +273,26 @@ pub mod value {
             }
         }
 
+        pub fn is_array(&self) -> bool {
+            matches!(self, Value::Array(_))
+        }
+
+        pub fn is_map(&self) -> bool {
+            matches!(self, Value::Map(_))
+        }
+
+        pub fn is_bool(&self) -> bool {
+            matches!(self, Value::Bool(_))
+        }
+
+        pub fn is_nil(&self) -> bool {
+            matches!(self, Value::Nil)
+        }
+
+        pub fn is_uninitialized(&self) -> bool {
+            matches!(self, Value::Uninitialized)
+        }
+
         pub fn is_falsey(&self) -> bool {
             match self {
                 Value::Nil => true,
@@ -145,9 +301,40 @@ pub mod value {
             }
         }
 
+        /// Falsiness with Python's rules: zero, the empty string and empty collections are
+        /// falsey too.
+        pub fn is_pythonic_falsey(&self) -> bool {
+            match self {
+                Value::Number(number) => *number == 0.0,
+                #[cfg(feature = "integers")]
+                Value::Int(integer) => *integer == 0,
+                Value::String(string) => string.borrow().as_str().is_empty(),
+                Value::Array(array) => array.borrow().elements.is_empty(),
+                Value::Map(map) => map.borrow().entries.len() == 0,
+                _ => self.is_falsey(),
+            }
+        }
+
+        pub fn as_bool(&self) -> Result<bool, CastError> {
+            match self {
+                Self::Bool(boolean) => Ok(*boolean),
+                _ => Err(CastError),
+            }
+        }
+
         pub fn as_number(&self) -> Result<f64, CastError> {
             match self {
                 Self::Number(value) => Ok(*value),
+                #[cfg(feature = "integers")]
+                Self::Int(integer) => Ok(*integer as f64),
+                _ => Err(CastError),
+            }
+        }
+
+        #[cfg(feature = "integers")]
+        pub fn as_int(&self) -> Result<i64, CastError> {
+            match self {
+                Self::Int(integer) => Ok(*integer),
                 _ => Err(CastError),
             }
         }
@@ -207,6 +394,20 @@ pub mod value {
                 _ => Err(CastError),
             }
         }
+
+        pub fn as_array(&self) -> Result<Gc<ObjArray>, CastError> {
+            match self {
+                Self::Array(array) => Ok(array.clone()),
+                _ => Err(CastError),
+            }
+        }
+
+        pub fn as_map(&self) -> Result<Gc<ObjMap>, CastError> {
+            match self {
+                Self::Map(map) => Ok(map.clone()),
+                _ => Err(CastError),
+            }
+        }
     }
 
     unsafe impl Trace for Value {
@@ -220,6 +421,8 @@ pub mod value {
                 Value::Instance(instance) => instance.trace(),
                 Value::BoundMethod(bound_method) => bound_method.trace(),
                 Value::Native(native) => native.trace(),
+                Value::Array(array) => array.trace(),
+                Value::Map(map) => map.trace(),
                 _ => (),
             }
         }
@@ -234,6 +437,8 @@ pub mod value {
                 Value::Instance(instance) => instance.root(),
                 Value::BoundMethod(bound_method) => bound_method.root(),
                 Value::Native(native) => native.root(),
+                Value::Array(array) => array.root(),
+                Value::Map(map) => map.root(),
                 _ => (),
             }
         }
@@ -248,6 +453,8 @@ pub mod value {
                 Value::Instance(instance) => instance.unroot(),
                 Value::BoundMethod(bound_method) => bound_method.unroot(),
                 Value::Native(native) => native.unroot(),
+                Value::Array(array) => array.unroot(),
+                Value::Map(map) => map.unroot(),
                 _ => (),
             }
         }
@@ -268,19 +475,19 @@ pub mod value {
     }
 }
 
-#[cfg(nan_boxing)]
+#[cfg(feature = "nan-boxing")]
 pub mod value {
     use super::CastError;
     use super::ValueType;
     use crate::gc::{Gc, Trace};
     use crate::object::*;
-    use crate::vm::InterpretError;
     use std::fmt::{Display, Formatter};
     use std::mem::ManuallyDrop;
 
     pub const SIGN_BIT: u64 = 0x8000000000000000;
     pub const QNAN: u64 = 0x7FF8000000000000;
     pub const REAL_INDEFINITE: u64 = SIGN_BIT | QNAN;
+    //an object's type is bits 48 to 50 and bit 1, which a `GcBox`'s address always leaves clear
     pub const STRING: u64 = 0 << 48;
     pub const UPVALUE: u64 = 1 << 48;
     pub const FUNCTION: u64 = 2 << 48;
@@ -289,12 +496,23 @@ pub mod value {
     pub const INSTANCE: u64 = 5 << 48;
     pub const BOUND_METHOD: u64 = 6 << 48;
     pub const NATIVE_FN: u64 = 7 << 48;
+    pub const ARRAY: u64 = 0x2;
+    pub const MAP: u64 = 1 << 48 | 0x2;
+    const OBJECT_TYPE: u64 = 7 << 48 | 0x2;
     pub const TAG_NIL: u64 = 0x1;
     pub const TAG_TRUE: u64 = 0x2;
     pub const TAG_FALSE: u64 = 0x3;
+    pub const TAG_UNINITIALIZED: u64 = 0x4;
     pub const NIL: u64 = QNAN | TAG_NIL;
     pub const TRUE: u64 = QNAN | TAG_TRUE;
     pub const FALSE: u64 = QNAN | TAG_FALSE;
+    pub const UNINITIALIZED: u64 = QNAN | TAG_UNINITIALIZED;
+    //a positive quiet NaN with the lowest type bit set holds a 48-bit integer in two's
+    //complement; nil and the booleans leave those bits clear
+    #[cfg(feature = "integers")]
+    pub const INT: u64 = QNAN | (4 << 48);
+    #[cfg(feature = "integers")]
+    const PAYLOAD: u64 = 0xFFFF_FFFF_FFFF;
 
     #[repr(C)]
     pub union Value {
@@ -308,37 +526,42 @@ pub mod value {
         instance: ManuallyDrop<Gc<ObjInstance>>,
         bound_method: ManuallyDrop<Gc<ObjBoundMethod>>,
         native: ManuallyDrop<Gc<ObjNative>>,
+        array: ManuallyDrop<Gc<ObjArray>>,
+        map: ManuallyDrop<Gc<ObjMap>>,
     }
 
     impl Value {
         pub fn to_string(&self) -> Option<String> {
             if let Ok(string) = self.as_string() {
-                Some(string.borrow().to_string())
+                Some(string.borrow().as_str().to_string())
             } else {
                 None
             }
         }
 
+        //one match on the top 16 bits: a set sign bit over a quiet NaN is an object, with its
+        //type in the three bits below and bit 1, and a positive quiet NaN is nil, a boolean or
+        //NaN itself. integers have the next bit down set, so they fall through with the floats
         pub fn value_type(&self) -> ValueType {
-            if self.is_nil() {
-                ValueType::Nil
-            } else if self.is_bool() {
-                ValueType::Bool
-            } else if self.is_object() {
-                let object_tag = (unsafe { self.bits } & NATIVE_FN);
-                match object_tag {
-                    STRING => ValueType::String,
-                    UPVALUE => ValueType::Upvalue,
-                    FUNCTION => ValueType::Function,
-                    CLOSURE => ValueType::Closure,
-                    CLASS => ValueType::Class,
-                    INSTANCE => ValueType::Instance,
-                    BOUND_METHOD => ValueType::BoundMethod,
-                    NATIVE_FN => ValueType::Native,
-                    _ => unreachable!(),
-                }
-            } else {
-                ValueType::Number
+            let bits = unsafe { self.bits };
+            match bits >> 48 {
+                0xFFF8 if bits == REAL_INDEFINITE => ValueType::Number,
+                0xFFF8 if bits & 0x2 != 0 => ValueType::Array,
+                0xFFF8 => ValueType::String,
+                0xFFF9 if bits & 0x2 != 0 => ValueType::Map,
+                0xFFF9 => ValueType::Upvalue,
+                0xFFFA => ValueType::Function,
+                0xFFFB => ValueType::Closure,
+                0xFFFC => ValueType::Class,
+                0xFFFD => ValueType::Instance,
+                0xFFFE => ValueType::BoundMethod,
+                0xFFFF => ValueType::Native,
+                0x7FF8 => match bits {
+                    NIL | UNINITIALIZED => ValueType::Nil,
+                    TRUE | FALSE => ValueType::Bool,
+                    _ => ValueType::Number,
+                },
+                _ => ValueType::Number,
             }
         }
 
@@ -355,7 +578,36 @@ pub mod value {
         }
 
         pub fn number(number: f64) -> Value {
-            Value { number }
+            //0/0 gives a negative quiet NaN on x86, whose top bits would read as a string tag,
+            //so every NaN is stored as the positive one
+            if number.is_nan() {
+                Value { bits: QNAN }
+            } else {
+                Value { number }
+            }
+        }
+
+        //integers too big for the payload are stored as floats instead
+        #[cfg(feature = "integers")]
+        pub fn int(integer: i64) -> Value {
+            if (-(1 << 47)..1 << 47).contains(&integer) {
+                Value {
+                    bits: INT | (integer as u64 & PAYLOAD),
+                }
+            } else {
+                Value::number(integer as f64)
+            }
+        }
+
+        #[cfg(not(feature = "integers"))]
+        pub fn int(integer: i64) -> Value {
+            Value::number(integer as f64)
+        }
+
+        pub fn uninitialized() -> Value {
+            Value {
+                bits: UNINITIALIZED,
+            }
         }
 
         pub fn string(string: Gc<ObjString>) -> Value {
@@ -366,7 +618,7 @@ pub mod value {
             result
         }
 
-        pub fn upvalue(upvalue: Gc<ObjUpvalue>) -> Value {
+        pub fn _upvalue(upvalue: Gc<ObjUpvalue>) -> Value {
             let mut result = Value {
                 upvalue: ManuallyDrop::new(upvalue),
             };
@@ -422,187 +674,200 @@ pub mod value {
             result
         }
 
+        pub fn array(array: Gc<ObjArray>) -> Value {
+            let mut result = Value {
+                array: ManuallyDrop::new(array),
+            };
+            unsafe { result.bits |= QNAN | SIGN_BIT | ARRAY };
+            result
+        }
+
+        pub fn map(map: Gc<ObjMap>) -> Value {
+            let mut result = Value {
+                map: ManuallyDrop::new(map),
+            };
+            unsafe { result.bits |= QNAN | SIGN_BIT | MAP };
+            result
+        }
+
         pub fn is_object(&self) -> bool {
             unsafe {
                 self.bits != REAL_INDEFINITE && self.bits & REAL_INDEFINITE == REAL_INDEFINITE
             }
         }
 
+        fn is_object_type(&self, object_type: u64) -> bool {
+            self.is_object() && unsafe { self.bits } & OBJECT_TYPE == object_type
+        }
+
         pub fn is_number(&self) -> bool {
-            (unsafe { self.bits } & QNAN) != QNAN
+            #[cfg(feature = "integers")]
+            if self.as_int().is_ok() {
+                return true;
+            }
+            //NaN itself is the one quiet NaN that's a number
+            let bits = unsafe { self.bits };
+            bits & QNAN != QNAN || bits == QNAN
         }
 
         pub fn is_string(&self) -> bool {
-            match self.as_string() {
-                Ok(_) => true,
-                Err(_) => false,
-            }
+            self.is_object_type(STRING)
+        }
+
+        pub fn is_array(&self) -> bool {
+            self.is_object_type(ARRAY)
+        }
+
+        pub fn is_map(&self) -> bool {
+            self.is_object_type(MAP)
         }
 
         pub fn is_falsey(&self) -> bool {
-            unsafe {
-                if self.bits == FALSE || self.bits == NIL {
-                    true
-                } else {
-                    false
-                }
+            unsafe { self.bits == FALSE || self.bits == NIL }
+        }
+
+        /// Falsiness with Python's rules: zero, the empty string and empty collections are
+        /// falsey too.
+        pub fn is_pythonic_falsey(&self) -> bool {
+            match self.value_type() {
+                ValueType::Number => self.as_number().unwrap() == 0.0,
+                ValueType::String => self.as_string().unwrap().borrow().as_str().is_empty(),
+                ValueType::Array => self.as_array().unwrap().borrow().elements.is_empty(),
+                ValueType::Map => self.as_map().unwrap().borrow().entries.len() == 0,
+                _ => self.is_falsey(),
             }
         }
 
         pub fn is_bool(&self) -> bool {
-            unsafe { return self.bits == TRUE || self.bits == FALSE }
+            unsafe { self.bits == TRUE || self.bits == FALSE }
         }
 
         pub fn is_nil(&self) -> bool {
-            unsafe { return self.bits == NIL }
+            unsafe { self.bits == NIL }
         }
 
-        pub fn as_bool(&self) -> Result<bool, InterpretError> {
-            unsafe {
-                if self.bits == TRUE {
-                    return Ok(true);
-                } else if self.bits == FALSE {
-                    return Ok(false);
-                }
-            }
+        pub fn is_uninitialized(&self) -> bool {
+            unsafe { self.bits == UNINITIALIZED }
+        }
 
-            Err(InterpretError::Runtime)
+        pub fn as_bool(&self) -> Result<bool, CastError> {
+            match unsafe { self.bits } {
+                TRUE => Ok(true),
+                FALSE => Ok(false),
+                _ => Err(CastError),
+            }
         }
 
-        pub fn as_number(&self) -> Result<f64, InterpretError> {
-            if unsafe { self.bits } & QNAN == QNAN {
-                return Err(InterpretError::Runtime);
+        pub fn as_number(&self) -> Result<f64, CastError> {
+            #[cfg(feature = "integers")]
+            if let Ok(integer) = self.as_int() {
+                return Ok(integer as f64);
+            }
+            if self.is_number() {
+                Ok(unsafe { self.number })
+            } else {
+                Err(CastError)
             }
+        }
 
-            Ok(unsafe { self.number })
+        #[cfg(feature = "integers")]
+        pub fn as_int(&self) -> Result<i64, CastError> {
+            let bits = unsafe { self.bits };
+            if bits & !PAYLOAD != INT {
+                return Err(CastError);
+            }
+            //shifting back down copies bit 47 into the top bits, restoring the sign
+            Ok(((bits << 16) as i64) >> 16)
         }
 
+        //`Gc` ignores the tag bits in its pointer, and a clone of it comes out without them
         pub fn as_string(&self) -> Result<Gc<ObjString>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != STRING {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.string).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(STRING) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.string).clone() })
         }
 
-        pub fn as_upvalue(&self) -> Result<Gc<ObjUpvalue>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != UPVALUE {
-                return Err(CastError::IncorrectObjectType);
+        pub fn _as_upvalue(&self) -> Result<Gc<ObjUpvalue>, CastError> {
+            if !self.is_object_type(UPVALUE) {
+                return Err(CastError);
             }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.upvalue).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            Ok(unsafe { (*self.upvalue).clone() })
         }
 
         pub fn as_function(&self) -> Result<Gc<ObjFunction>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != FUNCTION {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.function).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(FUNCTION) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.function).clone() })
         }
 
         pub fn as_closure(&self) -> Result<Gc<ObjClosure>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != CLOSURE {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.closure).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(CLOSURE) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.closure).clone() })
         }
 
         pub fn as_class(&self) -> Result<Gc<ObjClass>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != CLASS {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.class).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(CLASS) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.class).clone() })
         }
 
         pub fn as_instance(&self) -> Result<Gc<ObjInstance>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != INSTANCE {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.instance).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(INSTANCE) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.instance).clone() })
         }
 
         pub fn as_bound_method(&self) -> Result<Gc<ObjBoundMethod>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != BOUND_METHOD {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.bound_method).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(BOUND_METHOD) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.bound_method).clone() })
         }
 
         pub fn as_native(&self) -> Result<Gc<ObjNative>, CastError> {
-            if !self.is_object() {
-                return Err(CastError::NotAnObject);
-            } else if unsafe { self.bits } & NATIVE_FN != NATIVE_FN {
-                return Err(CastError::IncorrectObjectType);
-            }
-            //bit weird, but temp will not cause a drop of self, and dereferencing then cloning
-            //ManuallyDrop<Gc<T>> makes a normal Gc<T>
-            let temp = Value {
-                bits: unsafe { self.bits } & !(QNAN | SIGN_BIT | NATIVE_FN),
-            };
-            let result = unsafe { (*temp.native).clone() };
-            std::mem::forget(temp);
-            return Ok(result);
+            if !self.is_object_type(NATIVE_FN) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.native).clone() })
+        }
+
+        pub fn as_array(&self) -> Result<Gc<ObjArray>, CastError> {
+            if !self.is_object_type(ARRAY) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.array).clone() })
+        }
+
+        pub fn as_map(&self) -> Result<Gc<ObjMap>, CastError> {
+            if !self.is_object_type(MAP) {
+                return Err(CastError);
+            }
+            Ok(unsafe { (*self.map).clone() })
+        }
+
+        //the handle this value holds, tag bits and all, for tracing it in place
+        fn object(&self) -> Option<&dyn Trace> {
+            unsafe {
+                match self.value_type() {
+                    ValueType::String => Some(&*self.string),
+                    ValueType::Upvalue => Some(&*self.upvalue),
+                    ValueType::Function => Some(&*self.function),
+                    ValueType::Closure => Some(&*self.closure),
+                    ValueType::Class => Some(&*self.class),
+                    ValueType::Instance => Some(&*self.instance),
+                    ValueType::BoundMethod => Some(&*self.bound_method),
+                    ValueType::Native => Some(&*self.native),
+                    ValueType::Array => Some(&*self.array),
+                    ValueType::Map => Some(&*self.map),
+                    _ => None,
+                }
+            }
         }
     }
 
@@ -610,13 +875,15 @@ pub mod value {
         fn clone(&self) -> Self {
             match self.value_type() {
                 ValueType::String => Value::string(self.as_string().unwrap()),
-                ValueType::Upvalue => Value::upvalue(self.as_upvalue().unwrap()),
+                ValueType::Upvalue => Value::_upvalue(self._as_upvalue().unwrap()),
                 ValueType::Function => Value::function(self.as_function().unwrap()),
                 ValueType::Closure => Value::closure(self.as_closure().unwrap()),
                 ValueType::Class => Value::class(self.as_class().unwrap()),
                 ValueType::Instance => Value::instance(self.as_instance().unwrap()),
                 ValueType::BoundMethod => Value::bound_method(self.as_bound_method().unwrap()),
                 ValueType::Native => Value::native(self.as_native().unwrap()),
+                ValueType::Array => Value::array(self.as_array().unwrap()),
+                ValueType::Map => Value::map(self.as_map().unwrap()),
                 _ => Value {
                     bits: unsafe { self.bits },
                 },
@@ -624,20 +891,20 @@ pub mod value {
         }
     }
 
+    //must agree with the enum's `eq` above
     impl PartialEq for Value {
         fn eq(&self, other: &Self) -> bool {
             match (self.value_type(), other.value_type()) {
-                (ValueType::Nil, ValueType::Nil) => true,
-                (ValueType::Bool, ValueType::Bool) => {
-                    self.as_bool().unwrap() == other.as_bool().unwrap()
-                }
+                //nil and uninitialized are each only equal to themselves
+                (ValueType::Nil, ValueType::Nil) | (ValueType::Bool, ValueType::Bool) => unsafe {
+                    self.bits == other.bits
+                },
+                //integers fit in 48 bits, so they compare exactly as floats
                 (ValueType::Number, ValueType::Number) => {
                     self.as_number().unwrap() == other.as_number().unwrap()
                 }
-                (ValueType::String, ValueType::String) => {
-                    self.as_string().unwrap() == other.as_string().unwrap()
-                }
-                _ => false,
+                //the same object under the same tag, whichever handle is a root
+                _ => self.is_object() && unsafe { self.bits & !1 == other.bits & !1 },
             }
         }
     }
@@ -645,101 +912,63 @@ pub mod value {
     impl Display for Value {
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
             match self.value_type() {
+                ValueType::Nil if self.is_uninitialized() => write!(f, "uninitialized"),
                 ValueType::Nil => f.write_str("nil"),
                 ValueType::Bool => self.as_bool().unwrap().fmt(f),
-                ValueType::Number => self.as_number().unwrap().fmt(f),
-                ValueType::String => self.as_string().unwrap().fmt(f),
-                ValueType::Upvalue => self.as_upvalue().unwrap().fmt(f),
-                ValueType::Function => self.as_function().unwrap().fmt(f),
-                ValueType::Closure => self.as_closure().unwrap().fmt(f),
-                ValueType::Class => self.as_class().unwrap().fmt(f),
-                ValueType::Instance => self.as_instance().unwrap().fmt(f),
-                ValueType::BoundMethod => self.as_bound_method().unwrap().fmt(f),
-                ValueType::Native => self.as_native().unwrap().fmt(f),
+                #[cfg(feature = "integers")]
+                ValueType::Number if self.as_int().is_ok() => self.as_int().unwrap().fmt(f),
+                ValueType::Number => f.write_str(&super::format_number(self.as_number().unwrap())),
+                ValueType::String => self.as_string().unwrap().borrow().fmt(f),
+                ValueType::Upvalue => self._as_upvalue().unwrap().borrow().fmt(f),
+                ValueType::Function => self.as_function().unwrap().borrow().fmt(f),
+                ValueType::Closure => self.as_closure().unwrap().borrow().fmt(f),
+                ValueType::Class => self.as_class().unwrap().borrow().fmt(f),
+                ValueType::Instance => self.as_instance().unwrap().borrow().fmt(f),
+                ValueType::BoundMethod => self.as_bound_method().unwrap().borrow().fmt(f),
+                ValueType::Native => self.as_native().unwrap().borrow().fmt(f),
+                ValueType::Array => self.as_array().unwrap().borrow().fmt(f),
+                ValueType::Map => self.as_map().unwrap().borrow().fmt(f),
             }
         }
     }
 
+    //a clone would be a second root, so these work on the handle inside the value
     unsafe impl Trace for Value {
         fn trace(&self) {
-            match self.value_type() {
-                ValueType::String => self.as_string().unwrap().trace(),
-                ValueType::Upvalue => self.as_upvalue().unwrap().trace(),
-                ValueType::Function => self.as_function().unwrap().trace(),
-                ValueType::Closure => self.as_closure().unwrap().trace(),
-                ValueType::Class => self.as_class().unwrap().trace(),
-                ValueType::Instance => self.as_instance().unwrap().trace(),
-                ValueType::BoundMethod => self.as_bound_method().unwrap().trace(),
-                ValueType::Native => self.as_native().unwrap().trace(),
-                _ => (),
+            if let Some(object) = self.object() {
+                object.trace();
             }
         }
 
         fn root(&self) {
-            match self.value_type() {
-                ValueType::String => self.as_string().unwrap().root(),
-                ValueType::Upvalue => self.as_upvalue().unwrap().root(),
-                ValueType::Function => self.as_function().unwrap().root(),
-                ValueType::Closure => self.as_closure().unwrap().root(),
-                ValueType::Class => self.as_class().unwrap().root(),
-                ValueType::Instance => self.as_instance().unwrap().root(),
-                ValueType::BoundMethod => self.as_bound_method().unwrap().root(),
-                ValueType::Native => self.as_native().unwrap().root(),
-                _ => (),
+            if let Some(object) = self.object() {
+                object.root();
             }
         }
 
         fn unroot(&self) {
-            match self.value_type() {
-                ValueType::String => self.as_string().unwrap().unroot(),
-                ValueType::Upvalue => self.as_upvalue().unwrap().unroot(),
-                ValueType::Function => self.as_function().unwrap().unroot(),
-                ValueType::Closure => self.as_closure().unwrap().unroot(),
-                ValueType::Class => self.as_class().unwrap().unroot(),
-                ValueType::Instance => self.as_instance().unwrap().unroot(),
-                ValueType::BoundMethod => self.as_bound_method().unwrap().unroot(),
-                ValueType::Native => self.as_native().unwrap().unroot(),
-                _ => (),
+            if let Some(object) = self.object() {
+                object.unroot();
             }
         }
     }
 
     impl Drop for Value {
         fn drop(&mut self) {
-            match self.value_type() {
-                ValueType::String => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.string);
-                },
-                ValueType::Upvalue => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.upvalue);
-                },
-                ValueType::Function => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.function);
-                },
-                ValueType::Closure => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.closure);
-                },
-                ValueType::Class => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.class);
-                },
-                ValueType::Instance => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.instance);
-                },
-                ValueType::BoundMethod => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.bound_method);
-                },
-                ValueType::Native => unsafe {
-                    self.bits &= !(QNAN | SIGN_BIT | NATIVE_FN);
-                    ManuallyDrop::drop(&mut self.native);
-                },
-                _ => (),
+            unsafe {
+                match self.value_type() {
+                    ValueType::String => ManuallyDrop::drop(&mut self.string),
+                    ValueType::Upvalue => ManuallyDrop::drop(&mut self.upvalue),
+                    ValueType::Function => ManuallyDrop::drop(&mut self.function),
+                    ValueType::Closure => ManuallyDrop::drop(&mut self.closure),
+                    ValueType::Class => ManuallyDrop::drop(&mut self.class),
+                    ValueType::Instance => ManuallyDrop::drop(&mut self.instance),
+                    ValueType::BoundMethod => ManuallyDrop::drop(&mut self.bound_method),
+                    ValueType::Native => ManuallyDrop::drop(&mut self.native),
+                    ValueType::Array => ManuallyDrop::drop(&mut self.array),
+                    ValueType::Map => ManuallyDrop::drop(&mut self.map),
+                    _ => (),
+                }
             }
         }
     }
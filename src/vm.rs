@@ -1,35 +1,59 @@
-use crate::chunk::{Chunk, OpCode};
+use crate::chunk::{CacheHit, Chunk, OpCode, PropertyCache};
+use crate::compiler::CompilerOptions;
 use crate::gc::Gc;
 use crate::object::{
-    ObjBoundMethod, ObjClass, ObjClosure, ObjInstance, ObjNative, ObjString, ObjUpvalue,
+    HostFn, MapKey, NativeError, NativeFn, NativeFunction, ObjArray, ObjBoundMethod, ObjClass,
+    ObjClosure, ObjFunction, ObjInstance, ObjMap, ObjNative, ObjString, ObjUpvalue,
 };
+use crate::global::GlobalSlots;
+use crate::methods::{array_method, map_method, string_method};
+use crate::table::Table;
 use crate::value::{value::*, ValueType};
 
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::cmp::Ordering;
 
 const STACK_MAX: usize = 256;
+/// The default for `VM::set_max_frames`.
+const FRAMES_MAX: usize = 64;
+//how many calls a "Stack overflow." error lists
+const BACKTRACE_FRAMES: usize = 10;
 thread_local! {
     pub static START_TIME: Cell<std::time::Instant> = Cell::new(std::time::Instant::now());
 }
 
+//`$symbol` is the operator as written, for the error naming the operand that isn't a number.
+//the second form gives the result as an expression of the two numbers
 macro_rules! binary_op {
-    ($vm: expr, $create_fn: ident, $op: tt) => {
+    ($vm: expr, $symbol: expr, |$a: ident, $b: ident| $result: expr) => {
         {
             use crate::value::value::Value;
-            if !Value::is_number($vm.peek(0)?) || !Value::is_number($vm.peek(1)?) {
-                $vm.runtime_error(format!("Operands must be numbers."))?;
+            for (side, distance) in [("Left", 1), ("Right", 0)] {
+                let operand = $vm.peek(distance)?;
+                if !Value::is_number(operand) {
+                    let type_name = operand.value_type().name();
+                    let message = format!(
+                        "{} operand of '{}' must be a number, got {}.",
+                        side, $symbol, type_name
+                    );
+                    $vm.runtime_error(message)?;
+                }
             }
-            let b = $vm.pop()?.as_number().or_else(|_| $vm.runtime_error(format!("Operand must be a number.")))?;
-            let a = $vm.pop()?.as_number().or_else(|_| $vm.runtime_error(format!("Operand must be a number.")))?;
-            $vm.push(Value::$create_fn(a $op b))?;
+            let $b = $vm.pop()?.as_number().unwrap();
+            let $a = $vm.pop()?.as_number().unwrap();
+            $vm.push($result)?;
         }
-    }
+    };
+    ($vm: expr, $create_fn: ident, $op: tt, $symbol: expr) => {
+        binary_op!($vm, $symbol, |a, b| Value::$create_fn(a $op b))
+    };
 }
 #[derive(Clone, Copy, Debug)]
 pub enum InterpretError {
     Compile,
     Runtime,
+    //the script called `exit` with this code
+    Exit(i32),
 }
 #[derive(Clone)]
 pub struct CallFrame {
@@ -38,6 +62,14 @@ pub struct CallFrame {
     stack_offset: usize,
 }
 
+//where a runtime error in a `try` block goes: the `catch` at `ip` in the innermost of `frames`
+//frames, once the stack is cut back to `stack_index`
+struct Handler {
+    frames: usize,
+    stack_index: usize,
+    ip: usize,
+}
+
 impl CallFrame {
     fn new(closure: Gc<ObjClosure>, stack_offset: usize) -> Self {
         Self {
@@ -48,39 +80,565 @@ impl CallFrame {
     }
 }
 
-fn clock_native(_: *mut [Value]) -> Value {
-    Value::number(START_TIME.with(|start_time| start_time.get().elapsed().as_secs_f64()))
+fn clock_native(_: *mut [Value]) -> Result<Value, NativeError> {
+    Ok(Value::number(START_TIME.with(|start_time| start_time.get().elapsed().as_secs_f64())))
+}
+
+//whole milliseconds, so differences between two readings are exact
+fn time_millis_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [] => Ok(Value::int(
+            START_TIME.with(|start_time| start_time.get().elapsed().as_millis()) as i64,
+        )),
+        args => Err(arity_error(0, args.len())),
+    }
+}
+
+fn sleep_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [ms] => match ms.as_number() {
+            Ok(ms) if ms >= 0.0 && ms.is_finite() => {
+                std::thread::sleep(std::time::Duration::from_secs_f64(ms / 1000.0));
+                Ok(Value::nil())
+            }
+            _ => Err(native_error("Sleep duration must be a non-negative number.")),
+        },
+        args => Err(arity_error(1, args.len())),
+    }
+}
+
+fn map_native(_: *mut [Value]) -> Result<Value, NativeError> {
+    Ok(Value::map(ObjMap::new(Table::new())))
+}
+
+fn assert_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    let args = unsafe { &*args };
+    match args {
+        [condition] | [condition, _] if !condition.is_falsey() => Ok(Value::nil()),
+        [_] => Err(NativeError::Runtime("Assertion failed.".to_string())),
+        [_, message] => Err(NativeError::Runtime(format!("Assertion failed: {}", message))),
+        _ => Err(NativeError::Runtime(format!(
+            "Expected 1 or 2 arguments but got {}.",
+            args.len()
+        ))),
+    }
+}
+
+fn exit_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    let args = unsafe { &*args };
+    match args {
+        [] => Err(NativeError::Exit(0)),
+        [code] => match code.as_number() {
+            Ok(code) if code.fract() == 0.0 => Err(NativeError::Exit(code as i32)),
+            _ => Err(NativeError::Runtime("Exit code must be an integer.".to_string())),
+        },
+        _ => Err(NativeError::Runtime(format!(
+            "Expected 0 or 1 arguments but got {}.",
+            args.len()
+        ))),
+    }
+}
+
+//passes if calling its argument raises a runtime error, which isn't reported
+fn assert_throws_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [callee] => match host.try_call(callee.clone(), &[])? {
+            Ok(_) => Err(native_error("Expected an error but none was thrown.")),
+            Err(_) => Ok(Value::nil()),
+        },
+        args => Err(arity_error(1, args.len())),
+    }
+}
+
+//the callbacks see the elements the array had when the call began
+fn map_array_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, callback] => {
+            let mut mapped = vec![];
+            for element in array_elements(array, "map")? {
+                mapped.push(host.call(callback.clone(), &[element])?);
+            }
+            Ok(Value::array(ObjArray::new(mapped)))
+        }
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+fn filter_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, callback] => {
+            let mut kept = vec![];
+            for element in array_elements(array, "filter")? {
+                if !host.call(callback.clone(), std::slice::from_ref(&element))?.is_falsey() {
+                    kept.push(element);
+                }
+            }
+            Ok(Value::array(ObjArray::new(kept)))
+        }
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+//the callback gets the running total and the next element, starting from `init`
+fn reduce_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, callback, init] => {
+            let mut total = init.clone();
+            for element in array_elements(array, "reduce")? {
+                total = host.call(callback.clone(), &[total, element])?;
+            }
+            Ok(total)
+        }
+        args => Err(arity_error(3, args.len())),
+    }
+}
+
+//a copy, so a callback can change the array without upsetting the loop over it
+fn array_elements(array: &Value, native: &str) -> Result<Vec<Value>, NativeError> {
+    match array.as_array() {
+        Ok(array) => Ok(array.borrow().elements.clone()),
+        Err(_) => Err(NativeError::Runtime(format!(
+            "First argument to '{}' must be an array.",
+            native
+        ))),
+    }
+}
+
+//natives aren't included, so at the start of a script this is empty
+fn globals_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [] => {
+            let names = host
+                .global_names()
+                .into_iter()
+                .map(|name| Value::string(ObjString::new(name)))
+                .collect();
+            Ok(Value::array(ObjArray::new(names)))
+        }
+        args => Err(arity_error(0, args.len())),
+    }
+}
+
+pub(crate) fn native_error(message: &str) -> NativeError {
+    NativeError::Runtime(message.to_string())
+}
+
+pub(crate) fn arity_error(expected: usize, got: usize) -> NativeError {
+    NativeError::Runtime(format!("Expected {} arguments but got {}.", expected, got))
+}
+
+//elements are compared with `==`, so NaN is never found
+fn position_in(array: &Value, value: &Value, native: &str) -> Result<Option<usize>, NativeError> {
+    match array.as_array() {
+        Ok(array) => Ok(array.borrow().elements.iter().position(|element| element == value)),
+        Err(_) => Err(NativeError::Runtime(format!(
+            "First argument to '{}' must be an array.",
+            native
+        ))),
+    }
+}
+
+fn contains_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, value] => Ok(Value::bool_(position_in(array, value, "contains")?.is_some())),
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+fn index_of_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, value] => match position_in(array, value, "index_of")? {
+            Some(index) => Ok(Value::int(index as i64)),
+            None => Ok(Value::int(-1)),
+        },
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+fn map_has_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [map, key] => {
+            let map = map
+                .as_map()
+                .map_err(|_| native_error("First argument to 'map_has' must be a map."))?;
+            let key = MapKey::new(key.clone()).map_err(|_| {
+                native_error("Map key must be nil, a boolean, a number, or a string.")
+            })?;
+            let has = map.borrow().entries.get(&key).is_some();
+            Ok(Value::bool_(has))
+        }
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+//elements are written as `print` would write them
+fn join_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [array, separator] => {
+            let array = array
+                .as_array()
+                .map_err(|_| native_error("First argument to 'join' must be an array."))?;
+            let separator = separator
+                .as_string()
+                .map_err(|_| native_error("Separator must be a string."))?;
+            //checked as it grows, so a huge result fails before it's built
+            let mut joined = String::new();
+            for (i, element) in array.borrow().elements.iter().enumerate() {
+                if i > 0 {
+                    joined.push_str(separator.borrow().as_str());
+                }
+                joined += &host.stringify(element)?;
+                if joined.len() > host.max_string_length() {
+                    return Err(native_error("Joined string is too long."));
+                }
+            }
+            Ok(Value::string(ObjString::new(joined)))
+        }
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+//what `print` would show, including the result of a `toString` method
+fn str_native(host: &mut dyn Host, args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [value] => Ok(Value::string(ObjString::new(host.stringify(value)?))),
+        args => Err(arity_error(1, args.len())),
+    }
+}
+
+//`/` always divides as floats; this rounds the quotient down, so -7 / 2 gives -4
+fn floor_div_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        //integers stay exact, unless the quotient overflows
+        #[cfg(feature = "integers")]
+        [a, b]
+            if matches!((a.as_int(), b.as_int()), (Ok(a), Ok(b)) if a.checked_div(b).is_some()) =>
+        {
+            let (a, b) = (a.as_int().unwrap(), b.as_int().unwrap());
+            let rounded_up = a % b != 0 && (a < 0) != (b < 0);
+            Ok(Value::int(a / b - rounded_up as i64))
+        }
+        [a, b] => match (a.as_number(), b.as_number()) {
+            (Ok(_), Ok(0.0)) => Err(native_error("Division by zero.")),
+            (Ok(a), Ok(b)) => Ok(Value::number((a / b).floor())),
+            _ => Err(native_error("Arguments to 'floor_div' must be numbers.")),
+        },
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+//a fixed number of decimal places, unlike `print`, which shows up to six significant digits
+fn format_number_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [x, decimals] => match (x.as_number(), decimals.as_number()) {
+            (Ok(x), Ok(decimals))
+                if decimals.fract() == 0.0 && (0.0..=100.0).contains(&decimals) =>
+            {
+                let formatted = format!("{:.*}", decimals as usize, x);
+                Ok(Value::string(ObjString::new(formatted)))
+            }
+            (Ok(_), _) => Err(native_error("Decimal places must be an integer from 0 to 100.")),
+            _ => Err(native_error("First argument to 'format_number' must be a number.")),
+        },
+        args => Err(arity_error(2, args.len())),
+    }
+}
+
+//strings count characters, not bytes; see `byte_len`
+fn len_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    let len = match unsafe { &*args } {
+        [string] if string.is_string() => string.as_string().unwrap().borrow().char_len(),
+        [array] if array.is_array() => array.as_array().unwrap().borrow().elements.len(),
+        [map] if map.is_map() => map.as_map().unwrap().borrow().entries.len(),
+        [_] => return Err(native_error("Argument to 'len' must be a string, array or map.")),
+        args => return Err(arity_error(1, args.len())),
+    };
+    Ok(Value::int(len as i64))
+}
+
+fn byte_len_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [string] if string.is_string() => {
+            Ok(Value::int(string.as_string().unwrap().borrow().byte_len() as i64))
+        }
+        [_] => Err(native_error("Argument to 'byte_len' must be a string.")),
+        args => Err(arity_error(1, args.len())),
+    }
+}
+
+//returns the instance, so it can wrap a constructor call
+fn freeze_native(args: *mut [Value]) -> Result<Value, NativeError> {
+    match unsafe { &*args } {
+        [value] if value.as_instance().is_ok() => {
+            value.as_instance().unwrap().borrow_mut().frozen = true;
+            Ok(value.clone())
+        }
+        [_] => Err(native_error("Argument to 'freeze' must be an instance.")),
+        args => Err(arity_error(1, args.len())),
+    }
+}
+
+/// Frees what a `VM` allocated when it's dropped. Nothing outside the VM roots its objects, so
+/// without a last collection they'd stay allocated until something else on the thread allocates.
+struct CollectOnDrop;
+
+impl Drop for CollectOnDrop {
+    fn drop(&mut self) {
+        crate::gc::collect_garbage();
+    }
+}
+
+/// What natives created with `ObjNative::host` can ask of the VM running them.
+pub trait Host {
+    /// Calls `callee` with `args` and runs it to completion. A runtime error it raises is
+    /// unwound and its message handed back instead of being reported.
+    fn try_call(
+        &mut self,
+        callee: Value,
+        args: &[Value],
+    ) -> Result<Result<Value, String>, NativeError>;
+
+    /// Calls `callee` with `args` like `try_call`, but a runtime error it raises becomes the
+    /// native's error.
+    fn call(&mut self, callee: Value, args: &[Value]) -> Result<Value, NativeError>;
+
+    /// The longest string in bytes a native may build; see `VM::set_max_string_length`.
+    fn max_string_length(&self) -> usize;
+
+    /// The text `print` would show for `value`, calling `toString` methods. A runtime error in
+    /// one of them is handed back as the native's error.
+    fn stringify(&mut self, value: &Value) -> Result<String, NativeError>;
+
+    /// The names of the globals scripts have defined, in the order they were first defined; see
+    /// `VM::defined_globals`.
+    fn global_names(&self) -> Vec<String>;
 }
 
 pub struct VM<'a, StdOut: std::io::Write, StdErr: std::io::Write> {
     frames: Vec<CallFrame>,
-    frame_count: usize,
+    max_frames: usize,
+    //how many calls a runtime error lists after its message
+    backtrace_frames: usize,
     stack: [Value; STACK_MAX],
     stack_index: usize,
-    globals: HashMap<Gc<ObjString>, Value>,
+    //the slot of every global name this VM's scripts and natives have used; scripts have to be
+    //compiled with it, see `compile`
+    global_slots: GlobalSlots,
+    //indexed by the slots in `global_slots`; `None` until the global is defined
+    globals: Vec<Option<Value>>,
+    //the slots scripts have defined, in the order they were first defined; slot numbers follow
+    //the order names were first seen, which a forward reference can change
+    global_order: Vec<u16>,
     pub init_string: Gc<ObjString>,
-    pub open_upvalues: Option<Gc<ObjUpvalue>>,
+    pub bool_string: Gc<ObjString>,
+    pub equals_string: Gc<ObjString>,
+    pub to_string_string: Gc<ObjString>,
+    pub get_field_string: Gc<ObjString>,
+    pub set_field_string: Gc<ObjString>,
+    pythonic_truthiness: bool,
+    ieee_division: bool,
+    trace_execution: bool,
+    compiler_options: CompilerOptions,
+    //sorted by the stack slot each one points at, lowest first, so a capture can binary search
+    //for an existing upvalue and closing a frame's upvalues pops them off the end
+    pub open_upvalues: Vec<Gc<ObjUpvalue>>,
+    //set while `try_call` runs; runtime errors are kept here instead of being reported
+    caught_error: Option<Option<String>>,
+    //the `try` blocks being run, innermost last, and the value thrown or the message of the
+    //runtime error on its way to the innermost one's `catch`
+    handlers: Vec<Handler>,
+    thrown: Option<Value>,
+    //the arrays and maps being printed and the instances whose `toString` is running, innermost
+    //last; see `stringify`
+    stringifying: Vec<Value>,
+    //what the outermost function returned when it last finished, for `eval_expression`
+    returned: Value,
+    //the function `run_function` was last given, as compiled with `compiler_options`
+    script: Option<Gc<ObjFunction>>,
+    //instructions left to run and bytes left to print, when limited
+    step_budget: Option<u64>,
+    output_budget: Option<usize>,
+    //the longest string concatenation, repetition or `join` may build, in bytes
+    max_string_length: usize,
     out: &'a mut StdOut,
     err: &'a mut StdErr,
+    //fields drop in order, so this runs once every handle above has been released
+    _collect: CollectOnDrop,
 }
 
 impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr> {
     pub fn new(out: &'a mut StdOut, err: &'a mut StdErr) -> Self {
         let mut result = Self {
-            frames: vec![],
-            frame_count: 0,
+            frames: Vec::with_capacity(FRAMES_MAX),
+            max_frames: FRAMES_MAX,
+            backtrace_frames: 0,
             stack: std::array::from_fn(|_| Value::number(0.0).clone()),
             stack_index: 0,
-            globals: HashMap::new(),
+            global_slots: GlobalSlots::new(),
+            globals: vec![],
+            global_order: vec![],
             init_string: ObjString::new("init".to_string()),
-            open_upvalues: None,
+            bool_string: ObjString::new("bool".to_string()),
+            equals_string: ObjString::new("equals".to_string()),
+            to_string_string: ObjString::new("toString".to_string()),
+            get_field_string: ObjString::new("getField".to_string()),
+            set_field_string: ObjString::new("setField".to_string()),
+            pythonic_truthiness: false,
+            ieee_division: false,
+            trace_execution: false,
+            compiler_options: CompilerOptions::default(),
+            open_upvalues: vec![],
+            caught_error: None,
+            handlers: vec![],
+            thrown: None,
+            stringifying: vec![],
+            returned: Value::nil(),
+            script: None,
+            step_budget: None,
+            output_budget: None,
+            max_string_length: isize::MAX as usize,
             out,
             err,
+            _collect: CollectOnDrop,
         };
         result.define_native("clock", clock_native);
+        result.define_native("time_millis", time_millis_native);
+        result.define_native("sleep", sleep_native);
+        result.define_native("Map", map_native);
+        result.define_native("assert", assert_native);
+        result.define_native("exit", exit_native);
+        result.define_native("contains", contains_native);
+        result.define_native("index_of", index_of_native);
+        result.define_native("map_has", map_has_native);
+        result.define_native("floor_div", floor_div_native);
+        result.define_host_native("join", join_native);
+        result.define_native("format_number", format_number_native);
+        result.define_native("len", len_native);
+        result.define_native("byte_len", byte_len_native);
+        result.define_native("freeze", freeze_native);
+        result.define_host_native("str", str_native);
+        result.define_host_native("assert_throws", assert_throws_native);
+        result.define_host_native("globals", globals_native);
+        result.define_host_native("map", map_array_native);
+        result.define_host_native("filter", filter_native);
+        result.define_host_native("reduce", reduce_native);
         result
     }
 
+    /// Makes zero, the empty string and empty collections falsey. Off by default, where only
+    /// `nil` and `false` are.
+    pub fn set_pythonic_truthiness(&mut self, enabled: bool) {
+        self.pythonic_truthiness = enabled;
+    }
+
+    /// Makes dividing by zero give infinity, or NaN for `0 / 0`, as IEEE 754 floats do. Off by
+    /// default, where it's a "Division by zero." runtime error.
+    pub fn set_ieee_division(&mut self, enabled: bool) {
+        self.ieee_division = enabled;
+    }
+
+    /// Limits how deeply calls can nest before a "Stack overflow." runtime error, which lists the
+    /// innermost calls.
+    pub fn set_max_frames(&mut self, max_frames: usize) {
+        self.max_frames = max_frames;
+        self.frames.reserve(max_frames.saturating_sub(self.frames.len()));
+    }
+
+    /// Lists the line of each of the innermost `frames` calls after a runtime error's message,
+    /// the innermost being where the error happened. 0, the default, shows just the message.
+    pub fn set_backtrace(&mut self, frames: usize) {
+        self.backtrace_frames = frames;
+    }
+
+    /// Limits how many more instructions the VM runs, across every script it's given, before a
+    /// "Step budget exhausted." runtime error. `None` lifts the limit.
+    pub fn set_step_budget(&mut self, steps: Option<u64>) {
+        self.step_budget = steps;
+    }
+
+    /// Limits how many more bytes `print` writes before an "Output limit exceeded." runtime
+    /// error. `None` lifts the limit.
+    pub fn set_output_budget(&mut self, bytes: Option<usize>) {
+        self.output_budget = bytes;
+    }
+
+    /// Limits the length in bytes of strings built by concatenation, repetition and `join`.
+    pub fn set_max_string_length(&mut self, bytes: usize) {
+        self.max_string_length = bytes;
+    }
+
+    /// Sets every limit to a conservative default for running untrusted scripts:
+    /// - 64 nested calls;
+    /// - 10 million instructions, a few seconds of work;
+    /// - 1 MiB of output;
+    /// - strings of up to 1 MiB, so memory use is bounded by the instruction budget;
+    /// - no `sleep`, which could otherwise hang without running any instructions.
+    ///
+    /// No native touches the filesystem or the environment, so there is nothing to turn off
+    /// for those.
+    pub fn sandbox_defaults(&mut self) {
+        self.set_max_frames(FRAMES_MAX);
+        self.set_step_budget(Some(10_000_000));
+        self.set_output_budget(Some(1 << 20));
+        self.set_max_string_length(1 << 20);
+        let slot = self.global_slots.slot("sleep").expect("natives have the first slots");
+        if let Some(sleep) = self.globals.get_mut(slot as usize) {
+            *sleep = None;
+        }
+    }
+
+    /// Logs the stack and each instruction to the err writer before it runs.
+    pub fn set_trace_execution(&mut self, trace_execution: bool) {
+        self.trace_execution = trace_execution;
+    }
+
+    /// Compiles later scripts in strict mode; see `CompilerOptions::strict`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.compiler_options.strict = strict;
+    }
+
+    /// Compiles later scripts in relaxed mode; see `CompilerOptions::relaxed`.
+    pub fn set_relaxed(&mut self, relaxed: bool) {
+        self.compiler_options.relaxed = relaxed;
+    }
+
+    /// Shows the source line and carets under the token after each compile error and warning;
+    /// see `CompilerOptions::snippets`.
+    pub fn set_snippets(&mut self, snippets: bool) {
+        self.compiler_options.snippets = snippets;
+    }
+
+    /// Warns about local variables that are never used; see `CompilerOptions::warn_unused`.
+    pub fn set_warn_unused(&mut self, warn_unused: bool) {
+        self.compiler_options.warn_unused = warn_unused;
+    }
+
+    /// Warns about statements that can never run; see `CompilerOptions::warn_unreachable`.
+    pub fn set_warn_unreachable(&mut self, warn_unreachable: bool) {
+        self.compiler_options.warn_unreachable = warn_unreachable;
+    }
+
+    /// Runs the peephole pass over later scripts; see `CompilerOptions::optimize`.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.compiler_options.optimize = optimize;
+    }
+
+    /// Lets later scripts leave out `;` at the end of a line; see
+    /// `CompilerOptions::infer_semicolons`.
+    pub fn set_infer_semicolons(&mut self, infer_semicolons: bool) {
+        self.compiler_options.infer_semicolons = infer_semicolons;
+    }
+
+    /// The options later scripts are compiled with.
+    pub fn compiler_options(&self) -> CompilerOptions {
+        self.compiler_options
+    }
+
+    /// Compiles later scripts the way the REPL wants them; see `CompilerOptions::repl`.
+    pub fn set_repl_mode(&mut self, repl: bool) {
+        self.compiler_options.repl = repl;
+    }
+
     pub fn current_chunk(&self) -> Gc<Chunk> {
         self.current_frame()
             .closure
@@ -91,8 +649,16 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
             .clone()
     }
 
+    //forgets the script that failed, so the next one the host runs starts from an empty stack
+    //instead of returning into it. Its locals are closed over first, in case a closure stored in
+    //a global still refers to them.
     pub fn reset_stack(&mut self) {
+        let first = self.stack.as_mut_ptr();
+        self.close_upvalues(first);
         self.stack_index = 0;
+        self.frames.clear();
+        self.handlers.clear();
+        self.thrown = None;
     }
     pub fn current_frame(&self) -> &CallFrame {
         self.frames.last().unwrap()
@@ -103,15 +669,32 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
     }
 
     fn runtime_error<T>(&mut self, msg: String) -> Result<T, InterpretError> {
+        self.runtime_error_with_trace(msg, self.backtrace_frames)
+    }
+
+    //reports `msg` followed by where the innermost `frames` calls were, innermost first
+    fn runtime_error_with_trace<T>(
+        &mut self,
+        msg: String,
+        frames: usize,
+    ) -> Result<T, InterpretError> {
+        if !self.handlers.is_empty() {
+            return self.throw(Value::string(ObjString::new(msg)));
+        }
+        if let Some(caught) = &mut self.caught_error {
+            *caught = Some(msg);
+            return Err(InterpretError::Runtime);
+        }
         writeln!(self.err, "{}", msg).ok();
-        for i in (0..self.frame_count).rev() {
+        let frames = frames.min(self.frames.len());
+        for i in (self.frames.len() - frames..self.frames.len()).rev() {
             let frame = &self.frames[i];
             let closure = frame.closure.borrow();
             let function = closure.function.borrow();
             write!(
                 self.err,
                 "[line {}] in ",
-                function.chunk.borrow().get_line(frame.ip - 1)
+                function.chunk.borrow().get_line(frame.ip)
             )
             .ok();
             match &function.name {
@@ -119,18 +702,68 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                 Some(string) => writeln!(self.err, "{}", string.borrow().as_str()).ok(),
             };
         }
+        if frames > 0 && frames < self.frames.len() {
+            writeln!(self.err, "... {} more calls", self.frames.len() - frames).ok();
+        }
         self.reset_stack();
         Err(InterpretError::Runtime)
     }
 
-    fn define_native(&mut self, name: &str, function: fn(*mut [Value]) -> Value) {
-        let name = ObjString::new(name.to_string());
+    //sends `value` to the innermost `catch`; with none to catch it, its text is reported like
+    //any other runtime error
+    fn throw<T>(&mut self, value: Value) -> Result<T, InterpretError> {
+        if self.handlers.is_empty() {
+            let message = self.stringify(&value)?;
+            return self.runtime_error(message);
+        }
+        self.thrown = Some(value);
+        Err(InterpretError::Runtime)
+    }
+
+    //deep recursion is easier to follow with the calls that led to it, but only the innermost
+    //few, since there are as many as the frame limit allows
+    fn stack_overflow<T>(&mut self) -> Result<T, InterpretError> {
+        self.runtime_error_with_trace("Stack overflow.".to_string(), BACKTRACE_FRAMES)
+    }
+
+    fn define_native(&mut self, name: &str, function: NativeFn) {
+        let slot = self.global_slots.slot(name).expect("natives have the first slots");
         let native = Value::native(ObjNative::new(function).into());
-        self.globals.insert(name, native);
+        self.set_global(slot, native);
+    }
+
+    fn define_host_native(&mut self, name: &str, function: HostFn) {
+        let slot = self.global_slots.slot(name).expect("natives have the first slots");
+        let native = Value::native(ObjNative::host(function));
+        self.set_global(slot, native);
+    }
+
+    /// The globals scripts have defined and their current values, in the order they were first
+    /// defined. Natives aren't included.
+    pub fn defined_globals(&self) -> Vec<(String, Value)> {
+        self.global_order
+            .iter()
+            .map(|slot| {
+                let name = self.global_slots.name(*slot).to_string();
+                (name, self.get_global(*slot).unwrap().clone())
+            })
+            .collect()
+    }
+
+    fn get_global(&self, slot: u16) -> Option<&Value> {
+        self.globals.get(slot as usize).and_then(Option::as_ref)
+    }
+
+    fn set_global(&mut self, slot: u16, value: Value) {
+        let slot = slot as usize;
+        if slot >= self.globals.len() {
+            self.globals.resize(slot + 1, None);
+        }
+        self.globals[slot] = Some(value);
     }
 
     pub fn peek(&mut self, index: usize) -> Result<&mut Value, InterpretError> {
-        if index > self.stack_index {
+        if index >= self.stack_index {
             self.runtime_error(format!(
                 "Peek index {} is greater than stack size {}.",
                 index, self.stack_index
@@ -140,11 +773,36 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
     }
 
     pub fn get_value_slice(&mut self, arg_count: usize) -> Result<*mut [Value], InterpretError> {
-        let stack = &mut self.stack;
-        let (_, slice) = stack.split_at_mut(self.stack_index - arg_count);
+        let slice = &mut self.stack[self.stack_index - arg_count..self.stack_index];
         Ok(slice as *mut _)
     }
 
+    /// Calls `function` with the top `arg_count` values, preceded by the receiver in the slot
+    /// below them if `with_receiver` is set, and replaces them and that slot with its result.
+    fn call_native(
+        &mut self,
+        function: NativeFunction,
+        arg_count: usize,
+        with_receiver: bool,
+    ) -> Result<(), InterpretError> {
+        let callee_slot = self.stack_index - arg_count - 1;
+        let args = self.get_value_slice(arg_count + with_receiver as usize)?;
+        let result = match function {
+            NativeFunction::Plain(function) => function(args),
+            //the host can run code that exits and empties the stack, so don't count down from it
+            NativeFunction::Host(function) => function(self, args),
+        };
+        self.stack_index = callee_slot;
+        match result {
+            Ok(result) => self.push(result),
+            Err(NativeError::Runtime(message)) => self.runtime_error(message),
+            Err(NativeError::Exit(code)) => {
+                self.reset_stack();
+                Err(InterpretError::Exit(code))
+            }
+        }
+    }
+
     pub fn call(&mut self, callee: Gc<ObjClosure>, arg_count: usize) -> Result<(), InterpretError> {
         let arity = callee.borrow().function.borrow().arity;
         if arg_count != arity {
@@ -153,12 +811,48 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                 arity, arg_count,
             ));
         }
+        if self.frames.len() >= self.max_frames {
+            return self.stack_overflow();
+        }
 
         let frame = CallFrame::new(callee, self.stack_index - arg_count - 1);
         self.frames.push(frame);
         Ok(())
     }
 
+    /// Calls `callee` in place of the current frame, so `return f();` runs in constant frame
+    /// space. Anything that isn't a Lox function is called normally and the `Return` after the
+    /// `TailCall` returns its result.
+    fn tail_call_value(&mut self, callee: Value, arg_count: usize) -> Result<(), InterpretError> {
+        let closure = if let Ok(closure) = callee.as_closure() {
+            closure
+        } else if let Ok(bound_method) = callee.as_bound_method() {
+            self.stack[self.stack_index - arg_count - 1] = bound_method.borrow().receiver.clone();
+            bound_method.borrow().method.clone()
+        } else {
+            return self.call_value(callee, arg_count);
+        };
+        let arity = closure.borrow().function.borrow().arity;
+        if arg_count != arity {
+            return self.runtime_error(format!(
+                "Expected {} arguments but got {}.",
+                arity, arg_count,
+            ));
+        }
+
+        //the replaced frame's locals die here, so anything that captured them is closed first
+        let stack_offset = self.current_frame().stack_offset;
+        let last = &mut self.stack[stack_offset] as *mut _;
+        self.close_upvalues(last);
+        let start = self.stack_index - arg_count - 1;
+        for i in 0..=arg_count {
+            self.stack[stack_offset + i] = self.stack[start + i].clone();
+        }
+        self.stack_index = stack_offset + arg_count + 1;
+        *self.current_frame_mut() = CallFrame::new(closure, stack_offset);
+        Ok(())
+    }
+
     pub fn call_value(&mut self, callee: Value, arg_count: usize) -> Result<(), InterpretError> {
         match callee.value_type() {
             ValueType::BoundMethod => {
@@ -171,8 +865,8 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                 let class = callee.as_class().unwrap();
                 self.stack[self.stack_index - arg_count - 1] =
                     Value::instance(ObjInstance::new(class.clone()).into());
-                if let Some(closure) = class.borrow().methods.get(&self.init_string) {
-                    return self.call(closure.clone(), arg_count);
+                if let Some(closure) = ObjClass::find_method(&class, &self.init_string) {
+                    return self.call(closure, arg_count);
                 } else if arg_count != 0 {
                     return self
                         .runtime_error(format!("Expected 0 arguments but got {}.", arg_count));
@@ -181,25 +875,179 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
             }
             ValueType::Closure => return self.call(callee.as_closure().unwrap(), arg_count),
             ValueType::Native => {
-                let native = callee.as_native().unwrap().borrow().function;
-                let result = native(self.get_value_slice(arg_count)?);
-                self.stack_index -= arg_count + 1;
-                self.push(result)
+                let native = callee.as_native().unwrap();
+                let native = native.borrow();
+                match native.receiver.clone() {
+                    //the receiver takes the callee's slot, as for a bound method
+                    Some(receiver) => {
+                        self.stack[self.stack_index - arg_count - 1] = receiver;
+                        self.call_native(native.function, arg_count, true)
+                    }
+                    None => self.call_native(native.function, arg_count, false),
+                }
             }
             _ => return self.runtime_error("Can only call functions and classes.".to_string()),
         }
     }
 
+    /// Calls `callee` from inside the VM and runs it to completion, returning its result.
+    pub fn call_function(&mut self, callee: Value, args: &[Value]) -> Result<Value, InterpretError> {
+        let depth = self.frames.len();
+        self.push(callee.clone())?;
+        for arg in args {
+            self.push(arg.clone())?;
+        }
+        self.call_value(callee, args.len())?;
+        self.finish_call(depth)
+    }
+
+    fn call_method(
+        &mut self,
+        receiver: Value,
+        method: Gc<ObjClosure>,
+        args: &[Value],
+    ) -> Result<Value, InterpretError> {
+        let depth = self.frames.len();
+        self.push(receiver)?;
+        for arg in args {
+            self.push(arg.clone())?;
+        }
+        self.call(method, args.len())?;
+        self.finish_call(depth)
+    }
+
+    fn finish_call(&mut self, depth: usize) -> Result<Value, InterpretError> {
+        if self.frames.len() > depth {
+            self.run_to_depth(depth)?;
+        }
+        self.pop()
+    }
+
+    /// Truthiness used by every conditional opcode: instances defer to their class's `bool()`
+    /// method if it has one. The method's result is tested with plain truthiness, so it can't recurse.
+    fn is_falsey(&mut self, value: &Value) -> Result<bool, InterpretError> {
+        if let Ok(instance) = value.as_instance() {
+            let class = instance.borrow().class.clone();
+            let method = ObjClass::find_method(&class, &self.bool_string);
+            if let Some(method) = method {
+                let result = self.call_method(value.clone(), method, &[])?;
+                return Ok(self.is_falsey_value(&result));
+            }
+        }
+        Ok(self.is_falsey_value(value))
+    }
+
+    fn is_falsey_value(&self, value: &Value) -> bool {
+        if self.pythonic_truthiness {
+            value.is_pythonic_falsey()
+        } else {
+            value.is_falsey()
+        }
+    }
+
+    /// `==` for two values: when both are instances and the left one's class has an
+    /// `equals(other)` method, its result decides, tested with plain truthiness. Otherwise, and
+    /// inside the left operand's own methods so `equals` can compare `this` without recursing,
+    /// instances are only equal to themselves.
+    fn values_equal(&mut self, a: Value, b: Value) -> Result<bool, InterpretError> {
+        if let (Ok(instance), true) = (a.as_instance(), b.as_instance().is_ok()) {
+            let class = instance.borrow().class.clone();
+            let in_own_method = self
+                .frames
+                .last()
+                .is_some_and(|frame| self.stack[frame.stack_offset] == a);
+            let method = ObjClass::find_method(&class, &self.equals_string);
+            if let (Some(method), false) = (method, in_own_method) {
+                let result = self.call_method(a, method, &[b])?;
+                return Ok(!self.is_falsey_value(&result));
+            }
+        }
+        Ok(a == b)
+    }
+
+    /// Finds the `getField`/`setField` method that handles properties of `receiver` that are
+    /// neither fields nor methods. Code running in one of the receiver's own methods bypasses it
+    /// and sees the real fields, so `init` can set up storage and a fallback can't recurse into
+    /// itself.
+    fn property_fallback(
+        &self,
+        receiver: &Value,
+        class: &Gc<ObjClass>,
+        name: &Gc<ObjString>,
+    ) -> Option<Gc<ObjClosure>> {
+        let frame = self.frames.last()?;
+        if self.stack[frame.stack_offset] == *receiver {
+            return None;
+        }
+        ObjClass::find_method(class, name)
+    }
+
+    /// Reads `name` from `instance` the slow way and remembers where it was found in the
+    /// property cache `cache`. Properties handled by `getField` aren't cached.
+    ///
+    /// A property is looked up as a field first, then as a method, then through the class's
+    /// `getField` (see `property_fallback`). Instances have no declared fields, so a field that
+    /// was never assigned is simply missing: reading it is an "Undefined property" error, not
+    /// `nil`. A class that wants unset fields to read as `nil` can return it from `getField`.
+    fn get_instance_property(
+        &mut self,
+        receiver: Value,
+        instance: Gc<ObjInstance>,
+        name: Gc<ObjString>,
+        cache: usize,
+    ) -> Result<(), InterpretError> {
+        let class = instance.borrow().class.clone();
+        let index = instance.borrow().fields.index_of(&name);
+        if let Some(index) = index {
+            let value = instance.borrow().fields.get_index(index).unwrap().1.clone();
+            self.current_chunk().borrow_mut().property_caches[cache] =
+                PropertyCache::Field(class, index);
+            self.pop()?;
+            return self.push(value);
+        }
+        let method = ObjClass::find_method(&class, &name);
+        if let Some(method) = method {
+            //cached before binding, since calling a getter switches to another chunk
+            self.current_chunk().borrow_mut().property_caches[cache] =
+                PropertyCache::Method(class, method.clone());
+            return self.bind_closure(method);
+        }
+        match self.property_fallback(&receiver, &class, &self.get_field_string) {
+            Some(get_field) => {
+                let value = self.call_method(receiver, get_field, &[Value::string(name)])?;
+                self.pop()?;
+                self.push(value)
+            }
+            None => self.runtime_error(format!("Undefined property '{}'.", name)),
+        }
+    }
+
     fn invoke_from_class(
         &mut self,
         class: Gc<ObjClass>,
         name: Gc<ObjString>,
         arg_count: usize,
     ) -> Result<(), InterpretError> {
-        match class.borrow().methods.get(&name) {
+        let method = match ObjClass::find_method(&class, &name) {
             None => return self.runtime_error(format!("Undefined property '{}'.", name)),
-            Some(method) => self.call(method.clone(), arg_count),
+            Some(method) => method,
+        };
+        self.invoke_closure(method, arg_count)
+    }
+
+    fn invoke_closure(
+        &mut self,
+        method: Gc<ObjClosure>,
+        arg_count: usize,
+    ) -> Result<(), InterpretError> {
+        if method.borrow().function.borrow().is_getter {
+            //`obj.getter(args)` calls whatever the getter returns
+            let receiver = self.peek(arg_count)?.clone();
+            let value = self.call_method(receiver, method, &[])?;
+            self.stack[self.stack_index - arg_count - 1] = value.clone();
+            return self.call_value(value, arg_count);
         }
+        self.call(method, arg_count)
     }
 
     fn invoke(&mut self, name: Gc<ObjString>, arg_count: usize) -> Result<(), InterpretError> {
@@ -208,79 +1056,116 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
             if let Some(value) = instance.borrow().fields.get(&name) {
                 self.stack[self.stack_index - arg_count - 1] = value.clone();
                 return self.call_value(value.clone(), arg_count);
-            } else {
-                return self.invoke_from_class(instance.borrow().class.clone(), name, arg_count);
             }
+            let class = instance.borrow().class.clone();
+            if ObjClass::find_method(&class, &name).is_none() {
+                let get_field = self.property_fallback(&receiver, &class, &self.get_field_string);
+                if let Some(get_field) = get_field {
+                    let value = self.call_method(receiver, get_field, &[Value::string(name)])?;
+                    self.stack[self.stack_index - arg_count - 1] = value.clone();
+                    return self.call_value(value, arg_count);
+                }
+            }
+            self.invoke_from_class(class, name, arg_count)
+        } else if let Ok(class) = receiver.as_class() {
+            let method = ObjClass::find_static_method(&class, &name);
+            match method {
+                Some(method) => self.invoke_closure(method, arg_count),
+                None => self.runtime_error("Only instances have methods.".to_string()),
+            }
+        } else if let Some(method) = self.builtin_method(&receiver, &name) {
+            self.call_native(NativeFunction::Plain(method), arg_count, true)
         } else {
             return self.runtime_error("Only instances have methods.".to_string());
         }
     }
 
+    /// The built-in method `name` of a value that isn't an instance or a class.
+    fn builtin_method(&self, receiver: &Value, name: &Gc<ObjString>) -> Option<NativeFn> {
+        if receiver.is_string() {
+            string_method(name.borrow().as_str())
+        } else if receiver.as_array().is_ok() {
+            array_method(name.borrow().as_str())
+        } else if receiver.as_map().is_ok() {
+            map_method(name.borrow().as_str())
+        } else {
+            None
+        }
+    }
+
     fn bind_method(
         &mut self,
         class: Gc<ObjClass>,
         name: Gc<ObjString>,
     ) -> Result<(), InterpretError> {
-        let class_borrow = class.borrow();
-        let method = class_borrow.methods.get(&name);
+        let method = ObjClass::find_method(&class, &name);
         match method {
-            Some(method) => {
-                let receiver = self.peek(0)?.clone();
-                let bound_method = ObjBoundMethod::new(receiver, method.clone());
-                self.pop()?;
-                self.push(Value::bound_method(bound_method.into()))
-            }
+            Some(method) => self.bind_closure(method),
             None => self.runtime_error(format!("Undefined property '{}'.", name)),
         }
     }
 
-    fn capture_upvalue(&mut self, local: *mut Value) -> Gc<ObjUpvalue> {
-        let mut previous_upvalue = None;
-        let mut current_upvalue = self.open_upvalues.clone();
-        while let Some(upvalue) = current_upvalue.clone() {
-            if upvalue.borrow().location <= local {
-                break;
-            }
-            previous_upvalue = Some(upvalue.clone());
-            current_upvalue = upvalue.borrow().next.clone();
+    /// Replaces the receiver on top of the stack with `method` bound to it, or with the result
+    /// of calling `method` if it's a getter.
+    fn bind_closure(&mut self, method: Gc<ObjClosure>) -> Result<(), InterpretError> {
+        if method.borrow().function.borrow().is_getter {
+            //the receiver on top of the stack becomes the getter's `this` slot
+            return self.call(method, 0);
         }
+        let receiver = self.peek(0)?.clone();
+        let bound_method = ObjBoundMethod::new(receiver, method);
+        self.pop()?;
+        self.push(Value::bound_method(bound_method))
+    }
 
-        if let Some(upvalue) = current_upvalue.clone() {
-            if upvalue.borrow().location == local {
-                return upvalue;
+    fn capture_upvalue(&mut self, local: *mut Value) -> Gc<ObjUpvalue> {
+        let index = self
+            .open_upvalues
+            .binary_search_by(|upvalue| upvalue.borrow().location.cmp(&local));
+        match index {
+            Ok(index) => self.open_upvalues[index].clone(),
+            Err(index) => {
+                let created_upvalue = ObjUpvalue::new(local);
+                self.open_upvalues.insert(index, created_upvalue.clone());
+                created_upvalue
             }
         }
-
-        let created_upvalue = ObjUpvalue::new(local);
-        if let Some(upvalue) = current_upvalue {
-            created_upvalue.borrow_mut().add_next(upvalue);
-        }
-        if let Some(upvalue) = previous_upvalue {
-            upvalue.borrow_mut().add_next(created_upvalue.clone());
-        } else {
-            self.open_upvalues = Some(created_upvalue.clone());
-        };
-        created_upvalue
     }
 
     fn close_upvalues(&mut self, last: *mut Value) {
-        while let Some(open_upvalue) = self.open_upvalues.clone() {
+        while let Some(open_upvalue) = self.open_upvalues.last() {
             if open_upvalue.borrow().location < last {
                 break;
             }
+            let open_upvalue = self.open_upvalues.pop().unwrap();
             let mut upvalue = open_upvalue.borrow_mut();
             upvalue.closed = unsafe { &*upvalue.location }.clone();
             upvalue.location = std::ptr::null_mut(); //rust *really* dislikes self pointers. cover this in writeup
-            self.open_upvalues = upvalue.next.clone();
         }
     }
 
-    fn define_method(&mut self, name: Gc<ObjString>) -> Result<(), InterpretError> {
+    /// The superclass of the class the running method was defined in. The compiler only emits
+    /// `GetSuper`/`SuperInvoke` inside methods of classes that have a superclass, but a corrupt
+    /// bytecode file can have them anywhere.
+    fn current_superclass(&mut self) -> Result<Gc<ObjClass>, InterpretError> {
+        let class = self.current_frame().closure.borrow().class.clone();
+        match class.and_then(|class| class.borrow().superclass.clone()) {
+            Some(superclass) => Ok(superclass),
+            None => self.runtime_error("No superclass to look up a method in.".to_string()),
+        }
+    }
+
+    fn define_method(&mut self, name: Gc<ObjString>, is_static: bool) -> Result<(), InterpretError> {
         let method = self.peek(0)?.clone();
         let class = self.peek(1)?.clone();
         if let Ok(class) = class.as_class() {
             if let Ok(method) = method.as_closure() {
-                class.borrow_mut().methods.insert(name, method);
+                method.borrow_mut().class = Some(class.clone());
+                if is_static {
+                    class.borrow_mut().static_methods.insert(name, method);
+                } else {
+                    class.borrow_mut().methods.insert(name, method);
+                }
             } else {
                 self.runtime_error(format!(
                     "Provided global name was not a string! this is a compiler error."
@@ -293,13 +1178,21 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
 
     pub fn push(&mut self, value: Value) -> Result<(), InterpretError> {
         if self.stack_index >= 255 {
-            self.runtime_error(format!("Stack overflow."))?;
+            self.stack_overflow()?;
         }
         self.stack[self.stack_index] = value;
         self.stack_index += 1;
         Ok(())
     }
 
+    /// Pushes a variable's value, failing if it was declared under --strict and never assigned.
+    fn push_initialized(&mut self, value: Value) -> Result<(), InterpretError> {
+        if value.is_uninitialized() {
+            return self.runtime_error("Can't read an uninitialized variable.".to_string());
+        }
+        self.push(value)
+    }
+
     pub fn pop(&mut self) -> Result<Value, InterpretError> {
         if self.stack_index == 0 {
             self.runtime_error(format!("Stack is empty, no value to pop."))?;
@@ -318,12 +1211,203 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
             self.runtime_error("Operands must be two numbers or two strings.".to_string())?;
         }
 
-        let new_value = concatenate_strings(a.unwrap(), b.unwrap());
+        let (a, b) = (a.unwrap(), b.unwrap());
+        if a.len() + b.len() > self.max_string_length {
+            return self.runtime_error("Concatenated string is too long.".to_string());
+        }
+        let new_value = concatenate_strings(a, b);
         self.pop()?;
         self.pop()?;
         self.push(new_value)
     }
 
+    /// `"ab" * 3`: the string on the stack repeated by the count above it.
+    fn repeat_string(&mut self) -> Result<(), InterpretError> {
+        let count = self.pop()?.as_number().unwrap();
+        let string = self.pop()?.as_string().unwrap();
+        if count < 0.0 || count.fract() != 0.0 {
+            return self.runtime_error("Repeat count must be a non-negative integer.".to_string());
+        }
+        let string = string.borrow();
+        if count * string.as_str().len() as f64 > self.max_string_length as f64 {
+            return self.runtime_error("Repeated string is too long.".to_string());
+        }
+        let repeated = string.as_str().repeat(count as usize);
+        self.push(Value::string(ObjString::new(repeated)))
+    }
+
+    fn array_index(
+        &mut self,
+        array: &Value,
+        index: &Value,
+    ) -> Result<(Gc<ObjArray>, usize), InterpretError> {
+        let array = match array.as_array() {
+            Ok(array) => array,
+            Err(_) => {
+                return self.runtime_error("Only arrays and maps can be indexed.".to_string())
+            }
+        };
+        let index = match index.as_number() {
+            Ok(index) if index.fract() == 0.0 => index,
+            _ => return self.runtime_error("Array index must be an integer.".to_string()),
+        };
+        if index < 0.0 || index >= array.borrow().elements.len() as f64 {
+            return self.runtime_error("Array index out of bounds.".to_string());
+        }
+        Ok((array, index as usize))
+    }
+
+    fn map_key(&mut self, key: Value) -> Result<MapKey, InterpretError> {
+        match MapKey::new(key) {
+            Ok(key) => Ok(key),
+            Err(_) => self.runtime_error(
+                "Map key must be nil, a boolean, a number, or a string.".to_string(),
+            ),
+        }
+    }
+
+    fn print_value(&mut self, value: Value, end: &str) -> Result<(), InterpretError> {
+        let text = self.stringify(&value)? + end;
+        match &mut self.output_budget {
+            None => {
+                self.out.write_all(text.as_bytes()).ok();
+            }
+            Some(budget) => {
+                if text.len() > *budget {
+                    *budget = 0;
+                    return self.runtime_error("Output limit exceeded.".to_string());
+                }
+                *budget -= text.len();
+                self.out.write_all(text.as_bytes()).ok();
+            }
+        }
+        Ok(())
+    }
+
+    /// The text `print` shows for `value`. Unlike its `Display`, this calls the `toString` method
+    /// of instances whose class has one, including inside arrays and maps, and shows an array or
+    /// map that contains itself as `[...]` or `{...}`. An instance printed again while its own
+    /// `toString` runs gets the default text, so `toString` can't recurse forever.
+    fn stringify(&mut self, value: &Value) -> Result<String, InterpretError> {
+        let in_progress = self.stringifying.contains(value);
+        match value.value_type() {
+            ValueType::Array if in_progress => Ok("[...]".to_string()),
+            ValueType::Map if in_progress => Ok("{...}".to_string()),
+            ValueType::Array => {
+                let elements = value.as_array().unwrap().borrow().elements.clone();
+                self.stringifying.push(value.clone());
+                let mut text = "[".to_string();
+                let mut result = Ok(());
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        text.push_str(", ");
+                    }
+                    match self.stringify(element) {
+                        Ok(element) => text.push_str(&element),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                self.stringifying.pop();
+                result.map(|_| text + "]")
+            }
+            ValueType::Map => {
+                let entries: Vec<_> = value
+                    .as_map()
+                    .unwrap()
+                    .borrow()
+                    .entries
+                    .iter()
+                    .map(|(key, value)| (key.value().clone(), value.clone()))
+                    .collect();
+                self.stringifying.push(value.clone());
+                let mut text = "{".to_string();
+                let mut result = Ok(());
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        text.push_str(", ");
+                    }
+                    match self.stringify(value) {
+                        Ok(value) => text += &format!("{}: {}", key, value),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                self.stringifying.pop();
+                result.map(|_| text + "}")
+            }
+            ValueType::Instance if !in_progress => {
+                let class = value.as_instance().unwrap().borrow().class.clone();
+                let method = match ObjClass::find_method(&class, &self.to_string_string) {
+                    Some(method) => method,
+                    None => return Ok(format!("{}", value)),
+                };
+                self.stringifying.push(value.clone());
+                let result = self.call_method(value.clone(), method, &[]);
+                self.stringifying.pop();
+                let result = result?;
+                match result.to_string() {
+                    Some(string) => Ok(string),
+                    None => self.runtime_error(format!(
+                        "'toString' must return a string, got {}.",
+                        result.value_type().name()
+                    )),
+                }
+            }
+            _ => Ok(format!("{}", value)),
+        }
+    }
+
+    fn compare_strings(&mut self, compare: fn(&str, &str) -> bool) -> Result<(), InterpretError> {
+        let b = self.pop()?.as_string().unwrap();
+        let a = self.pop()?.as_string().unwrap();
+        let result = compare(a.borrow().as_str(), b.borrow().as_str());
+        self.push(Value::bool_(result))
+    }
+
+    //does `op` if its operands are integers and so is the answer, and says whether it did;
+    //otherwise the operands are left for the float arithmetic, which also reports bad types
+    #[cfg(feature = "integers")]
+    fn integer_operation(&mut self, op: OpCode) -> Result<bool, InterpretError> {
+        let (operands, result) = match op {
+            OpCode::Negate => match self.peek(0)?.as_int() {
+                Ok(a) => (1, a.checked_neg().map(Value::int)),
+                Err(_) => return Ok(false),
+            },
+            _ => match (self.peek(1)?.as_int(), self.peek(0)?.as_int()) {
+                (Ok(a), Ok(b)) => (2, crate::value::integer_arithmetic(op, a, b)),
+                _ => return Ok(false),
+            },
+        };
+        match result {
+            Some(result) => {
+                for _ in 0..operands {
+                    self.pop()?;
+                }
+                self.push(result)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    //writes the stack and the instruction about to run to the err writer
+    fn trace_instruction(&mut self) {
+        write!(self.err, "          ").ok();
+        for value in self.stack[..self.stack_index].iter() {
+            write!(self.err, "[ {} ]", value).ok();
+        }
+        writeln!(self.err).ok();
+        let ip = self.current_frame().ip;
+        self.current_chunk()
+            .borrow()
+            .disassemble_instruction_to(&self.global_slots, self.err, ip);
+    }
+
     fn read_operation(&mut self) -> Option<OpCode> {
         let result = self
             .current_chunk()
@@ -357,23 +1441,100 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
     }
 
     pub fn run(&mut self) -> Result<(), InterpretError> {
+        self.run_to_depth(0)
+    }
+
+    /// Executes until the frame stack unwinds back to `depth` frames (0 for the top-level script).
+    /// A runtime error in a `try` block run by one of those frames goes to its `catch`.
+    fn run_to_depth(&mut self, depth: usize) -> Result<(), InterpretError> {
+        loop {
+            match self.run_instructions(depth) {
+                Err(InterpretError::Runtime)
+                    if self.thrown.is_some()
+                        && self.handlers.last().is_some_and(|handler| handler.frames > depth) =>
+                {
+                    self.unwind()?
+                }
+                result => return result,
+            }
+        }
+    }
+
+    //continues at the innermost `catch`, dropping the frames and stack slots above its `try`
+    //and pushing what was thrown
+    fn unwind(&mut self) -> Result<(), InterpretError> {
+        let handler = self.handlers.pop().unwrap();
+        let thrown = self.thrown.take().unwrap_or_else(Value::nil);
+        let last = &mut self.stack[handler.stack_index] as *mut _;
+        self.close_upvalues(last);
+        self.frames.truncate(handler.frames);
+        self.stack_index = handler.stack_index;
+        self.current_frame_mut().ip = handler.ip;
+        self.push(thrown)
+    }
+
+    fn run_instructions(&mut self, depth: usize) -> Result<(), InterpretError> {
         loop {
-            //print!("[");
-            //for index in 0..self.stack_index {
-            //    print!("{}, ", self.stack[index]);
-            //}
-            //println!("]");
+            if let Some(steps) = &mut self.step_budget {
+                if *steps == 0 {
+                    return self.runtime_error("Step budget exhausted.".to_string());
+                }
+                *steps -= 1;
+            }
+            if self.trace_execution {
+                self.trace_instruction();
+            }
             let read_op = self.read_operation();
             match read_op {
                 None => return Ok(()), //must return something if there is no code
                 Some(op) => match op {
+                    #[cfg(feature = "integers")]
+                    OpCode::Add
+                    | OpCode::Subtract
+                    | OpCode::Multiply
+                    | OpCode::Divide
+                    | OpCode::Greater
+                    | OpCode::Less
+                    | OpCode::GreaterEqual
+                    | OpCode::LessEqual
+                    | OpCode::Negate
+                        if self.integer_operation(op)? => {}
                     OpCode::Jump => {
                         let offset = self.read_u16();
                         self.current_frame_mut().ip += offset as usize;
                     }
                     OpCode::JumpIfFalse => {
                         let offset = self.read_u16();
-                        if self.peek(0)?.is_falsey() {
+                        let condition = self.peek(0)?.clone();
+                        if self.is_falsey(&condition)? {
+                            self.current_frame_mut().ip += offset as usize;
+                        }
+                    }
+                    OpCode::JumpIfTrue => {
+                        let offset = self.read_u16();
+                        let condition = self.peek(0)?.clone();
+                        if !self.is_falsey(&condition)? {
+                            self.current_frame_mut().ip += offset as usize;
+                        }
+                    }
+                    //only `nil` itself, not `false` or anything with a `bool` method
+                    OpCode::JumpIfNotNil => {
+                        let offset = self.read_u16();
+                        if !self.peek(0)?.is_nil() {
+                            self.current_frame_mut().ip += offset as usize;
+                        }
+                    }
+                    OpCode::PopJumpIfFalse => {
+                        let offset = self.read_u16();
+                        let condition = self.pop()?;
+                        if self.is_falsey(&condition)? {
+                            self.current_frame_mut().ip += offset as usize;
+                        }
+                    }
+                    OpCode::PopJumpIfTrue => {
+                        let offset = self.read_u16();
+                        let condition = self.pop()?;
+                        if !self.is_falsey(&condition)? {
                             self.current_frame_mut().ip += offset as usize;
                         }
                     }
@@ -386,6 +1547,11 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         let callee = self.peek(arg_count as usize)?.clone();
                         self.call_value(callee, arg_count as usize)?;
                     }
+                    OpCode::TailCall => {
+                        let arg_count = self.read_byte();
+                        let callee = self.peek(arg_count as usize)?.clone();
+                        self.tail_call_value(callee, arg_count as usize)?;
+                    }
                     OpCode::Invoke => {
                         let global = self.read_byte();
                         let string = self.current_chunk().borrow().constants[global as usize]
@@ -397,34 +1563,37 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                     }
                     OpCode::SuperInvoke => {
                         let name = self.read_string();
-                        let superclass = self.pop()?.as_class().unwrap();
                         let arg_count = self.read_byte() as usize;
+                        let superclass = self.current_superclass()?;
                         self.invoke_from_class(superclass, name, arg_count)?;
                     }
                     OpCode::Closure => {
-                        let index = self.read_byte();
-                        if let Ok(function) = self.current_chunk().borrow().constants
-                            [index as usize]
-                            .clone()
-                            .as_function()
+                        //the function and all of its upvalue operands are read under a single
+                        //borrow of the chunk rather than one per byte
+                        let chunk = self.current_chunk();
+                        let chunk = chunk.borrow();
+                        let ip = self.current_frame().ip;
+                        if let Ok(function) = chunk.constants[chunk.code[ip] as usize].as_function()
                         {
-                            let closure = ObjClosure::new(function.clone());
+                            let upvalue_count = function.borrow().upvalue_count;
+                            let operands = &chunk.code[ip + 1..ip + 1 + 2 * upvalue_count];
+                            self.current_frame_mut().ip += 1 + operands.len();
+                            let closure = ObjClosure::new(function);
+                            closure.borrow_mut().class =
+                                self.current_frame().closure.borrow().class.clone();
+                            closure.borrow_mut().upvalues.reserve(upvalue_count);
                             self.push(Value::closure(closure.clone().into()))?;
-                            for _i in 0..function.borrow().upvalue_count {
-                                let is_local = self.read_byte();
-                                let index = self.read_byte();
-                                if is_local != 0 {
-                                    let offset = self.current_frame_mut().stack_offset;
-                                    let upvalue =
-                                        &mut self.stack[offset + index as usize] as *mut _;
-                                    let upvalue = self.capture_upvalue(upvalue);
-                                    closure.borrow_mut().upvalues.push(upvalue);
+                            let offset = self.current_frame().stack_offset;
+                            let parent_closure = self.current_frame().closure.clone();
+                            for operand in operands.chunks_exact(2) {
+                                let (is_local, index) = (operand[0], operand[1] as usize);
+                                let upvalue = if is_local != 0 {
+                                    let local = &mut self.stack[offset + index] as *mut _;
+                                    self.capture_upvalue(local)
                                 } else {
-                                    let parent_closure = self.current_frame().closure.clone();
-                                    let upvalue =
-                                        parent_closure.borrow().upvalues[index as usize].clone();
-                                    closure.borrow_mut().upvalues.push(upvalue);
-                                }
+                                    parent_closure.borrow().upvalues[index].clone()
+                                };
+                                closure.borrow_mut().upvalues.push(upvalue);
                             }
                         } else {
                             self.runtime_error(format!(
@@ -442,17 +1611,23 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                             Ok(superclass) => superclass,
                             Err(_) => {return self.runtime_error("Superclass must be a class.".to_string());}
                         };
-                        let subclass = self.peek(0)?.clone().as_class().unwrap();
-                        let subclass_methods = &mut subclass.borrow_mut().methods;
-
-                        for (name, method) in &superclass.borrow().methods {
-                            subclass_methods.insert(name.clone(), method.clone());
-                        }
+                        //methods aren't copied down; lookups walk the superclass chain
+                        //always a class in compiled code, but not necessarily in a corrupt file
+                        let subclass = match self.peek(0)?.clone().as_class() {
+                            Ok(subclass) => subclass,
+                            Err(_) => return self.runtime_error("Only a class can inherit.".to_string()),
+                        };
+                        subclass.borrow_mut().superclass = Some(superclass);
+                        self.pop()?;
                         self.pop()?;
                     }
                     OpCode::Method => {
                         let name = self.read_string();
-                        self.define_method(name)?;
+                        self.define_method(name, false)?;
+                    }
+                    OpCode::StaticMethod => {
+                        let name = self.read_string();
+                        self.define_method(name, true)?;
                     }
                     OpCode::CloseUpvalue => {
                         let last = &mut self.stack[self.stack_index - 1] as *mut _;
@@ -460,29 +1635,89 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         self.pop()?;
                     }
                     OpCode::Return => {
-                        let result = self.pop()?;
+                        if self.stack_index == 0 {
+                            self.runtime_error("Stack is empty, no value to pop.".to_string())?;
+                        }
                         let stack_index = self.current_frame().stack_offset;
                         let last = &mut self.stack[stack_index] as *mut _;
                         self.close_upvalues(last);
                         self.frames.pop();
+                        //a `return` inside a `try` leaves it without reaching its `PopHandler`
+                        while self
+                            .handlers
+                            .last()
+                            .is_some_and(|handler| handler.frames > self.frames.len())
+                        {
+                            self.handlers.pop();
+                        }
                         if self.frames.len() == 0 {
+                            self.returned = self.pop()?;
                             self.pop()?;
                             return Ok(());
                         }
-                        self.stack_index = stack_index;
-                        self.push(result)?;
+                        //move the result into the callee's slot rather than popping and pushing
+                        //it; this happens after closing upvalues so they still see the locals
+                        self.stack.swap(stack_index, self.stack_index - 1);
+                        self.stack_index = stack_index + 1;
+                        if self.frames.len() == depth {
+                            return Ok(());
+                        }
                     }
                     OpCode::Print => {
                         let result = self.pop()?;
-                        writeln!(self.out, "{}", result).ok();
+                        self.print_value(result, "\n")?;
+                    }
+                    OpCode::PrintSep => {
+                        let result = self.pop()?;
+                        self.print_value(result, " ")?;
                     }
                     OpCode::Pop => {
                         self.pop()?;
                     }
+                    OpCode::Dup => {
+                        let value = self.peek(0)?.clone();
+                        self.push(value)?;
+                    }
+                    OpCode::Swap => {
+                        let top = self.pop()?;
+                        let below = self.pop()?;
+                        self.push(top)?;
+                        self.push(below)?;
+                    }
+                    OpCode::Over => {
+                        let value = self.peek(1)?.clone();
+                        self.push(value)?;
+                    }
+                    OpCode::Nop => (),
+                    OpCode::PushHandler => {
+                        let offset = self.read_u16();
+                        self.handlers.push(Handler {
+                            frames: self.frames.len(),
+                            stack_index: self.stack_index,
+                            ip: self.current_frame().ip + offset as usize,
+                        });
+                    }
+                    OpCode::PopHandler => {
+                        self.handlers.pop();
+                    }
+                    OpCode::Throw => {
+                        let value = self.pop()?;
+                        self.throw(value)?;
+                    }
+                    OpCode::PopN => {
+                        let count = self.read_byte() as usize;
+                        if count > self.stack_index {
+                            self.runtime_error("Stack is empty, no value to pop.".to_string())?;
+                        }
+                        for slot in &mut self.stack[self.stack_index - count..self.stack_index] {
+                            *slot = Value::number(0.0);
+                        }
+                        self.stack_index -= count;
+                    }
                     OpCode::GetLocal => {
                         let slot = self.read_byte();
                         let offset = self.current_frame_mut().stack_offset;
-                        self.push(self.stack[slot as usize + offset].clone())?;
+                        self.push_initialized(self.stack[slot as usize + offset].clone())?;
                     }
                     OpCode::SetLocal => {
                         let slot = self.read_byte();
@@ -490,33 +1725,36 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         self.stack[slot as usize + offset] = self.peek(0)?.clone();
                     }
                     OpCode::GetGlobal => {
-                        let name = self.read_string();
-                        match self.globals.get(&name) {
+                        let slot = self.read_u16();
+                        match self.get_global(slot) {
                             None => {
+                                let name = self.global_slots.name(slot).to_string();
                                 self.runtime_error(format!("Undefined variable '{}'.", name))?;
                             }
-                            Some(value) => self.push(value.clone())?,
+                            Some(value) => self.push_initialized(value.clone())?,
                         };
                     }
                     OpCode::DefineGlobal => {
-                        let name = self.read_string();
+                        let slot = self.read_u16();
                         let value = self.peek(0)?.clone();
-                        self.globals.insert(name, value);
+                        if self.get_global(slot).is_none() {
+                            self.global_order.push(slot);
+                        }
+                        self.set_global(slot, value);
                         self.pop()?;
                     }
                     OpCode::SetGlobal => {
-                        let name = self.read_string();
+                        let slot = self.read_u16();
                         let value = self.peek(0)?.clone();
-                        match self.globals.entry(name.clone()) {
-                            std::collections::hash_map::Entry::Occupied(mut occupied) => {
-                                *occupied.get_mut() = value;
-                            }
-                            std::collections::hash_map::Entry::Vacant(_) => {
-                                self.runtime_error(format!("Undefined variable '{}'", name))?;
-                            }
+                        if self.get_global(slot).is_some() {
+                            self.set_global(slot, value);
+                        } else {
+                            let name = self.global_slots.name(slot).to_string();
+                            self.runtime_error(format!("Undefined variable '{}'", name))?;
                         }
                     }
                     OpCode::Nil => self.push(Value::nil())?,
+                    OpCode::Uninitialized => self.push(Value::uninitialized())?,
                     OpCode::False => self.push(Value::bool_(false))?,
                     OpCode::True => self.push(Value::bool_(true))?,
                     OpCode::Negate => {
@@ -525,9 +1763,15 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         })?;
                         self.push(Value::number(-value))?;
                     }
+                    OpCode::UnaryPlus => {
+                        if !self.peek(0)?.is_number() {
+                            return self.runtime_error("Operand must be a number.".to_string());
+                        }
+                    }
                     OpCode::Not => {
                         let value = self.pop()?;
-                        self.push(Value::bool_(value.is_falsey()))?;
+                        let falsey = self.is_falsey(&value)?;
+                        self.push(Value::bool_(falsey))?;
                     }
                     OpCode::GetUpvalue => {
                         let slot = self.read_byte();
@@ -538,7 +1782,7 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         } else {
                             unsafe { &*slot.borrow().location }.clone()
                         };
-                        self.push(upvalue)?;
+                        self.push_initialized(upvalue)?;
                     }
                     OpCode::SetUpvalue => {
                         let slot = self.read_byte();
@@ -553,26 +1797,61 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                         }
                     }
                     OpCode::GetProperty => {
-                        let instance = self.peek(0)?.clone().as_instance();
-                        if let Ok(instance) = instance {
-                            let name = self.read_byte();
-                            if let Ok(name) = self.current_chunk().borrow().constants[name as usize]
-                                .clone()
-                                .as_string()
-                            {
-                                match instance.borrow().fields.get(&name) {
-                                    Some(value) => {
-                                        self.pop()?;
-                                        self.push(value.clone())?;
-                                    }
-                                    None => {
-                                        self.bind_method(instance.borrow().class.clone(), name)?;
-                                    }
+                        let receiver = self.peek(0)?.clone();
+                        if let Ok(instance) = receiver.as_instance() {
+                            //the operands and the cache are read under a single borrow of
+                            //the chunk, and a hit doesn't touch the name's hash
+                            let chunk = self.current_chunk();
+                            let ip = self.current_frame().ip;
+                            let (name, cache, hit) = {
+                                let chunk = chunk.borrow();
+                                let name = chunk.constants[chunk.code[ip] as usize]
+                                    .as_string()
+                                    .expect("property names are string constants");
+                                let cache =
+                                    ((chunk.code[ip + 1] as usize) << 8) | chunk.code[ip + 2] as usize;
+                                let hit =
+                                    chunk.property_caches[cache].lookup(&instance.borrow(), &name);
+                                (name, cache, hit)
+                            };
+                            self.current_frame_mut().ip += 3;
+                            match hit {
+                                Some(CacheHit::Field(value)) => {
+                                    self.pop()?;
+                                    self.push(value)?;
+                                }
+                                Some(CacheHit::Method(method)) => self.bind_closure(method)?,
+                                None => {
+                                    self.get_instance_property(receiver, instance, name, cache)?
+                                }
+                            }
+                        } else if let Ok(class) = receiver.as_class() {
+                            let name = self.read_string();
+                            self.read_u16();
+                            let method = ObjClass::find_static_method(&class, &name);
+                            match method {
+                                Some(method) => self.bind_closure(method)?,
+                                None => {
+                                    return self.runtime_error(
+                                        "Only instances have properties.".to_string(),
+                                    );
                                 }
                             }
                         } else {
-                            return self
-                                .runtime_error("Only instances have properties.".to_string());
+                            let name = self.read_string();
+                            self.read_u16();
+                            match self.builtin_method(&receiver, &name) {
+                                Some(method) => {
+                                    let native = ObjNative::bound(method, receiver);
+                                    self.pop()?;
+                                    self.push(Value::native(native))?;
+                                }
+                                None => {
+                                    return self.runtime_error(
+                                        "Only instances have properties.".to_string(),
+                                    );
+                                }
+                            }
                         }
                     }
                     OpCode::SetProperty => {
@@ -583,10 +1862,28 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                                 .clone()
                                 .as_string()
                             {
-                                instance
-                                    .borrow_mut()
-                                    .fields
-                                    .insert(name, self.peek(0)?.clone());
+                                if instance.borrow().frozen {
+                                    return self.runtime_error(
+                                        "Cannot modify a frozen instance.".to_string(),
+                                    );
+                                }
+                                let value = self.peek(0)?.clone();
+                                let receiver = self.peek(1)?.clone();
+                                let class = instance.borrow().class.clone();
+                                let fallback = if instance.borrow().fields.get(&name).is_some() {
+                                    None
+                                } else {
+                                    self.property_fallback(&receiver, &class, &self.set_field_string)
+                                };
+                                match fallback {
+                                    Some(set_field) => {
+                                        let args = [Value::string(name), value];
+                                        self.call_method(receiver, set_field, &args)?;
+                                    }
+                                    None => {
+                                        instance.borrow_mut().fields.insert(name, value);
+                                    }
+                                }
                             }
                             let value = self.pop()?;
                             self.pop()?;
@@ -603,16 +1900,140 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                             .clone()
                             .as_string()
                             .unwrap();
-                        let superclass = self.pop()?.as_class().unwrap();
+                        //`super.name` only looks at methods and getters from the superclass up;
+                        //fields live on the instance and are read through `this`
+                        let superclass = self.current_superclass()?;
                         self.bind_method(superclass, name)?;
                     }
+                    OpCode::GetIndex => {
+                        let index = self.pop()?;
+                        let target = self.pop()?;
+                        if let Ok(map) = target.as_map() {
+                            let key = self.map_key(index)?;
+                            let value = map.borrow().entries.get(&key).cloned();
+                            match value {
+                                Some(value) => self.push(value)?,
+                                None => {
+                                    return self.runtime_error(format!(
+                                        "Undefined key '{}'.",
+                                        key.value()
+                                    ));
+                                }
+                            }
+                        } else {
+                            let (array, index) = self.array_index(&target, &index)?;
+                            let element = array.borrow().elements[index].clone();
+                            self.push(element)?;
+                        }
+                    }
+                    OpCode::SetIndex => {
+                        let value = self.pop()?;
+                        let index = self.pop()?;
+                        let target = self.pop()?;
+                        if let Ok(map) = target.as_map() {
+                            let key = self.map_key(index)?;
+                            map.borrow_mut().entries.insert(key, value.clone());
+                        } else {
+                            let (array, index) = self.array_index(&target, &index)?;
+                            array.borrow_mut().elements[index] = value.clone();
+                        }
+                        self.push(value)?;
+                    }
+                    OpCode::Array => {
+                        let count = self.read_byte() as usize;
+                        let mut elements = Vec::with_capacity(count);
+                        for _ in 0..count {
+                            elements.push(self.pop()?);
+                        }
+                        elements.reverse();
+                        self.push(Value::array(ObjArray::new(elements)))?;
+                    }
+                    //a map is iterated over by its keys, as they were when the loop began
+                    OpCode::Iterable => {
+                        let value = self.peek(0)?.clone();
+                        if let Ok(map) = value.as_map() {
+                            let keys = map.borrow().keys();
+                            *self.peek(0)? = Value::array(ObjArray::new(keys));
+                        } else if value.as_array().is_err() {
+                            return self
+                                .runtime_error("Can only iterate over arrays and maps.".to_string());
+                        }
+                    }
+                    OpCode::Length => {
+                        let value = self.pop()?;
+                        match value.as_array() {
+                            Ok(array) => {
+                                let length = array.borrow().elements.len();
+                                self.push(Value::int(length as i64))?;
+                            }
+                            Err(_) => {
+                                return self
+                                    .runtime_error("Can only iterate over arrays.".to_string());
+                            }
+                        }
+                    }
                     OpCode::Equal => {
                         let b = self.pop()?;
                         let a = self.pop()?;
-                        self.push(Value::bool_(a == b))?;
+                        let equal = self.values_equal(a, b)?;
+                        self.push(Value::bool_(equal))?;
+                    }
+                    OpCode::Greater => {
+                        if self.peek(0)?.is_string() && self.peek(1)?.is_string() {
+                            self.compare_strings(|a, b| a > b)?;
+                        } else {
+                            binary_op!(self, bool_, >, ">")
+                        }
+                    }
+                    OpCode::GreaterEqual => {
+                        if self.peek(0)?.is_string() && self.peek(1)?.is_string() {
+                            self.compare_strings(|a, b| a >= b)?;
+                        } else {
+                            //`!(a < b)`, so NaN compares as true, the same as in constant folding
+                            binary_op!(self, ">=", |a, b| {
+                                Value::bool_(a.partial_cmp(&b) != Some(Ordering::Less))
+                            })
+                        }
+                    }
+                    OpCode::Less => {
+                        if self.peek(0)?.is_string() && self.peek(1)?.is_string() {
+                            self.compare_strings(|a, b| a < b)?;
+                        } else {
+                            binary_op!(self, bool_, <, "<")
+                        }
+                    }
+                    OpCode::LessEqual => {
+                        if self.peek(0)?.is_string() && self.peek(1)?.is_string() {
+                            self.compare_strings(|a, b| a <= b)?;
+                        } else {
+                            //`!(a > b)`, like `>=` above
+                            binary_op!(self, "<=", |a, b| {
+                                Value::bool_(a.partial_cmp(&b) != Some(Ordering::Greater))
+                            })
+                        }
+                    }
+                    OpCode::Xor => {
+                        let b = self.pop()?;
+                        let a = self.pop()?;
+                        let result = self.is_falsey(&a)? != self.is_falsey(&b)?;
+                        self.push(Value::bool_(result))?;
+                    }
+                    OpCode::IsInstance => {
+                        let class = match self.pop()?.as_class() {
+                            Ok(class) => class,
+                            Err(_) => {
+                                return self.runtime_error(
+                                    "Right operand of 'is' must be a class.".to_string(),
+                                );
+                            }
+                        };
+                        let value = self.pop()?;
+                        let is_instance = match value.as_instance() {
+                            Ok(instance) => ObjClass::is_subclass(&instance.borrow().class, &class),
+                            Err(_) => false,
+                        };
+                        self.push(Value::bool_(is_instance))?;
                     }
-                    OpCode::Greater => binary_op!(self, bool_, >),
-                    OpCode::Less => binary_op!(self, bool_, <),
                     OpCode::Add => {
                         if self.peek(0)?.is_string() && self.peek(1)?.is_string() {
                             self.concatenate_strings()?;
@@ -630,9 +2051,27 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
                             self.push(Value::number(a + b))?;
                         }
                     }
-                    OpCode::Subtract => binary_op!(self, number, -),
-                    OpCode::Multiply => binary_op!(self, number, *),
-                    OpCode::Divide => binary_op!(self, number, /),
+                    OpCode::Subtract => binary_op!(self, number, -, "-"),
+                    OpCode::Multiply => {
+                        if self.peek(1)?.is_string() && self.peek(0)?.is_number() {
+                            self.repeat_string()?;
+                        } else {
+                            binary_op!(self, number, *, "*")
+                        }
+                    }
+                    OpCode::Divide => {
+                        //a non-number operand is left for binary_op! to report
+                        let by_zero = self.peek(1)?.is_number()
+                            && self.peek(0)?.as_number().is_ok_and(|b| b == 0.0);
+                        if by_zero && !self.ieee_division {
+                            return self.runtime_error("Division by zero.".to_string());
+                        }
+                        binary_op!(self, number, /, "/")
+                    }
+                    OpCode::PushByte => {
+                        let byte = self.read_byte();
+                        self.push(Value::int(byte as i64))?;
+                    }
                     OpCode::Constant => {
                         let index = self.read_byte();
                         let value = self.current_chunk().borrow().constants[index as usize].clone();
@@ -644,8 +2083,61 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
     }
 
     pub fn interpret(&mut self, source: String) -> Result<(), InterpretError> {
-        let function = crate::compiler::compile(source.as_str(), self.err)?;
-        //function.borrow().chunk.borrow().disassemble();
+        self.script = None;
+        let function = self.compile(source.as_str())?;
+        self.run_function(function)
+    }
+
+    /// Compiles `source` for this VM without running it, for `run_function`. Compile errors
+    /// are written to the err writer.
+    pub fn compile(&mut self, source: &str) -> Result<Gc<ObjFunction>, InterpretError> {
+        let options = self.compiler_options;
+        crate::compiler::compile_with_options(source, options, &mut self.global_slots, self.err)
+    }
+
+    /// The global slots scripts for this VM are compiled with, to name them in a disassembly
+    /// or to serialize a compiled script.
+    pub fn global_slots(&self) -> &GlobalSlots {
+        &self.global_slots
+    }
+
+    /// Compiles `source` as a single expression, runs it and returns its value, for a host
+    /// that wants the result rather than what the code prints. Globals defined by earlier
+    /// scripts are visible to it.
+    pub fn eval_expression(&mut self, source: &str) -> Result<Value, InterpretError> {
+        let function =
+            crate::compiler::compile_expression(
+                source,
+                self.compiler_options,
+                &mut self.global_slots,
+                self.err,
+            )?;
+        self.run_function(function)?;
+        Ok(std::mem::replace(&mut self.returned, Value::nil()))
+    }
+
+    /// Loads a script written by `serialize_function` and runs it without touching the compiler.
+    pub fn interpret_bytecode(&mut self, bytes: &[u8]) -> Result<(), InterpretError> {
+        self.script = None;
+        match crate::chunk::serialize::deserialize_function(bytes, &mut self.global_slots) {
+            Ok(function) => self.run_function(function),
+            Err(e) => {
+                writeln!(self.err, "Could not load bytecode: {}.", e).ok();
+                Err(InterpretError::Compile)
+            }
+        }
+    }
+
+    /// The script that last ran, or started to, as it was compiled; `None` before the first one
+    /// and when the last one given to `interpret` or `interpret_bytecode` couldn't be loaded.
+    pub fn last_script(&self) -> Option<Gc<ObjFunction>> {
+        self.script.clone()
+    }
+
+    /// Runs a script already compiled by `compile`, so a host can compile once and run it many
+    /// times. Globals defined by earlier runs are still visible.
+    pub fn run_function(&mut self, function: Gc<ObjFunction>) -> Result<(), InterpretError> {
+        self.script = Some(function.clone());
         self.push(Value::function(function.clone().into()))?;
         let closure = ObjClosure::new(function);
         self.pop()?;
@@ -654,3 +2146,63 @@ impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr>
         self.run()
     }
 }
+
+impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> VM<'a, StdOut, StdErr> {
+    //runs `run`, which may call into Lox code, handing back the message of a runtime error it
+    //raises instead of reporting it
+    fn catch_error<T>(
+        &mut self,
+        run: impl FnOnce(&mut Self) -> Result<T, InterpretError>,
+    ) -> Result<Result<T, String>, NativeError> {
+        let stack_index = self.stack_index;
+        let depth = self.frames.len();
+        let outer = self.caught_error.replace(None);
+        //the error goes back to the native before any `try` outside it
+        let outer_handlers = std::mem::take(&mut self.handlers);
+        let result = run(self);
+        self.handlers = outer_handlers;
+        let caught = std::mem::replace(&mut self.caught_error, outer);
+        match result {
+            Ok(value) => Ok(Ok(value)),
+            Err(InterpretError::Exit(code)) => Err(NativeError::Exit(code)),
+            Err(_) => {
+                //drop whatever the failed call left on the stack, closing over its locals first
+                let last = &mut self.stack[stack_index] as *mut _;
+                self.close_upvalues(last);
+                self.frames.truncate(depth);
+                self.stack_index = stack_index;
+                Ok(Err(caught.flatten().unwrap_or_default()))
+            }
+        }
+    }
+}
+
+impl<'a, StdOut: std::io::Write, StdErr: std::io::Write> Host for VM<'a, StdOut, StdErr> {
+    fn try_call(
+        &mut self,
+        callee: Value,
+        args: &[Value],
+    ) -> Result<Result<Value, String>, NativeError> {
+        self.catch_error(|vm| vm.call_function(callee, args))
+    }
+
+    fn call(&mut self, callee: Value, args: &[Value]) -> Result<Value, NativeError> {
+        self.try_call(callee, args)?.map_err(NativeError::Runtime)
+    }
+
+    fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
+    fn stringify(&mut self, value: &Value) -> Result<String, NativeError> {
+        self.catch_error(|vm| vm.stringify(value))?
+            .map_err(NativeError::Runtime)
+    }
+
+    fn global_names(&self) -> Vec<String> {
+        self.global_order
+            .iter()
+            .map(|slot| self.global_slots.name(*slot).to_string())
+            .collect()
+    }
+}